@@ -0,0 +1,140 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gameharmony::domain::storage::Storage;
+use gameharmony::infrastructure::{FileSystemStore, SteamApp};
+use gameharmony::services::matching::{AppIndex, MatchingConfig, MatchingService};
+use gameharmony::services::text_utils::TitleNormalizer;
+use regex::Regex;
+use std::path::Path;
+use std::sync::Arc;
+
+const SAMPLE_NAMES: &str = include_str!("data/app_names.txt");
+
+/// The bundled sample of Steam app names, with made-up appids
+fn sample_apps() -> Vec<SteamApp> {
+    SAMPLE_NAMES
+        .lines()
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, name)| SteamApp {
+            appid: i as u64 + 10,
+            name: name.to_string(),
+        })
+        .collect()
+}
+
+/// A store in `dir`, with the directories the pipeline would have created
+fn temp_store(dir: &Path) -> FileSystemStore {
+    let (data_dir, cache_dir) = (dir.join("data"), dir.join("cache"));
+    std::fs::create_dir_all(&data_dir).unwrap();
+    std::fs::create_dir_all(&cache_dir).unwrap();
+    FileSystemStore::new(data_dir, cache_dir)
+}
+
+/// Titles as scraped from outlets, spelled differently than on Steam
+const QUERIES: [&str; 8] = [
+    "The Witcher III: Wild Hunt",
+    "Baldur’s Gate III",
+    "Half Life 2",
+    "NieR: Automata",
+    "God of War: Ragnarok",
+    "Left Four Dead 2",
+    "Hollow Knight: Silksong",
+    "Not A Game On Steam",
+];
+
+fn bench_normalize(c: &mut Criterion) {
+    let apps = sample_apps();
+    c.bench_function("normalize sample", |b| {
+        b.iter(|| {
+            for app in &apps {
+                black_box(TitleNormalizer::normalize(&app.name));
+            }
+        })
+    });
+}
+
+const ROMAN_NUMERALS: [&str; 25] = [
+    "xxv", "xxiv", "xxiii", "xxii", "xxi", "xx", "xix", "xviii", "xvii", "xvi", "xv", "xiv",
+    "xiii", "xii", "xi", "x", "ix", "viii", "vii", "vi", "v", "iv", "iii", "ii", "i",
+];
+
+/// Patterns `normalize` compiled on every call before they became statics:
+/// year suffixes, possessives, punctuation, numbers and 25 Roman numerals
+fn per_call_patterns() -> Vec<String> {
+    let mut patterns: Vec<String> = [
+        r"\s*(?:\(?\d{4}\)?)\b",
+        r"(?:'\s*s|\s+s)\b",
+        r"[^\w\s-]",
+        r"(\d+)",
+        r"[^\w\s]",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect();
+    patterns.extend(
+        ROMAN_NUMERALS
+            .iter()
+            .map(|numeral| format!(r"(?i)\b{}\b", numeral)),
+    );
+    patterns
+}
+
+/// Normalizing with the regexes compiled once against compiling them per
+/// title, the cost of index construction before the statics
+fn bench_regex_compilation(c: &mut Criterion) {
+    let apps = sample_apps();
+    let patterns = per_call_patterns();
+    let mut group = c.benchmark_group("normalize regexes");
+    // Compiling takes over a second per pass over the sample
+    group.sample_size(10);
+    group.bench_function("compiled once", |b| {
+        b.iter(|| {
+            for app in &apps {
+                black_box(TitleNormalizer::normalize(&app.name));
+            }
+        })
+    });
+    group.bench_function("compiled per call", |b| {
+        b.iter(|| {
+            for app in &apps {
+                for pattern in &patterns {
+                    black_box(Regex::new(pattern).unwrap());
+                }
+                black_box(TitleNormalizer::normalize(&app.name));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_build_index(c: &mut Criterion) {
+    let apps = sample_apps();
+    let dir = tempfile::tempdir().unwrap();
+    let store = temp_store(dir.path());
+    let config = MatchingConfig::default();
+    c.bench_function("build_index", |b| {
+        b.iter(|| AppIndex::build_index(black_box(apps.clone()), &config, &store).unwrap())
+    });
+}
+
+fn bench_match(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let store: Arc<dyn Storage> = Arc::new(temp_store(dir.path()));
+    let matching = MatchingService::new(sample_apps(), store, MatchingConfig::default()).unwrap();
+
+    c.bench_function("match single title", |b| {
+        b.iter(|| matching.match_all(black_box(&QUERIES[..1])))
+    });
+    c.bench_function("match_all queries", |b| {
+        b.iter(|| matching.match_all(black_box(&QUERIES)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_normalize,
+    bench_regex_compilation,
+    bench_build_index,
+    bench_match
+);
+criterion_main!(benches);
@@ -1,31 +1,712 @@
 use crate::config::cli::Args;
-use crate::error::Result;
+use crate::domain::{Game, CONFLICT_FIELDS};
+use crate::error::{GameError, Result};
+use crate::services::enrichment::RAWG_PROVIDER;
+use crate::services::matching::GameWithSteamId;
+use crate::services::scoring::ScoringStrategy;
+use crate::services::text_utils::TitleNormalizer;
 use clap::Parser;
-use reqwest::Client;
-use serde::Deserialize;
+use regex::Regex;
+use reqwest::{Client, Proxy};
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tracing::info;
 
-pub(crate) mod cli;
+pub mod cli;
+
+const DEFAULT_CASING_RULES: &str = include_str!("../../casing_rules.json");
+const DEFAULT_NORMALIZATION_RULES: &str = include_str!("../../normalization_rules.json");
+const DEFAULT_DECK_TOKENS: &str = include_str!("../../deck_tokens.json");
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Website {
+    /// Page to scrape, or the path of the file for local lists
     pub url: String,
+    #[serde(default)]
     pub name_selector: String,
+    #[serde(default)]
     pub rank_selector: String,
+    /// Overrides the regex the scraper finds the rank with, its first capture
+    /// group being the rank
+    #[serde(default)]
+    pub rank_regex: Option<String>,
+    /// Overrides the regex the scraper finds the name with, its first capture
+    /// group being the name
+    #[serde(default)]
+    pub name_regex: Option<String>,
+    /// Selector of the reviewer's paragraph about each entry, the first match
+    /// after the entry's name being taken
+    #[serde(default)]
+    pub blurb_selector: Option<String>,
     pub scraper_type: String,
+    /// Name of the outlet
     pub display_name: String,
+    /// Which of the outlet's lists this is, e.g. "Top 100" or "Best of 2024",
+    /// for outlets with several lists
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Genre or audience the list ranks, e.g. "indie" or "strategy". Games
+    /// are also scored by the lists of each genre alone.
+    #[serde(default)]
+    pub genre: Option<String>,
     pub pattern: String,
+    /// The list is an unordered showcase, so its entries only count as an
+    /// appearance and not as a position
+    #[serde(default)]
+    pub unranked: bool,
+    /// Credit line shown wherever the rankings of this outlet are republished
+    #[serde(default)]
+    pub attribution: Option<String>,
+    /// Terms under which the list may be reused
+    #[serde(default)]
+    pub license_note: Option<String>,
+    /// How to reach the further pages of lists split across several pages
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+    /// How the pages of the site are fetched
+    #[serde(default)]
+    pub fetcher: FetchBackend,
+    /// Capture the `wayback` fetcher reads, e.g. "20240101", the latest if
+    /// unset
+    #[serde(default)]
+    pub wayback_timestamp: Option<String>,
+    /// Where the byline and dates of the article are, tried before the
+    /// common meta tags
+    #[serde(default)]
+    pub article_meta: ArticleMeta,
+    /// Scrape the site even where its robots.txt disallows it
+    #[serde(default)]
+    pub ignore_robots: bool,
+    /// Seconds to wait between requests to the site, overriding the
+    /// Crawl-delay of its robots.txt
+    #[serde(default)]
+    pub crawl_delay: Option<f64>,
+    /// User agent for this site, instead of the default or rotated one
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Proxy for this site, instead of `--proxy`
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchBackend {
+    /// Plain GET requests
+    #[default]
+    Http,
+    /// A headless browser, for lists rendered with JavaScript
+    Browser,
+    /// The page's capture in the Wayback Machine
+    Wayback,
+}
+
+/// Selectors of the author and dates of a list article, for sites whose meta
+/// tags are missing or wrong. Matched elements are read from their `content`
+/// or `datetime` attribute, else from their text.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArticleMeta {
+    #[serde(default)]
+    pub author: Vec<String>,
+    #[serde(default)]
+    pub published: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+/// Lists split across pages are followed either through a next-page link or
+/// by filling page numbers into a URL template
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pagination {
+    /// Selector of the link to the next page
+    #[serde(default)]
+    pub next_selector: Option<String>,
+    /// URL of the further pages, `{page}` being replaced by the page number
+    #[serde(default)]
+    pub url_template: Option<String>,
+    /// Number the template starts at, the page at `url` being the one before
+    #[serde(default = "Pagination::default_first_page")]
+    pub first_page: usize,
+    /// Pages read at most, including the first one
+    #[serde(default = "Pagination::default_max_pages")]
+    pub max_pages: usize,
+}
+
+impl Pagination {
+    fn default_first_page() -> usize {
+        2
+    }
+
+    fn default_max_pages() -> usize {
+        10
+    }
+}
+
+/// Scraper type of lists read from a local JSON or CSV file instead of a website
+pub const LOCAL_SCRAPER_TYPE: &str = "local";
+
+/// Scraper type of lists built from the votes in a Reddit thread or wiki page
+pub const REDDIT_SCRAPER_TYPE: &str = "reddit";
+
+impl Website {
+    pub fn is_local(&self) -> bool {
+        self.scraper_type == LOCAL_SCRAPER_TYPE
+    }
+
+    /// Name the list is ranked under, the outlet and its category if it has
+    /// one, e.g. "IGN — Top 100"
+    pub fn source_name(&self) -> String {
+        match &self.category {
+            Some(category) => format!("{} — {}", self.display_name, category),
+            None => self.display_name.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ScraperConfig {
     pub websites: Vec<Website>,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub editions: EditionConfig,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    #[serde(default)]
+    pub enrichment: EnrichmentConfig,
+    /// Games dropped after merging, e.g. delisted ones. Matched on the
+    /// normalized title, so variants are caught as well.
+    #[serde(default)]
+    pub exclude_titles: Vec<String>,
+    /// Games kept in the manifest regardless of `--top` and the thresholds
+    #[serde(default)]
+    pub pin_titles: Vec<String>,
+}
+
+impl ScraperConfig {
+    /// Reads the config and refuses configs that fail `lint`
+    pub fn load(path: &Path) -> Result<Self> {
+        let config: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+        let issues = config.lint();
+        if !issues.is_empty() {
+            return Err(GameError::Other(format!(
+                "Invalid scraper config {:?}:\n  {}",
+                path,
+                issues.join("\n  ")
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Website a scraped list came from: the one with its URL, else the first
+    /// whose pattern it contains
+    pub fn website_for(&self, source: &str) -> Option<&Website> {
+        self.websites
+            .iter()
+            .find(|w| w.url == source)
+            .or_else(|| self.websites.iter().find(|w| source.contains(&w.pattern)))
+    }
+
+    /// Finds website entries that would double-count or mix up rankings
+    pub fn lint(&self) -> Vec<String> {
+        let describe = |i: usize| {
+            let website = &self.websites[i];
+            format!(
+                "website #{} ({}, {})",
+                i + 1,
+                website.source_name(),
+                website.url
+            )
+        };
+
+        let mut issues = Vec::new();
+        for (i, website) in self.websites.iter().enumerate() {
+            if website.is_local() && !Path::new(&website.url).is_file() {
+                issues.push(format!("{} points at a missing file", describe(i)));
+            }
+            for (field, regex) in [
+                ("rank_regex", &website.rank_regex),
+                ("name_regex", &website.name_regex),
+            ] {
+                if let Some(Err(e)) = regex.as_deref().map(Regex::new) {
+                    issues.push(format!("{} has an invalid {}: {}", describe(i), field, e));
+                }
+            }
+            if website
+                .wayback_timestamp
+                .as_ref()
+                .is_some_and(|timestamp| !timestamp.chars().all(|c| c.is_ascii_digit()))
+            {
+                issues.push(format!(
+                    "{} has a wayback_timestamp that isn't of the form YYYYMMDDhhmmss",
+                    describe(i)
+                ));
+            }
+            let meta = &website.article_meta;
+            for (field, selectors) in [
+                ("author", &meta.author),
+                ("published", &meta.published),
+                ("modified", &meta.modified),
+            ] {
+                for selector in selectors {
+                    if Selector::parse(selector).is_err() {
+                        issues.push(format!(
+                            "{} has an invalid article_meta.{} selector {:?}",
+                            describe(i),
+                            field,
+                            selector
+                        ));
+                    }
+                }
+            }
+            if let Some(pagination) = &website.pagination {
+                if pagination.next_selector.is_some() == pagination.url_template.is_some() {
+                    issues.push(format!(
+                        "{} needs either a next_selector or a url_template for pagination",
+                        describe(i)
+                    ));
+                }
+                if let Some(selector) = &pagination.next_selector {
+                    if Selector::parse(selector).is_err() {
+                        issues.push(format!(
+                            "{} has an invalid next_selector {:?}",
+                            describe(i),
+                            selector
+                        ));
+                    }
+                }
+                if pagination
+                    .url_template
+                    .as_ref()
+                    .is_some_and(|template| !template.contains("{page}"))
+                {
+                    issues.push(format!(
+                        "{} has a url_template without {{page}}",
+                        describe(i)
+                    ));
+                }
+            }
+            for (j, other) in self.websites.iter().enumerate().take(i) {
+                if website.url == other.url {
+                    issues.push(format!(
+                        "{} duplicates the URL of {}",
+                        describe(i),
+                        describe(j)
+                    ));
+                } else if website.source_name() == other.source_name() {
+                    issues.push(format!(
+                        "{} reuses the display name and category of {} with a different URL",
+                        describe(i),
+                        describe(j)
+                    ));
+                }
+                if website.pattern == other.pattern {
+                    issues.push(format!(
+                        "{} has the same pattern {:?} as {}",
+                        describe(i),
+                        website.pattern,
+                        describe(j)
+                    ));
+                }
+            }
+        }
+        for field in self.enrichment.conflicts.keys() {
+            if !CONFLICT_FIELDS.contains(&field.as_str()) {
+                issues.push(format!(
+                    "enrichment.conflicts has the unknown field {:?}, expected one of {}",
+                    field,
+                    CONFLICT_FIELDS.join(", ")
+                ));
+            }
+        }
+        for field in self.output.fields.iter().flatten() {
+            if !Game::field_names().contains(&field.as_str()) {
+                issues.push(format!(
+                    "output.fields has the unknown field {:?}, expected one of {}",
+                    field,
+                    Game::field_names().join(", ")
+                ));
+            }
+        }
+        issues
+    }
+}
+
+/// Edition-aware merging, which merges "Persona 5 Royal" into "Persona 5" and
+/// keeps the edition name on the merged game
+#[derive(Debug, Clone, Deserialize)]
+pub struct EditionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Suffixes that mark an edition of a game, matched case-insensitively
+    #[serde(default = "EditionConfig::default_suffixes")]
+    pub suffixes: Vec<String>,
+    /// Titles whose editions are separate games and never merged, e.g.
+    /// "Resident Evil 4" whose remake is a game of its own
+    #[serde(default)]
+    pub keep_separate: Vec<String>,
+}
+
+impl EditionConfig {
+    fn default_suffixes() -> Vec<String> {
+        [
+            "Game of the Year Edition",
+            "GOTY Edition",
+            "Definitive Edition",
+            "Complete Edition",
+            "Deluxe Edition",
+            "Ultimate Edition",
+            "Enhanced Edition",
+            "Special Edition",
+            "Anniversary Edition",
+            "Director's Cut",
+            "Final Cut",
+            "Remastered",
+            "Remaster",
+            "Remake",
+            "Royal",
+            "Golden",
+            "Reload",
+            "HD",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+}
+
+impl Default for EditionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            suffixes: Self::default_suffixes(),
+            keep_separate: Vec::new(),
+        }
+    }
+}
+
+/// How the rankings are aggregated into harmony scores
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScoringConfig {
+    #[serde(default)]
+    pub strategy: ScoringStrategy,
+    /// Keep the scores of every strategy on the games, for comparing them
+    #[serde(default)]
+    pub compare_strategies: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutputConfig {
+    /// Game fields written to the manifest, all fields if unset
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EnrichmentConfig {
+    /// Providers queried during enrichment, all configured ones if unset
+    pub providers: Option<Vec<String>>,
+    /// How disagreeing providers are resolved, by game field
+    #[serde(default)]
+    pub conflicts: BTreeMap<String, ConflictPolicy>,
+}
+
+/// Which value a game keeps when enrichment providers disagree on a field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Steam's value, or the first provider's if Steam isn't involved
+    #[default]
+    PreferSteam,
+    /// The value of the provider whose data was fetched most recently
+    PreferNewest,
+    /// Steam's value, with every provider's value kept in the game's
+    /// `alternate_values`
+    KeepBoth,
+}
+
+/// Rules for formatting titles for display
+#[derive(Debug, Clone, Deserialize)]
+pub struct CasingRules {
+    /// Languages whose lowercase words are applied
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Words that are always written in upper case, e.g. "XCOM"
+    #[serde(default)]
+    pub always_upper: Vec<String>,
+    /// Words that are always written exactly as given, e.g. "NieR"
+    #[serde(default)]
+    pub always_exact: Vec<String>,
+    /// Articles and other small words per language, which stay lowercase
+    /// unless they start the title
+    #[serde(default)]
+    pub lowercase_words: HashMap<String, Vec<String>>,
+}
+
+impl Default for CasingRules {
+    fn default() -> Self {
+        serde_json::from_str(DEFAULT_CASING_RULES).expect("Bundled casing rules are valid")
+    }
+}
+
+impl CasingRules {
+    /// Loads the rules from `path`, or the bundled defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            info!("No casing rules at {:?}, using bundled defaults", path);
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn is_lowercase_word(&self, word: &str) -> bool {
+        self.languages
+            .iter()
+            .filter_map(|language| self.lowercase_words.get(language))
+            .flatten()
+            .any(|w| w.eq_ignore_ascii_case(word))
+    }
+
+    /// Returns the forced casing for `word`, if any rule matches it
+    pub fn forced_casing(&self, word: &str) -> Option<String> {
+        if let Some(exact) = self
+            .always_exact
+            .iter()
+            .find(|w| w.eq_ignore_ascii_case(word))
+        {
+            return Some(exact.clone());
+        }
+
+        self.always_upper
+            .iter()
+            .any(|w| w.eq_ignore_ascii_case(word))
+            .then(|| word.to_uppercase())
+    }
+}
+
+/// Rules for normalizing titles before they are merged and matched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizationRules {
+    /// Words dropped from normalized titles, e.g. "edition"
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+    /// Regex replacements applied in order to the lowercased title
+    #[serde(default)]
+    pub replacements: Vec<Replacement>,
+    /// Phrases of normalized words kept as they are, neither dropped as stop
+    /// words nor converted to numbers, e.g. "it takes two"
+    #[serde(default)]
+    pub protected_terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replacement {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+impl Default for NormalizationRules {
+    fn default() -> Self {
+        serde_json::from_str(DEFAULT_NORMALIZATION_RULES)
+            .expect("Bundled normalization rules are valid")
+    }
+}
+
+impl NormalizationRules {
+    /// Loads the rules from `path`, or the bundled defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            info!(
+                "No normalization rules at {:?}, using bundled defaults",
+                path
+            );
+            return Ok(Self::default());
+        }
+        let rules: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        for replacement in &rules.replacements {
+            Regex::new(&replacement.pattern).map_err(|e| {
+                GameError::Other(format!(
+                    "Invalid replacement pattern {:?} in {:?}: {}",
+                    replacement.pattern, path, e
+                ))
+            })?;
+        }
+        Ok(rules)
+    }
+}
+
+/// Human readable texts for the loc tokens of Steam Deck compatibility
+/// reports, per language
+#[derive(Debug, Clone)]
+pub struct DeckTokens {
+    language: String,
+    texts: HashMap<String, HashMap<String, String>>,
+}
+
+impl DeckTokens {
+    /// The bundled texts, preferring `language` and falling back to English
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            texts: serde_json::from_str(DEFAULT_DECK_TOKENS)
+                .expect("Bundled deck tokens are valid"),
+        }
+    }
+
+    /// Resolves a token like "#SteamDeckVerified_TestResult_InterfaceTextIsLegible".
+    /// Unknown tokens are turned into words, "Interface text is legible".
+    pub fn describe(&self, loc_token: &str) -> String {
+        [self.language.as_str(), "en"]
+            .iter()
+            .find_map(|language| self.texts.get(*language)?.get(loc_token))
+            .cloned()
+            .unwrap_or_else(|| Self::humanize(loc_token))
+    }
+
+    fn humanize(loc_token: &str) -> String {
+        let name = loc_token.rsplit('_').next().unwrap_or(loc_token);
+        let mut text = String::with_capacity(name.len() + 8);
+        for (i, c) in name.trim_start_matches('#').chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                text.push(' ');
+                text.extend(c.to_lowercase());
+            } else {
+                text.push(c);
+            }
+        }
+        text
+    }
+}
+
+impl Default for DeckTokens {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+/// Manual Steam match decisions keyed by scraped title, consulted before any
+/// automatic matching. `None` marks games that aren't on Steam.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatchOverrides(pub BTreeMap<String, Option<u64>>);
+
+impl MatchOverrides {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Looks up the decision for `title`, ignoring differences in normalization
+    pub fn get(&self, title: &str) -> Option<Option<u64>> {
+        if let Some(decision) = self.0.get(title) {
+            return Some(*decision);
+        }
+
+        let normalized = TitleNormalizer::normalize(title);
+        self.0
+            .iter()
+            .find(|(key, _)| TitleNormalizer::normalize(key) == normalized)
+            .map(|(_, decision)| *decision)
+    }
+
+    pub fn set(&mut self, title: &str, decision: Option<u64>) {
+        self.0.insert(title.to_string(), decision);
+    }
+
+    /// Replaces the Steam IDs of games with a manual decision
+    pub fn apply(&self, games: Vec<GameWithSteamId>) -> Vec<GameWithSteamId> {
+        games
+            .into_iter()
+            .map(|mut game| {
+                if let Some(decision) = self.get(&game.name) {
+                    game.steam_id = decision.map(|appid| appid.to_string());
+                }
+                game
+            })
+            .collect()
+    }
+}
+
+/// User agent every request is sent with, unless rotated or overridden
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+const BUNDLED_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.1 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:132.0) Gecko/20100101 Firefox/132.0",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36 Edg/130.0.0.0",
+];
+
+/// User agents handed out in turn, so scraping requests don't all look alike
+#[derive(Debug, Default)]
+pub struct UserAgentPool {
+    agents: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl UserAgentPool {
+    pub fn new(agents: Vec<String>) -> Self {
+        Self {
+            agents,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The bundled pool, or the user agents in `path`, one per line
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let agents = match path {
+            Some(path) => std::fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect(),
+            None => BUNDLED_USER_AGENTS
+                .iter()
+                .map(|ua| ua.to_string())
+                .collect(),
+        };
+        Ok(Self::new(agents))
+    }
+
+    /// The next user agent, `None` if the pool is empty and the client's
+    /// default applies
+    pub fn next(&self) -> Option<&str> {
+        if self.agents.is_empty() {
+            return None;
+        }
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.agents.len();
+        Some(&self.agents[i])
+    }
+}
+
+/// Builds the shared HTTP client, sending requests through `proxy` if given
+pub fn build_http_client(proxy: Option<&str>) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent(DEFAULT_USER_AGENT);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+    Ok(builder.build()?)
 }
 
 pub struct Config {
     pub args: Args,
     pub scraper_config: ScraperConfig,
+    pub casing_rules: CasingRules,
+    pub match_overrides: MatchOverrides,
     pub http_client: Client,
 }
 
@@ -35,21 +716,54 @@ impl Config {
 
         // Only load scraper config if we're doing the main scraping
         let scraper_config = if args.command.is_none() {
-            serde_json::from_str(&std::fs::read_to_string(&args.config_file)?)?
+            ScraperConfig::load(&args.config_file)?
         } else {
-            ScraperConfig { websites: vec![] }
+            ScraperConfig {
+                websites: vec![],
+                output: OutputConfig::default(),
+                editions: EditionConfig::default(),
+                scoring: ScoringConfig::default(),
+                enrichment: EnrichmentConfig::default(),
+                exclude_titles: vec![],
+                pin_titles: vec![],
+            }
         };
 
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .build()?;
+        let casing_rules = CasingRules::load(&args.casing_rules_file)?;
+        let match_overrides = MatchOverrides::load(&args.overrides_file)?;
+
+        let http_client = build_http_client(args.proxy.as_deref())?;
 
-        Ok(Self {
+        let config = Self {
             args,
             scraper_config,
+            casing_rules,
+            match_overrides,
             http_client,
-        })
+        };
+        let uses_rawg = config
+            .enrichment_providers()
+            .is_none_or(|providers| providers.iter().any(|p| p == RAWG_PROVIDER));
+        if config.args.command.is_none()
+            && uses_rawg
+            && !config.args.no_rawg
+            && config.args.rawg_api_key.is_none()
+        {
+            return Err(GameError::MissingConfig {
+                option: "RAWG API key",
+                hint: "set RAWG_API_KEY, or pass --no-rawg to skip RAWG enrichment",
+            });
+        }
+        Ok(config)
+    }
+
+    /// Enrichment providers selected by `--enrich`, or `enrichment.providers`
+    /// in the scraper config. All configured providers are queried if unset.
+    pub fn enrichment_providers(&self) -> Option<Vec<String>> {
+        self.args
+            .enrich
+            .clone()
+            .or_else(|| self.scraper_config.enrichment.providers.clone())
     }
 
     pub fn ensure_directories(&self) -> Result<()> {
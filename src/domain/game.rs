@@ -1,95 +1,464 @@
+use crate::config::DeckTokens;
+use crate::domain::numbers::{deserialize_rankings, Rank, Score};
+use crate::domain::serde_utils::serialize_sorted;
 use crate::infrastructure::{
-    ExtendedPlatforms, RawgGameDetailed, SteamDeckVerifiedResponse, StoreInfo,
+    BackloggdInfo, ExtendedPlatforms, GameFeatures, RawgGameDetailed, ReviewTrend,
+    SteamDeckVerifiedResponse, StoreInfo, TwitchViewers, YoutubeTrailer, DECK_STATUS_UNKNOWN,
 };
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use chrono::{DateTime, Local, TimeDelta};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+
+/// Fields that several enrichment providers supply, their disagreements are
+/// resolved by a `ConflictPolicy`
+pub const CONFLICT_FIELDS: [&str; 2] = ["release_date", "metacritic"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game {
     pub title: String,
-    pub rankings: HashMap<String, u64>,
+    /// Other titles the sources listed the game under, tried when a provider
+    /// finds nothing under `title`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternate_titles: Vec<String>,
+    /// URL friendly form of the title, for permalinks
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub slug: String,
+    /// Stays the same across runs, even when the display title changes
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub harmony_id: String,
+    #[serde(
+        serialize_with = "serialize_sorted",
+        deserialize_with = "deserialize_rankings"
+    )]
+    pub rankings: HashMap<String, Option<Rank>>,
+    /// Article and entry each ranking comes from, by source
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub ranking_links: HashMap<String, RankingLink>,
+    /// What the reviewers wrote about the game, by source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<Quote>,
     pub platforms: ExtendedPlatforms,
     pub stores: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub steam_id: Option<u64>,
+    /// Steam no longer sells the game, or not in this region, its store data
+    /// comes from RAWG instead
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub delisted_on_steam: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_score: Option<u64>,
     #[serde(default)]
     pub total_reviews: u64,
+    /// Steam's summary of the reviews, like "Mostly Positive"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_score_desc: Option<String>,
+    /// Whether the reviews of the last 30 days are above or below the overall
+    /// ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recent_review_trend: Option<ReviewTrend>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub price: Option<String>,
+    /// Prices in the configured additional regions, keyed by country code
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub regional_prices: HashMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub header_image: Option<String>,
+    /// Social preview card, set by `publish --cards`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<String>,
+    /// Plain text teaser of the description, capped in length
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailer_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailer_thumbnail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub achievements: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller_support: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub features: GameFeatures,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steam_categories: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metacritic: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub release_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_status: Option<ReleaseStatus>,
+    /// Title RAWG found the game under, if not `title`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rawg_query: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reddit_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metacritic_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protondb_url: Option<String>,
-    pub harmony_score: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steam_deck_details: Option<SteamDeckDetails>,
+    /// Popularity on Twitch, next to the critics' rankings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub twitch_viewers: Option<TwitchViewers>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backloggd: Option<BackloggdInfo>,
+    pub harmony_score: Score,
+    /// Score of this run alone, set when `harmony_score` is smoothed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_harmony_score: Option<Score>,
+    /// Harmony score from the rankings of the sources of each genre alone
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub genre_scores: BTreeMap<String, Score>,
+    /// How much the harmony score can be relied on, given the number of
+    /// sources listing the game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<ScoreConfidence>,
+    /// Score of every aggregation strategy, when comparing them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub strategy_scores: BTreeMap<String, Score>,
+    /// When each enrichment provider last delivered data for this game
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub last_enriched_at: HashMap<String, String>,
+    /// Values of every provider for fields whose conflicts are resolved with
+    /// `keep-both`, by field and provider
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub alternate_values: BTreeMap<String, BTreeMap<String, String>>,
+    /// Enrichment provider that supplied each field, set with `--with-provenance`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provenance: BTreeMap<String, String>,
+}
+
+/// Where in a list article a ranking was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankingLink {
+    pub url: String,
+    /// Id of the game's entry in the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
+}
+
+/// A reviewer's blurb about a game in a list article
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    pub source: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseStatus {
+    Released,
+    EarlyAccess,
+    /// Announced with a release date
+    ComingSoon,
+    /// Announced without a release date
+    Tba,
+}
+
+impl ReleaseStatus {
+    pub fn is_released(&self) -> bool {
+        matches!(self, ReleaseStatus::Released | ReleaseStatus::EarlyAccess)
+    }
+}
+
+/// Harmony score shrunk toward the mean of all games, the more so the fewer
+/// sources list the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreConfidence {
+    pub shrunk_score: Score,
+    /// Distance between the harmony score and the shrunk one
+    pub margin: Score,
+    /// Listed by too few sources for the score to be settled
+    pub provisional: bool,
+}
+
+/// The Steam Deck compatibility report of a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamDeckDetails {
+    pub category: u64,
+    pub notes: Vec<SteamDeckNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamDeckNote {
+    /// Steam's icon for the note, e.g. a check mark or a warning
+    pub display_type: u64,
+    pub text: String,
 }
 
 impl Game {
-    pub fn new(title: String, rankings: HashMap<String, u64>, harmony_score: u64) -> Self {
+    /// Names of the fields as written to the manifest, for validating
+    /// `--fields` and `output.fields`
+    pub fn field_names() -> &'static [&'static str] {
+        let mut names = FieldNames(&[]);
+        // Fails by design, the derived impl only hands over its field names
+        let _ = Game::deserialize(&mut names);
+        names.0
+    }
+
+    pub fn new(
+        title: String,
+        rankings: HashMap<String, Option<Rank>>,
+        harmony_score: Score,
+    ) -> Self {
         Self {
             title,
+            alternate_titles: Vec::new(),
+            slug: String::new(),
+            harmony_id: String::new(),
             rankings,
+            ranking_links: HashMap::new(),
+            quotes: Vec::new(),
             platforms: ExtendedPlatforms::default(),
             stores: Vec::new(),
             steam_id: None,
+            delisted_on_steam: false,
             user_score: None,
             total_reviews: 0,
+            review_score_desc: None,
+            recent_review_trend: None,
             price: None,
+            regional_prices: HashMap::new(),
             header_image: None,
+            card_image: None,
+            short_description: None,
+            summary: None,
+            screenshots: Vec::new(),
+            trailer_url: None,
+            trailer_thumbnail: None,
+            achievements: None,
+            controller_support: None,
+            features: GameFeatures::default(),
+            steam_categories: Vec::new(),
             metacritic: None,
             release_date: None,
+            release_status: None,
+            rawg_query: None,
             reddit_url: None,
             metacritic_url: None,
             protondb_url: None,
+            steam_deck_details: None,
+            twitch_viewers: None,
+            backloggd: None,
             harmony_score,
+            raw_harmony_score: None,
+            genre_scores: BTreeMap::new(),
+            confidence: None,
+            strategy_scores: BTreeMap::new(),
+            last_enriched_at: HashMap::new(),
+            alternate_values: BTreeMap::new(),
+            provenance: BTreeMap::new(),
+        }
+    }
+
+    /// Orders by harmony score, highest first, breaking ties by title and
+    /// Steam ID so equal scores keep their order between runs
+    pub fn cmp_by_score(&self, other: &Self) -> Ordering {
+        other
+            .harmony_score
+            .cmp(&self.harmony_score)
+            .then_with(|| self.title.cmp(&other.title))
+            .then_with(|| self.steam_id.cmp(&other.steam_id))
+    }
+
+    /// Whether the data of `provider` was fetched less than `max_age` ago
+    pub fn is_fresh(&self, provider: &str, max_age: Duration) -> bool {
+        let max_age = TimeDelta::from_std(max_age).unwrap_or(TimeDelta::max_value());
+        self.last_enriched_at
+            .get(provider)
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .is_some_and(|at| Local::now().signed_duration_since(at) < max_age)
+    }
+
+    /// Records when `provider` data was fetched. Data served from the cache
+    /// keeps the time its cache entry was written at, `cached_at`, or else
+    /// the timestamp of the previous enrichment, if there was one.
+    pub fn mark_enriched(
+        &mut self,
+        provider: &str,
+        previous: Option<&Game>,
+        cached_at: Option<SystemTime>,
+        refreshed: bool,
+    ) {
+        let fetched_at = if refreshed {
+            None
+        } else {
+            cached_at
+                .map(|at| DateTime::<Local>::from(at).to_rfc3339())
+                .or_else(|| previous.and_then(|p| p.last_enriched_at.get(provider).cloned()))
+        };
+
+        self.last_enriched_at.insert(
+            provider.to_string(),
+            fetched_at.unwrap_or_else(|| Local::now().to_rfc3339()),
+        );
+    }
+
+    /// Value of a field providers may disagree on, see `CONFLICT_FIELDS`
+    pub fn conflict_value(&self, field: &str) -> Option<String> {
+        match field {
+            "release_date" => self.release_date.clone(),
+            "metacritic" => self.metacritic.map(|score| score.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn set_conflict_value(&mut self, field: &str, value: Option<String>) {
+        match field {
+            "release_date" => self.release_date = value,
+            "metacritic" => self.metacritic = value.and_then(|score| score.parse().ok()),
+            _ => {}
         }
     }
 
-    pub fn with_steam_info(mut self, store_info: StoreInfo) -> Self {
+    /// Takes the store data of Steam, returning the fields it set
+    pub fn apply_steam_info(&mut self, store_info: StoreInfo) -> Vec<&'static str> {
         self.price = store_info.price;
+        self.regional_prices = store_info
+            .regional_prices
+            .into_iter()
+            .filter_map(|(region, price)| Some((region, price?)))
+            .collect();
+        self.delisted_on_steam = store_info.delisted;
+        self.release_status = store_info.release_status;
         self.platforms = store_info.platforms;
-        self.user_score = Some(store_info.user_score);
-        self.total_reviews = store_info.total_reviews;
+        self.user_score = store_info.user_score;
+        let has_total_reviews = store_info.total_reviews.is_some();
+        self.total_reviews = store_info.total_reviews.unwrap_or_default();
+        self.review_score_desc = store_info.review_score_desc;
+        self.recent_review_trend = store_info.recent_review_trend;
         self.header_image = store_info.header_image;
+        self.short_description = store_info.short_description;
+        self.screenshots = store_info.screenshots;
+        self.trailer_url = store_info.trailer_url;
+        self.achievements = store_info.achievements;
+        self.controller_support = store_info.controller_support;
+        self.features = store_info.features;
+        self.steam_categories = store_info.categories;
         self.metacritic = store_info.metacritic_score;
         self.metacritic_url = store_info.metacritic_url;
+        self.release_date = store_info.release_date;
         self.stores.push("Steam".to_string());
-        self
+
+        // Only the fields Steam had a value for are reported as set by it
+        let values = [
+            ("price", self.price.is_some()),
+            ("regional_prices", !self.regional_prices.is_empty()),
+            ("release_status", self.release_status.is_some()),
+            ("user_score", self.user_score.is_some()),
+            ("total_reviews", has_total_reviews),
+            ("review_score_desc", self.review_score_desc.is_some()),
+            ("recent_review_trend", self.recent_review_trend.is_some()),
+            ("header_image", self.header_image.is_some()),
+            ("short_description", self.short_description.is_some()),
+            ("screenshots", !self.screenshots.is_empty()),
+            ("trailer_url", self.trailer_url.is_some()),
+            ("achievements", self.achievements.is_some()),
+            ("controller_support", self.controller_support.is_some()),
+            ("features", self.features != GameFeatures::default()),
+            ("steam_categories", !self.steam_categories.is_empty()),
+            ("metacritic", self.metacritic.is_some()),
+            ("metacritic_url", self.metacritic_url.is_some()),
+            ("release_date", self.release_date.is_some()),
+        ];
+        let mut fields = vec!["delisted_on_steam", "platforms", "stores"];
+        fields.extend(
+            values
+                .into_iter()
+                .filter(|(_, set)| *set)
+                .map(|(field, _)| field),
+        );
+        fields
     }
 
-    pub fn with_steam_deck_info(
-        mut self,
+    pub fn apply_steam_deck_info(
+        &mut self,
         deck_status: SteamDeckVerifiedResponse,
-        steam_id: String,
-    ) -> Self {
-        if let Some(results) = deck_status.results {
-            if results.resolved_category > 0 {
-                self.platforms.steamdeck = "verified".to_string();
+        steam_id: u64,
+        tokens: &DeckTokens,
+    ) -> Vec<&'static str> {
+        match deck_status.results {
+            Some(results) if results.status() != DECK_STATUS_UNKNOWN => {
+                self.platforms.steamdeck = results.status().to_string();
                 self.protondb_url = Some(format!("https://www.protondb.com/app/{}", steam_id));
+                self.steam_deck_details = Some(SteamDeckDetails {
+                    category: results.resolved_category,
+                    notes: results
+                        .resolved_items
+                        .iter()
+                        .map(|item| SteamDeckNote {
+                            display_type: item.display_type,
+                            text: tokens.describe(&item.loc_token),
+                        })
+                        .collect(),
+                });
+                vec!["platforms", "protondb_url", "steam_deck_details"]
+            }
+            _ => {
+                self.platforms.steamdeck = DECK_STATUS_UNKNOWN.to_string();
+                vec!["platforms"]
             }
         }
-        self
     }
 
-    pub fn with_rawg_info(mut self, detailed: &RawgGameDetailed) -> Self {
-        if self.header_image.is_none() {
+    /// Replaces the Steam trailer, YouTube videos can be embedded anywhere
+    pub fn apply_youtube_trailer(&mut self, trailer: YoutubeTrailer) -> Vec<&'static str> {
+        self.trailer_url = Some(trailer.url);
+        self.trailer_thumbnail = trailer.thumbnail;
+        vec!["trailer_url", "trailer_thumbnail"]
+    }
+
+    /// Fills in what Steam left out from RAWG, returning the fields it set
+    pub fn apply_rawg_info(&mut self, detailed: &RawgGameDetailed) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if self.header_image.is_none() && detailed.background_image.is_some() {
             self.header_image = detailed.background_image.clone();
+            fields.push("header_image");
         }
-
-        if !self.platforms.switch {
-            self.platforms.switch = detailed
-                .platforms
+        if self.short_description.is_none() && detailed.description_raw.is_some() {
+            self.short_description = detailed.description_raw.clone();
+            fields.push("short_description");
+        }
+        if self.screenshots.is_empty() && !detailed.short_screenshots.is_empty() {
+            self.screenshots = detailed
+                .short_screenshots
                 .iter()
-                .any(|p| p.platform.name == "Nintendo Switch");
+                .map(|screenshot| screenshot.image.clone())
+                .collect();
+            fields.push("screenshots");
+        }
+
+        let on_platform = |name: &str| detailed.platforms.iter().any(|p| p.platform.name == name);
+        // Steam had no platforms to offer
+        if self.delisted_on_steam {
+            self.platforms.windows = on_platform("PC");
+            self.platforms.macos = on_platform("macOS");
+            self.platforms.linux = on_platform("Linux");
+            fields.push("platforms");
+        }
+        if !self.platforms.switch && on_platform("Nintendo Switch") {
+            self.platforms.switch = true;
+            fields.push("platforms");
         }
 
         if let Some(stores) = &detailed.stores {
@@ -100,22 +469,79 @@ impl Game {
                 }
             }
             updated_stores.sort();
-            self.stores = updated_stores;
+            if updated_stores != self.stores {
+                self.stores = updated_stores;
+                fields.push("stores");
+            }
         }
 
-        if self.metacritic.is_none() {
+        // Disagreements with earlier providers are resolved by the enrichment
+        if detailed.metacritic.is_some() {
             self.metacritic = detailed.metacritic;
+            fields.push("metacritic");
         }
-        if self.release_date.is_none() {
+        if detailed.released.is_some() {
             self.release_date = detailed.released.clone();
+            fields.push("release_date");
         }
-        if self.reddit_url.is_none() {
+        if self.release_status.is_none() {
+            self.release_status = Some(Self::rawg_release_status(detailed));
+            fields.push("release_status");
+        }
+        if self.reddit_url.is_none() && detailed.reddit_url.is_some() {
             self.reddit_url = detailed.reddit_url.clone();
+            fields.push("reddit_url");
         }
-        if self.metacritic_url.is_none() {
+        if self.metacritic_url.is_none() && detailed.metacritic_url.is_some() {
             self.metacritic_url = detailed.metacritic_url.clone();
+            fields.push("metacritic_url");
+        }
+        fields.dedup();
+        fields
+    }
+
+    fn rawg_release_status(detailed: &RawgGameDetailed) -> ReleaseStatus {
+        if detailed.tba {
+            return ReleaseStatus::Tba;
         }
+        // RAWG dates are YYYY-MM-DD, which compare in order as strings
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        match &detailed.released {
+            Some(released) if *released > today => ReleaseStatus::ComingSoon,
+            Some(_) => ReleaseStatus::Released,
+            None => ReleaseStatus::Tba,
+        }
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Deserializer that records the field names a derived `Deserialize` impl
+/// asks for, without reading any data
+struct FieldNames(&'static [&'static str]);
+
+impl<'de> Deserializer<'de> for &mut FieldNames {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only structs have field names"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = fields;
+        Err(de::Error::custom("field names recorded"))
+    }
 
-        self
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+        enum identifier ignored_any
     }
 }
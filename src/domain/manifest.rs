@@ -1,40 +1,115 @@
 use crate::domain::game::Game;
 use chrono::Local;
-use serde::{Deserialize, Serialize};
+use serde::ser::{Error, SerializeSeq, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct Manifest {
     pub total_games: usize,
     pub last_updated: String,
     pub games: Vec<Game>,
     pub metadata: ManifestMetadata,
+    /// Only these fields of every game are written to `projection`, chunks
+    /// and genre manifests, all of them if unset. The manifest itself is
+    /// always complete, so later runs and commands can read it.
+    #[serde(skip)]
+    pub selected_fields: Option<Vec<String>>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestMetadata {
-    pub sources: Vec<String>,
+    #[serde(deserialize_with = "deserialize_sources")]
+    pub sources: Vec<SourceInfo>,
     pub enrichment_used: EnrichmentInfo,
     pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<u64>,
+    /// Weight of the new scores when they were smoothed with the previous run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smoothing_alpha: Option<f64>,
+}
+
+/// A ranking list the manifest was built from, with its attribution and how
+/// current it is
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct SourceInfo {
+    pub name: String,
+    /// Outlet and list of sources named after both
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outlet: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license_note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scraped_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// Games scraped from the list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_count: Option<usize>,
+    /// Byline of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// Manifests written before sources carried attribution list them by name
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SourceEntry {
+    Name(String),
+    Info(Box<SourceInfo>),
+}
+
+fn deserialize_sources<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<SourceInfo>, D::Error> {
+    Ok(Vec::<SourceEntry>::deserialize(deserializer)?
+        .into_iter()
+        .map(|entry| match entry {
+            SourceEntry::Name(name) => SourceInfo {
+                name,
+                ..SourceInfo::default()
+            },
+            SourceEntry::Info(info) => *info,
+        })
+        .collect())
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct EnrichmentInfo {
     pub steam: bool,
     pub rawg: bool,
+    /// Providers queried during the run
+    #[serde(default)]
+    pub providers: Vec<String>,
 }
 
 impl Manifest {
     pub fn new(games: Vec<Game>) -> Self {
-        let sources: Vec<String> = games
+        let sources: Vec<SourceInfo> = games
             .iter()
             .flat_map(|game| game.rankings.keys().cloned())
-            .collect::<std::collections::HashSet<_>>()
+            .collect::<std::collections::BTreeSet<_>>()
             .into_iter()
+            .map(|name| SourceInfo {
+                name,
+                ..SourceInfo::default()
+            })
             .collect();
 
         let enrichment_used = EnrichmentInfo {
             steam: games.iter().any(|g| g.steam_id.is_some()),
             rawg: games.iter().any(|g| g.reddit_url.is_some()),
+            providers: Vec::new(),
         };
 
         Self {
@@ -45,7 +120,182 @@ impl Manifest {
                 sources,
                 enrichment_used,
                 version: env!("CARGO_PKG_VERSION").to_string(),
+                run_id: None,
+                smoothing_alpha: None,
             },
+            selected_fields: None,
+        }
+    }
+}
+
+impl Serialize for Manifest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with(None, serializer)
+    }
+}
+
+/// The manifest with only its selected game fields
+pub struct ManifestProjection<'a>(&'a Manifest);
+
+impl Serialize for ManifestProjection<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .serialize_with(self.0.selected_fields.as_deref(), serializer)
+    }
+}
+
+/// Entry point of a chunked manifest, listing the chunk files in rank order
+#[derive(Serialize)]
+pub struct ManifestIndex<'a> {
+    pub total_games: usize,
+    pub last_updated: &'a str,
+    pub metadata: &'a ManifestMetadata,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ChunkEntry {
+    pub file: String,
+    pub games: usize,
+}
+
+/// A slice of the games of a chunked manifest
+#[derive(Serialize)]
+pub struct ManifestChunk<'a> {
+    pub games: GamesSlice<'a>,
+}
+
+impl Manifest {
+    /// Splits the games into chunks of `chunk_size`, named after `file_name`
+    /// applied to the chunk number
+    pub fn chunks(
+        &self,
+        chunk_size: usize,
+        file_name: impl Fn(usize) -> String,
+    ) -> (ManifestIndex<'_>, Vec<(String, ManifestChunk<'_>)>) {
+        let chunk_size = chunk_size.max(1);
+        let chunks: Vec<(String, ManifestChunk)> = (0..self.games.len())
+            .step_by(chunk_size)
+            .enumerate()
+            .map(|(i, start)| {
+                let end = (start + chunk_size).min(self.games.len());
+                let games = self.games_slice(start..end);
+                (file_name(i + 1), ManifestChunk { games })
+            })
+            .collect();
+
+        let index = ManifestIndex {
+            total_games: self.total_games,
+            last_updated: &self.last_updated,
+            metadata: &self.metadata,
+            chunks: chunks
+                .iter()
+                .map(|(file, chunk)| ChunkEntry {
+                    file: file.clone(),
+                    games: chunk.games.games.len(),
+                })
+                .collect(),
+        };
+        (index, chunks)
+    }
+
+    /// The manifest as published, pruned down to the selected fields
+    pub fn projection(&self) -> ManifestProjection<'_> {
+        ManifestProjection(self)
+    }
+
+    fn serialize_with<S: Serializer>(
+        &self,
+        fields: Option<&[String]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Manifest", 4)?;
+        state.serialize_field("total_games", &self.total_games)?;
+        state.serialize_field("last_updated", &self.last_updated)?;
+        let games = GamesSlice {
+            games: &self.games,
+            fields,
+        };
+        state.serialize_field("games", &games)?;
+        state.serialize_field("metadata", &self.metadata)?;
+        state.end()
+    }
+
+    fn games_slice(&self, range: std::ops::Range<usize>) -> GamesSlice<'_> {
+        GamesSlice {
+            games: &self.games[range],
+            fields: self.selected_fields.as_deref(),
+        }
+    }
+}
+
+/// Games serialized with only the selected fields, all of them if unset
+pub struct GamesSlice<'a> {
+    games: &'a [Game],
+    fields: Option<&'a [String]>,
+}
+
+impl Serialize for GamesSlice<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.fields {
+            Some(fields) => SelectedFields {
+                games: self.games,
+                fields,
+            }
+            .serialize(serializer),
+            None => self.games.serialize(serializer),
+        }
+    }
+}
+
+/// Serializes games pruned down to the selected fields
+struct SelectedFields<'a> {
+    games: &'a [Game],
+    fields: &'a [String],
+}
+
+impl Serialize for SelectedFields<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.games.len()))?;
+        for game in self.games {
+            let mut value = serde_json::to_value(game).map_err(S::Error::custom)?;
+            if let Some(object) = value.as_object_mut() {
+                object.retain(|key, _| self.fields.contains(key));
+            }
+            seq.serialize_element(&value)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn game_keys(json: &serde_json::Value) -> Vec<String> {
+        let mut keys: Vec<String> = json["games"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn prunes_only_the_projection_to_the_selected_fields() {
+        let rankings = HashMap::from([("IGN".to_string(), Some(1))]);
+        let mut manifest = Manifest::new(vec![Game::new("Hades".to_string(), rankings, 90)]);
+        manifest.selected_fields = Some(vec!["title".to_string(), "harmony_score".to_string()]);
+
+        let full = serde_json::to_value(&manifest).unwrap();
+        let projection = serde_json::to_value(manifest.projection()).unwrap();
+        assert_eq!(game_keys(&projection), ["harmony_score", "title"]);
+        assert!(game_keys(&full).len() > 2);
+        for key in game_keys(&full) {
+            assert!(Game::field_names().contains(&key.as_str()), "{}", key);
         }
     }
 }
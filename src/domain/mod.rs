@@ -1,6 +1,10 @@
 mod game;
 mod manifest;
+pub mod numbers;
+mod run;
+pub mod serde_utils;
 pub mod storage;
 
-pub use game::Game;
-pub use manifest::Manifest;
+pub use game::{Game, Quote, RankingLink, ReleaseStatus, ScoreConfidence, CONFLICT_FIELDS};
+pub use manifest::{EnrichmentInfo, Manifest};
+pub use run::{AppIdMerge, FieldConflict, RunRecord, SourceDrift};
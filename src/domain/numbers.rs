@@ -0,0 +1,152 @@
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+
+/// Position of a game on a source's list, 1 being the top
+pub type Rank = u64;
+
+/// Harmony score and the scores derived from it
+pub type Score = u64;
+
+/// No outlet publishes longer lists, larger ranks are parsing mistakes
+pub const MAX_RANK: Rank = 1000;
+
+/// Parses a rank like `12`, `#12` or `12.`, rejecting anything outside
+/// `1..=MAX_RANK` instead of truncating it
+pub fn parse_rank(text: &str) -> Option<Rank> {
+    let text = text.trim().trim_start_matches('#').trim_end_matches('.');
+    text.parse::<Rank>()
+        .ok()
+        .filter(|rank| is_valid_rank(*rank))
+}
+
+/// Rank of the entry at `index` in a list without explicit ranks
+pub fn rank_from_index(index: usize) -> Option<Rank> {
+    index
+        .checked_add(1)
+        .and_then(|position| Rank::try_from(position).ok())
+        .filter(|rank| is_valid_rank(*rank))
+}
+
+pub fn is_valid_rank(rank: Rank) -> bool {
+    (1..=MAX_RANK).contains(&rank)
+}
+
+/// Converts a computed score, saturating at the bounds instead of wrapping
+pub fn score_from_f64(value: f64) -> Score {
+    if value.is_nan() || value <= 0.0 {
+        0
+    } else if value >= Score::MAX as f64 {
+        Score::MAX
+    } else {
+        value.round() as Score
+    }
+}
+
+/// Deserializes a rank, failing on values outside `1..=MAX_RANK`
+pub fn deserialize_rank<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rank, D::Error> {
+    let rank = Rank::deserialize(deserializer)?;
+    if !is_valid_rank(rank) {
+        return Err(serde::de::Error::custom(format!(
+            "rank {} is outside 1..={}",
+            rank, MAX_RANK
+        )));
+    }
+    Ok(rank)
+}
+
+/// Like `deserialize_rank`, for the rankings of a game by source
+pub fn deserialize_rankings<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Option<Rank>>, D::Error> {
+    let rankings = HashMap::<String, Option<Rank>>::deserialize(deserializer)?;
+    for (source, rank) in &rankings {
+        match rank {
+            Some(rank) if !is_valid_rank(*rank) => {
+                return Err(serde::de::Error::custom(format!(
+                    "rank {} of {} is outside 1..={}",
+                    rank, source, MAX_RANK
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(rankings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        #[serde(deserialize_with = "deserialize_rank")]
+        rank: Rank,
+        score: Score,
+        #[serde(deserialize_with = "deserialize_rankings")]
+        rankings: HashMap<String, Option<Rank>>,
+    }
+
+    fn entry(rank: &str, score: &str, ranking: &str) -> serde_json::Result<Entry> {
+        serde_json::from_str(&format!(
+            r#"{{"rank": {}, "score": {}, "rankings": {{"IGN": {}}}}}"#,
+            rank, score, ranking
+        ))
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let cases = [
+            ("0", "50", "1"),
+            ("1001", "50", "1"),
+            ("-1", "50", "1"),
+            ("1", "-50", "1"),
+            ("1", "50.5", "1"),
+            ("1", "50", "0"),
+            ("1", "50", "1001"),
+        ];
+        for (rank, score, ranking) in cases {
+            assert!(
+                entry(rank, score, ranking).is_err(),
+                "rank {}, score {}, ranking {}",
+                rank,
+                score,
+                ranking
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_valid_values() {
+        for (rank, score, ranking) in [("1", "0", "null"), ("1000", "9999", "1000")] {
+            let entry = entry(rank, score, ranking).unwrap();
+            let json = serde_json::to_string(&entry).unwrap();
+            assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), entry);
+        }
+    }
+
+    #[test]
+    fn parses_ranks() {
+        let cases = [
+            ("12", Some(12)),
+            ("#12", Some(12)),
+            (" 12. ", Some(12)),
+            ("0", None),
+            ("1001", None),
+            ("twelve", None),
+        ];
+        for (text, rank) in cases {
+            assert_eq!(parse_rank(text), rank, "{}", text);
+        }
+        assert_eq!(rank_from_index(0), Some(1));
+        assert_eq!(rank_from_index(MAX_RANK as usize), None);
+    }
+
+    #[test]
+    fn saturates_scores() {
+        assert_eq!(score_from_f64(f64::NAN), 0);
+        assert_eq!(score_from_f64(-3.0), 0);
+        assert_eq!(score_from_f64(41.6), 42);
+        assert_eq!(score_from_f64(f64::INFINITY), Score::MAX);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::{GameError, Result};
+use crate::services::identity::fnv1a;
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Interrupted,
+}
+
+/// A single execution of the pipeline, numbered in the order runs were started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: u64,
+    /// Timestamp and short hash, naming the directory of the run's artifacts
+    #[serde(default)]
+    pub key: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub status: RunStatus,
+    pub total_games: Option<usize>,
+    pub error: Option<String>,
+    /// Sources whose scrape fell sharply short of the previous one, the
+    /// previous games were used instead
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drifted: Vec<SourceDrift>,
+    /// Games matched to the same Steam app, merged into one entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_app_ids: Vec<AppIdMerge>,
+    /// Game fields enrichment providers disagreed on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub field_conflicts: Vec<FieldConflict>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppIdMerge {
+    pub steam_id: u64,
+    /// Title of the entry the others were merged into
+    pub kept: String,
+    pub merged: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldConflict {
+    pub title: String,
+    pub field: String,
+    /// Value of each provider
+    pub values: BTreeMap<String, String>,
+    /// Provider whose value the game kept
+    pub kept: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceDrift {
+    pub source: String,
+    pub previous_games: usize,
+    pub games: usize,
+    /// Whether the page text changed too, if not the site likely only changed
+    /// its markup
+    pub content_changed: bool,
+}
+
+impl RunRecord {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            key: Self::new_key(id),
+            started_at: Local::now().to_rfc3339(),
+            finished_at: None,
+            status: RunStatus::Running,
+            total_games: None,
+            error: None,
+            drifted: Vec::new(),
+            merged_app_ids: Vec::new(),
+            field_conflicts: Vec::new(),
+        }
+    }
+
+    /// Unique even for runs started in the same second, e.g. by two machines
+    /// sharing the data dir
+    fn new_key(id: u64) -> String {
+        let now = Utc::now();
+        let seed = format!(
+            "{}:{}:{}",
+            id,
+            std::process::id(),
+            now.timestamp_nanos_opt().unwrap_or_default()
+        );
+        let hash = fnv1a(seed.as_bytes());
+        format!("{}-{:06x}", now.format("%Y%m%dT%H%M%SZ"), hash & 0xff_ffff)
+    }
+
+    pub fn finish(&mut self, outcome: &Result<usize>) {
+        self.finished_at = Some(Local::now().to_rfc3339());
+        match outcome {
+            Ok(total_games) => {
+                self.status = RunStatus::Succeeded;
+                self.total_games = Some(*total_games);
+            }
+            Err(e) => {
+                self.status = match e {
+                    GameError::Interrupted(_) => RunStatus::Interrupted,
+                    _ => RunStatus::Failed,
+                };
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+}
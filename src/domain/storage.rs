@@ -1,26 +1,83 @@
-use super::{Game, Manifest};
+use super::numbers::Score;
+use super::{Game, Manifest, RunRecord};
 use crate::error::Result;
-use crate::infrastructure::{RawgGameDetailed, StoreInfo};
-use crate::services::matching::{GameWithSteamId, IndexedGames};
-use crate::services::merging::MergedGame;
+use crate::infrastructure::{
+    BackloggdInfo, CachedPage, RawgGameDetailed, RawgMiss, RawgUsage, RequestReport,
+    SteamAppListCache, SteamDeckVerifiedResponse, StoreInfo, TwitchHistory, YoutubeTrailer,
+};
+use crate::services::matching::{FilteredApp, GameWithSteamId, IndexedGames, NameFst};
+use crate::services::merging::{MergeReport, MergedGame};
 use crate::services::scraping::WebsiteGames;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
 
 pub trait Storage: Send + Sync {
     fn load_indexed_games(&self) -> Result<Option<IndexedGames>>;
     fn save_indexed_games(&self, index: &IndexedGames) -> Result<()>;
+    /// The title FST of index `version`, memory-mapped
+    fn load_name_fst(&self, version: u32) -> Result<Option<NameFst>>;
+    fn save_name_fst(&self, version: u32, fst: &[u8]) -> Result<()>;
+    fn load_steam_app_list(&self) -> Result<Option<SteamAppListCache>>;
+    fn save_steam_app_list(&self, list: &SteamAppListCache) -> Result<()>;
     fn load_website_games(&self, url: String) -> Result<Option<WebsiteGames>>;
     fn save_website_games(&self, games: &[WebsiteGames]) -> Result<()>;
+    /// The raw page at `url` as last scraped
+    fn load_page(&self, url: &str) -> Result<Option<CachedPage>>;
+    fn save_page(&self, url: &str, page: &CachedPage) -> Result<()>;
     fn load_merged_games(&self) -> Result<Option<Vec<MergedGame>>>;
     fn save_merged_games(&self, games: &[MergedGame]) -> Result<()>;
+    fn save_merge_report(&self, report: &MergeReport) -> Result<()>;
+    fn save_filtered_apps(&self, apps: &[FilteredApp]) -> Result<()>;
     fn load_matched_games(&self) -> Result<Option<Vec<GameWithSteamId>>>;
     fn save_matched_games(&self, games: &[GameWithSteamId]) -> Result<()>;
     fn load_app_info(&self, app_id: u64) -> Result<Option<StoreInfo>>;
     fn save_app_info(&self, app_id: u64, store_info: StoreInfo) -> Result<()>;
+    fn load_deck_info(&self, app_id: u64) -> Result<Option<SteamDeckVerifiedResponse>>;
+    fn save_deck_info(&self, app_id: u64, deck_info: &SteamDeckVerifiedResponse) -> Result<()>;
     fn load_rawg_info(&self, name: &str) -> Result<Option<RawgGameDetailed>>;
     fn save_rawg_info(&self, name: &str, rawg_info: RawgGameDetailed) -> Result<()>;
+    fn load_rawg_miss(&self, name: &str) -> Result<Option<RawgMiss>>;
+    fn save_rawg_miss(&self, name: &str, miss: &RawgMiss) -> Result<()>;
+    fn load_youtube_trailer(&self, name: &str) -> Result<Option<YoutubeTrailer>>;
+    fn save_youtube_trailer(&self, name: &str, trailer: &YoutubeTrailer) -> Result<()>;
+    fn load_twitch_history(&self, name: &str) -> Result<Option<TwitchHistory>>;
+    fn save_twitch_history(&self, name: &str, history: &TwitchHistory) -> Result<()>;
+    fn load_backloggd_info(&self, name: &str) -> Result<Option<BackloggdInfo>>;
+    fn save_backloggd_info(&self, name: &str, info: &BackloggdInfo) -> Result<()>;
+    /// When the entry `key` of the provider cache `subdir` was written
+    fn cached_at(&self, subdir: &str, key: &str) -> Result<Option<SystemTime>>;
+    fn load_rawg_usage(&self) -> Result<RawgUsage>;
+    fn save_rawg_usage(&self, usage: &RawgUsage) -> Result<()>;
+    fn save_request_report(&self, report: &RequestReport) -> Result<()>;
     fn load_enriched_games(&self) -> Result<Option<Vec<Game>>>;
     fn save_enriched_games(&self, games: &[Game]) -> Result<()>;
+    /// Appends a single enriched game to the progress of the current run
+    fn append_enriched_game(&self, game: &Game) -> Result<()>;
+    fn clear_enriched_progress(&self) -> Result<()>;
+    /// Games enriched by an interrupted run, which the next run resumes from
+    fn load_enriched_progress(&self) -> Result<Vec<Game>>;
+    /// Harmony scores of every game of the last run by normalized title,
+    /// including those the manifest left out
+    fn load_scores(&self) -> Result<Option<BTreeMap<String, Score>>>;
+    fn save_scores(&self, scores: &BTreeMap<String, Score>) -> Result<()>;
+    /// Harmony IDs handed out so far, by Steam app and by lowercased title
+    fn load_harmony_ids(&self) -> Result<Option<BTreeMap<String, String>>>;
+    fn save_harmony_ids(&self, ids: &BTreeMap<String, String>) -> Result<()>;
+    fn load_manifest(&self) -> Result<Option<Manifest>>;
+    /// Writes the complete manifest, and its projection if fields are selected
     fn save_manifest(&self, manifest: &Manifest) -> Result<()>;
+    /// Writes the games in files of `chunk_size` games plus an index of them,
+    /// for frontends that load the manifest lazily
+    fn save_manifest_chunks(&self, manifest: &Manifest, chunk_size: usize) -> Result<()>;
+    /// Writes the manifest of the games ranked by sources of `genre`
+    fn save_genre_manifest(&self, genre: &str, manifest: &Manifest) -> Result<()>;
+    /// Directs the reports and the manifest of the run with `key` to the run
+    /// directory, the stable paths get a copy of the latest
+    fn begin_run(&self, key: &str);
+    /// Removes the directory of the run with `key` and everything in it
+    fn remove_run(&self, key: &str) -> Result<()>;
+    fn load_runs(&self) -> Result<Vec<RunRecord>>;
+    fn save_runs(&self, runs: &[RunRecord]) -> Result<()>;
 }
 
 pub struct StorageKeys;
@@ -28,14 +85,35 @@ pub struct StorageKeys;
 impl StorageKeys {
     // Base directories
     pub const SOURCES_DIR: &'static str = "sources";
+    pub const PAGES_DIR: &'static str = "pages";
     pub const STEAM_APPS_DIR: &'static str = "steam_apps";
+    pub const STEAM_DECK_DIR: &'static str = "steam_deck";
     pub const RAWG_APPS_DIR: &'static str = "rawg_apps";
+    pub const RAWG_MISSES_DIR: &'static str = "rawg_misses";
+    pub const YOUTUBE_DIR: &'static str = "youtube";
+    pub const TWITCH_DIR: &'static str = "twitch";
+    pub const BACKLOGGD_DIR: &'static str = "backloggd";
     pub const ENHANCEMENTS_DIR: &'static str = "enhancements";
 
     pub const STEAM_APPS_INDEX: &'static str = "index_apps";
+    pub const STEAM_APP_LIST: &'static str = "app_list";
     pub const MERGED_GAMES: &'static str = "merged_games";
+    pub const MERGE_REPORT: &'static str = "merge_report";
+    pub const FILTERED_APPS: &'static str = "filtered_apps";
     pub const MERGED_GAMES_WITH_STEAM_ID: &'static str = "merged_with_steam_id";
 
     pub const ENRICHED_GAMES: &'static str = "enriched_games";
+    pub const ENRICHED_GAMES_PROGRESS: &'static str = "enriched_games.progress.jsonl";
     pub const MANIFEST: &'static str = "manifest";
+    pub const SCORES: &'static str = "harmony_scores";
+    pub const HARMONY_IDS: &'static str = "harmony_ids";
+    /// The manifest with only the selected game fields, next to the full one
+    pub const MANIFEST_PROJECTION: &'static str = "manifest_fields";
+    pub const MANIFEST_INDEX: &'static str = "manifest_index";
+    pub const MANIFEST_CHUNKS_DIR: &'static str = "manifest";
+    pub const GENRE_MANIFESTS_DIR: &'static str = "genres";
+    pub const RUNS: &'static str = "runs";
+    pub const RUN_DIRS: &'static str = "runs";
+    pub const RAWG_USAGE: &'static str = "rawg_usage";
+    pub const REQUEST_REPORT: &'static str = "request_report";
 }
@@ -1,4 +1,10 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 use thiserror::Error;
+use tracing::warn;
+
+/// Example errors logged per category in the end-of-run summary
+const SUMMARY_EXAMPLES: usize = 5;
 
 #[derive(Error, Debug)]
 pub enum GameError {
@@ -10,8 +16,111 @@ pub enum GameError {
     Serialization(#[from] serde_json::Error),
     #[error("Selector error: {0}")]
     Selector(String),
+    /// The upstream answered with something other than the expected data, e.g.
+    /// a rate-limit page, and the request may succeed when tried again
+    #[error("Retryable response from {url}: {reason}")]
+    Retryable { url: String, reason: String },
+    #[error("Scraping {source_name} ({url}) failed: {cause}")]
+    ScrapeFailed {
+        source_name: String,
+        url: String,
+        #[source]
+        cause: Box<GameError>,
+    },
+    #[error("{provider} enrichment of {title} failed: {cause}")]
+    EnrichmentFailed {
+        title: String,
+        provider: String,
+        #[source]
+        cause: Box<GameError>,
+    },
+    /// The upstream's best result is too unlike the queried title to be the
+    /// same game
+    #[error("Rejected low-confidence match {found:?} (similarity {similarity:.2})")]
+    LowConfidenceMatch { found: String, similarity: f64 },
+    /// A required option is missing, reported before the run starts
+    #[error("Missing {option}: {hint}")]
+    MissingConfig {
+        option: &'static str,
+        hint: &'static str,
+    },
+    /// The run was stopped by a signal, its progress is kept for the next run
+    #[error("Interrupted by {0}")]
+    Interrupted(&'static str),
     #[error("{0}")]
     Other(String),
 }
 
+impl GameError {
+    pub fn scrape_failed(source_name: &str, url: &str, cause: GameError) -> Self {
+        Self::ScrapeFailed {
+            source_name: source_name.to_string(),
+            url: url.to_string(),
+            cause: Box::new(cause),
+        }
+    }
+
+    pub fn enrichment_failed(title: &str, provider: &str, cause: GameError) -> Self {
+        Self::EnrichmentFailed {
+            title: title.to_string(),
+            provider: provider.to_string(),
+            cause: Box::new(cause),
+        }
+    }
+
+    /// Category the error is grouped under in the run summary, like
+    /// "enrichment/rawg/network"
+    pub fn category(&self) -> String {
+        match self {
+            GameError::Network(_) => "network".to_string(),
+            GameError::Io(_) => "io".to_string(),
+            GameError::Serialization(_) => "serialization".to_string(),
+            GameError::Selector(_) => "selector".to_string(),
+            GameError::Retryable { .. } => "retryable".to_string(),
+            GameError::ScrapeFailed { cause, .. } => format!("scrape/{}", cause.category()),
+            GameError::EnrichmentFailed {
+                provider, cause, ..
+            } => format!("enrichment/{}/{}", provider, cause.category()),
+            GameError::LowConfidenceMatch { .. } => "rejected".to_string(),
+            GameError::MissingConfig { .. } => "config".to_string(),
+            GameError::Interrupted(_) => "interrupted".to_string(),
+            GameError::Other(_) => "other".to_string(),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, GameError>;
+
+/// Failures that didn't abort the run, summarized by category once it ends
+#[derive(Debug, Default)]
+pub struct ErrorSummary {
+    errors: Mutex<Vec<GameError>>,
+}
+
+impl ErrorSummary {
+    pub fn record(&self, error: GameError) {
+        warn!("{}", error);
+        self.errors.lock().unwrap().push(error);
+    }
+
+    /// Logs the number of errors per category with a few examples each
+    pub fn log(&self) {
+        let errors = self.errors.lock().unwrap();
+        if errors.is_empty() {
+            return;
+        }
+
+        let mut by_category: BTreeMap<String, Vec<&GameError>> = BTreeMap::new();
+        for error in errors.iter() {
+            by_category.entry(error.category()).or_default().push(error);
+        }
+
+        warn!("{} errors during the run", errors.len());
+        for (category, errors) in by_category {
+            warn!("{}: {} errors", category, errors.len());
+            for error in errors.iter().take(SUMMARY_EXAMPLES) {
+                warn!("  {}", error);
+            }
+        }
+    }
+}
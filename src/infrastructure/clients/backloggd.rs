@@ -0,0 +1,109 @@
+use crate::domain::storage::{Storage, StorageKeys};
+use crate::error::Result;
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use once_cell::sync::Lazy;
+use reqwest::{Client, StatusCode};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::{error, info};
+
+static RATING: Lazy<Selector> = Lazy::new(|| Selector::parse("#score h1").unwrap());
+static STATS: Lazy<Selector> = Lazy::new(|| Selector::parse(".game-page-sidecard a").unwrap());
+
+/// Community ratings of a game on Backloggd, whose audience differs from the
+/// critic outlets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackloggdInfo {
+    pub url: String,
+    /// Average rating on Backloggd's 0-5 scale
+    pub average_rating: Option<f64>,
+    pub plays: Option<u64>,
+}
+
+pub struct BackloggdClient {
+    client: Client,
+    store: Arc<dyn Storage>,
+}
+
+impl BackloggdClient {
+    pub fn new(client: Client, store: Arc<dyn Storage>) -> Self {
+        Self { client, store }
+    }
+
+    /// When the cached Backloggd data of `title` was fetched
+    pub fn cached_at(&self, title: &str) -> Result<Option<SystemTime>> {
+        self.store.cached_at(StorageKeys::BACKLOGGD_DIR, title)
+    }
+
+    /// Reads the game page of `title`. Cached data is used unless `refresh`
+    /// is set.
+    pub async fn get_info(&self, title: &str, refresh: bool) -> Result<Option<BackloggdInfo>> {
+        if !refresh {
+            if let Some(cached) = self.store.load_backloggd_info(title)? {
+                info!("Using cached Backloggd data for {}", title);
+                return Ok(Some(cached));
+            }
+        }
+
+        let url = format!("https://backloggd.com/games/{}/", Self::slug(title));
+        REQUEST_STATS.record_request(&url);
+        let response = self.client.get(&url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            info!("No Backloggd page found for: {}", title);
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            error!("Backloggd error: Status {}", response.status());
+            return Ok(None);
+        }
+
+        let document = Html::parse_document(&response.text().await?);
+        let info = BackloggdInfo {
+            average_rating: document
+                .select(&RATING)
+                .next()
+                .and_then(|el| el.text().collect::<String>().trim().parse().ok()),
+            plays: Self::plays(&document),
+            url,
+        };
+        self.store.save_backloggd_info(title, &info)?;
+
+        Ok(Some(info))
+    }
+
+    /// The sidecard links to the plays of a game, labelled like "12K Plays"
+    fn plays(document: &Html) -> Option<u64> {
+        document
+            .select(&STATS)
+            .filter(|el| {
+                el.value()
+                    .attr("href")
+                    .is_some_and(|h| h.contains("/plays"))
+            })
+            .find_map(|el| parse_count(&el.text().collect::<String>()))
+    }
+
+    /// Backloggd slugs are the lowercase title with dashes between words
+    fn slug(title: &str) -> String {
+        title
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// Parses abbreviated counts like "842", "12K" or "1.2M"
+fn parse_count(text: &str) -> Option<u64> {
+    let count = text.split_whitespace().next()?.replace(',', "");
+    let (number, factor) = match count.chars().last()? {
+        'K' | 'k' => (&count[..count.len() - 1], 1_000.0),
+        'M' | 'm' => (&count[..count.len() - 1], 1_000_000.0),
+        _ => (count.as_str(), 1.0),
+    };
+    let value: f64 = number.parse().ok()?;
+    Some((value * factor).round() as u64)
+}
@@ -1,2 +1,8 @@
+pub mod backloggd;
 pub mod rawg;
+pub mod reddit;
+pub mod request_stats;
+pub mod robots;
 pub mod steam;
+pub mod twitch;
+pub mod youtube;
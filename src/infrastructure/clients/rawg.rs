@@ -1,9 +1,26 @@
-use crate::domain::storage::Storage;
-use crate::error::Result;
+use crate::domain::storage::{Storage, StorageKeys};
+use crate::error::{GameError, Result};
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use crate::services::text_utils::TitleNormalizer;
+use chrono::{DateTime, Local, TimeDelta};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::{Duration, SystemTime};
+use strsim::normalized_levenshtein;
+use tracing::{error, info, warn};
+
+/// Requests made for a title that isn't cached yet, search and details
+const REQUESTS_PER_LOOKUP: u64 = 2;
+
+/// Similarity of the normalized titles below which RAWG's top search result
+/// is taken for a different game
+pub const DEFAULT_RAWG_MIN_SIMILARITY: f64 = 0.8;
+
+/// How long a search that found no usable game is not repeated
+const RAWG_MISS_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 
 #[derive(Debug, Deserialize)]
 pub struct RawgSearchResponse {
@@ -20,6 +37,8 @@ pub struct RawgGameBasic {
     pub rating: Option<f64>,
     pub metacritic: Option<u64>,
     pub released: Option<String>,
+    #[serde(default)]
+    pub short_screenshots: Vec<RawgScreenshot>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -31,8 +50,21 @@ pub struct RawgGameDetailed {
     pub background_image: Option<String>,
     pub reddit_url: Option<String>,
     pub metacritic_url: Option<String>,
+    /// Announced without a release date
+    #[serde(default)]
+    pub tba: bool,
     pub platforms: Vec<RawgPlatform>,
     pub stores: Option<Vec<RawgStore>>,
+    #[serde(default)]
+    pub description_raw: Option<String>,
+    /// Only part of the search results, copied over when fetching details
+    #[serde(default)]
+    pub short_screenshots: Vec<RawgScreenshot>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RawgScreenshot {
+    pub image: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -55,10 +87,57 @@ pub struct StoreInfo {
     pub name: String,
 }
 
+/// A search that found no usable game, cached so it isn't repeated on every
+/// run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawgMiss {
+    pub checked_at: String,
+    /// Top search result, rejected as a different game, `None` if the search
+    /// had no results
+    pub found: Option<String>,
+}
+
+impl RawgMiss {
+    fn new(found: Option<String>) -> Self {
+        Self {
+            checked_at: Local::now().to_rfc3339(),
+            found,
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        let max_age = TimeDelta::from_std(RAWG_MISS_MAX_AGE).unwrap_or(TimeDelta::max_value());
+        DateTime::parse_from_rfc3339(&self.checked_at)
+            .is_ok_and(|at| Local::now().signed_duration_since(at) < max_age)
+    }
+}
+
+/// RAWG API requests made over time, to keep an eye on the key's quota
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RawgUsage {
+    pub total_requests: u64,
+    /// Requests per month, keyed like "2024-12"
+    pub monthly_requests: BTreeMap<String, u64>,
+    /// Requests per pipeline run, keyed by run ID
+    pub run_requests: BTreeMap<u64, u64>,
+}
+
+impl RawgUsage {
+    pub fn record(&mut self, run_id: u64, requests: u64) {
+        let month = chrono::Local::now().format("%Y-%m").to_string();
+        self.total_requests += requests;
+        *self.monthly_requests.entry(month).or_default() += requests;
+        *self.run_requests.entry(run_id).or_default() += requests;
+    }
+}
+
 pub struct RawgClient {
     client: Client,
     api_key: String,
     store: Arc<dyn Storage>,
+    budget: Option<u64>,
+    min_similarity: f64,
+    requests: AtomicU64,
 }
 
 impl RawgClient {
@@ -67,16 +146,113 @@ impl RawgClient {
             client,
             api_key,
             store,
+            budget: None,
+            min_similarity: DEFAULT_RAWG_MIN_SIMILARITY,
+            requests: AtomicU64::new(0),
         }
     }
 
-    pub async fn get_game_info(&self, title: &str) -> Result<Option<RawgGameDetailed>> {
-        if let Some(cached) = self.store.load_rawg_info(title)? {
-            info!("Using cached data for RAWG app {}", title);
-            return Ok(Some(cached));
+    /// Caps the number of API requests this client makes. Once the budget is
+    /// spent, only cached data is returned. Each title searched for is a
+    /// lookup of its own, including the fallback titles of a game.
+    pub fn with_budget(mut self, budget: Option<u64>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Rejects `found` as the RAWG game of a game known as `titles` if it
+    /// differs too much from all of them, RAWG's top search result can be an
+    /// unrelated game
+    fn check_match(&self, titles: &[String], found: &str) -> Result<()> {
+        let found_normalized = TitleNormalizer::normalize(found);
+        let similarity = titles
+            .iter()
+            .map(|title| {
+                normalized_levenshtein(&TitleNormalizer::normalize(title), &found_normalized)
+            })
+            .fold(0.0, f64::max);
+        if similarity < self.min_similarity {
+            return Err(GameError::LowConfidenceMatch {
+                found: found.to_string(),
+                similarity,
+            });
+        }
+        Ok(())
+    }
+
+    /// Number of API requests made so far
+    pub fn requests_made(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// When the cached result `get_game_info` finds first for `queries` was
+    /// fetched, `None` if one of them has to be searched
+    pub fn cached_at(&self, queries: &[String]) -> Result<Option<SystemTime>> {
+        for query in queries {
+            if let Some(at) = self.store.cached_at(StorageKeys::RAWG_APPS_DIR, query)? {
+                return Ok(Some(at));
+            }
+            if self
+                .store
+                .load_rawg_miss(query)?
+                .filter(RawgMiss::is_fresh)
+                .is_none()
+            {
+                return Ok(None);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Searches RAWG for `title` and fetches its details, if the game found is
+    /// similar enough to one of `titles`, the names of the game searched for.
+    /// Cached data is used unless `refresh` is set.
+    pub async fn get_game_info(
+        &self,
+        title: &str,
+        titles: &[String],
+        refresh: bool,
+    ) -> Result<Option<RawgGameDetailed>> {
+        if !refresh {
+            if let Some(cached) = self.store.load_rawg_info(title)? {
+                info!("Using cached data for RAWG app {}", title);
+                self.check_match(titles, &cached.name)?;
+                return Ok(Some(cached));
+            }
+            match self.store.load_rawg_miss(title)?.filter(RawgMiss::is_fresh) {
+                Some(RawgMiss {
+                    found: Some(found), ..
+                }) => {
+                    info!("Using cached RAWG rejection of {} for {}", found, title);
+                    // Searched again only if another name of the game now matches
+                    self.check_match(titles, &found)?;
+                }
+                Some(RawgMiss { found: None, .. }) => {
+                    info!("Using cached empty RAWG search for {}", title);
+                    return Ok(None);
+                }
+                None => {}
+            }
+        }
+
+        if let Some(budget) = self.budget {
+            if self.requests_made() + REQUESTS_PER_LOOKUP > budget {
+                warn!(
+                    "RAWG budget of {} requests spent, skipping {}",
+                    budget, title
+                );
+                return Ok(None);
+            }
         }
 
         let search_url = "https://api.rawg.io/api/games";
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        REQUEST_STATS.record_request(search_url);
         let response = self
             .client
             .get(search_url)
@@ -96,14 +272,22 @@ impl RawgClient {
         let search_data: RawgSearchResponse = response.json().await?;
         if search_data.results.is_empty() {
             info!("No RAWG data found for: {}", title);
+            self.store.save_rawg_miss(title, &RawgMiss::new(None))?;
             return Ok(None);
         }
 
         let basic_info = search_data.results.into_iter().next().unwrap();
         info!("Basic RAWG data found for {title}: {}", basic_info.name);
+        if let Err(e) = self.check_match(titles, &basic_info.name) {
+            self.store
+                .save_rawg_miss(title, &RawgMiss::new(Some(basic_info.name)))?;
+            return Err(e);
+        }
 
         // Get detailed information
         let detail_url = format!("https://api.rawg.io/api/games/{}", basic_info.id);
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        REQUEST_STATS.record_request(&detail_url);
         let detailed_response = self
             .client
             .get(&detail_url)
@@ -119,7 +303,8 @@ impl RawgClient {
             return Ok(None);
         }
 
-        let detailed_info: RawgGameDetailed = detailed_response.json().await?;
+        let mut detailed_info: RawgGameDetailed = detailed_response.json().await?;
+        detailed_info.short_screenshots = basic_info.short_screenshots;
 
         self.store.save_rawg_info(title, detailed_info.clone())?;
 
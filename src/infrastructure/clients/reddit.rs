@@ -0,0 +1,152 @@
+use crate::domain::numbers::{rank_from_index, Rank};
+use crate::error::{GameError, Result};
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use tracing::info;
+
+/// Markdown links, reduced to their text
+static LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Ordered or unordered markdown list items, like `1. Hades` or `* Hades`
+static LIST_ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(?:\d+[.)]|[*+-])\s+(.+)$").unwrap());
+
+#[derive(Debug, Deserialize)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingData {
+    children: Vec<Thing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Thing {
+    kind: String,
+    data: CommentData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CommentData {
+    body: Option<String>,
+    author: Option<String>,
+    score: i64,
+    stickied: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct WikiPage {
+    data: WikiData,
+}
+
+#[derive(Debug, Deserialize)]
+struct WikiData {
+    content_md: String,
+}
+
+/// Turns community votes into a ranked list through Reddit's public JSON API
+pub struct RedditClient {
+    client: Client,
+}
+
+impl RedditClient {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Reads a ranked list from a thread or a subreddit wiki page.
+    ///
+    /// In threads, every top-level comment names a game in its first line, and
+    /// comments are ranked by score. Wiki pages list the games in order.
+    pub async fn fetch_list(&self, url: &str) -> Result<Vec<(String, Rank)>> {
+        let json_url = format!("{}.json", url.trim_end_matches('/'));
+        REQUEST_STATS.record_request(&json_url);
+        let response = self
+            .client
+            .get(&json_url)
+            .query(&[("sort", "top"), ("limit", "500")])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let names = if url.contains("/wiki/") {
+            let page: WikiPage = response.json().await?;
+            Self::wiki_names(&page.data.content_md)
+        } else {
+            // A thread is the post followed by its comments
+            let listings: Vec<Listing> = response.json().await?;
+            let comments = listings
+                .into_iter()
+                .nth(1)
+                .ok_or_else(|| GameError::Other(format!("{} is not a Reddit thread", url)))?;
+            Self::comment_names(comments)
+        };
+        info!("Read {} games from Reddit {}", names.len(), url);
+
+        Ok(names
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, name)| Some((name, rank_from_index(i)?)))
+            .collect())
+    }
+
+    fn comment_names(comments: Listing) -> Vec<String> {
+        let mut comments: Vec<CommentData> = comments
+            .data
+            .children
+            .into_iter()
+            .filter(|thing| thing.kind == "t1")
+            .map(|thing| thing.data)
+            .filter(|comment| {
+                !comment.stickied && comment.author.as_deref() != Some("AutoModerator")
+            })
+            .collect();
+        comments.sort_by_key(|comment| std::cmp::Reverse(comment.score));
+
+        let mut names: Vec<String> = Vec::new();
+        for comment in comments {
+            let Some(name) = comment.body.as_deref().and_then(Self::first_line) else {
+                continue;
+            };
+            // Duplicate suggestions only count with their best score
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    fn wiki_names(markdown: &str) -> Vec<String> {
+        markdown
+            .lines()
+            .filter_map(|line| LIST_ITEM.captures(line))
+            .map(|caps| Self::plain_text(&caps[1]))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    fn first_line(body: &str) -> Option<String> {
+        let line = body.lines().map(str::trim).find(|line| !line.is_empty())?;
+        if matches!(line, "[deleted]" | "[removed]") {
+            return None;
+        }
+        let line = LIST_ITEM
+            .captures(line)
+            .map(|caps| caps[1].to_string())
+            .unwrap_or_else(|| line.to_string());
+        Some(Self::plain_text(&line)).filter(|name| !name.is_empty())
+    }
+
+    /// Drops markdown emphasis, headings and link targets
+    fn plain_text(markdown: &str) -> String {
+        LINK.replace_all(markdown, "$1")
+            .trim_start_matches('#')
+            .replace(['*', '_', '~'], "")
+            .trim()
+            .to_string()
+    }
+}
@@ -0,0 +1,102 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Requests and rate limiter waits of this process, shared by all clients
+pub static REQUEST_STATS: Lazy<RequestStats> = Lazy::new(RequestStats::default);
+
+#[derive(Default)]
+pub struct RequestStats {
+    inner: Mutex<RequestReport>,
+}
+
+/// Where a run spent its requests and waiting time
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RequestReport {
+    /// Requests per host
+    pub hosts: BTreeMap<String, u64>,
+    pub limiters: BTreeMap<String, LimiterStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LimiterStats {
+    /// Times the limiter held a request back
+    pub waits: u64,
+    #[serde(with = "millis")]
+    pub waited: Duration,
+    /// Fixed delay the limiter enforces between requests, if any
+    #[serde(with = "millis")]
+    pub interval: Duration,
+    pub concurrency: usize,
+}
+
+impl LimiterStats {
+    /// Shortest time the limiter needs for its waits, were nothing else slow.
+    /// Limiters without a fixed interval, like retry backoffs, don't bound it.
+    pub fn minimum_duration(&self) -> Duration {
+        let waits = u32::try_from(self.waits).unwrap_or(u32::MAX);
+        let concurrency = u32::try_from(self.concurrency.max(1)).unwrap_or(u32::MAX);
+        self.interval.saturating_mul(waits) / concurrency
+    }
+}
+
+impl RequestReport {
+    /// The run can't be faster than its slowest limiter
+    pub fn minimum_duration(&self) -> Duration {
+        self.limiters
+            .values()
+            .map(LimiterStats::minimum_duration)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl RequestStats {
+    pub fn record_request(&self, url: &str) {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| url.to_string());
+        *self.inner.lock().unwrap().hosts.entry(host).or_default() += 1;
+    }
+
+    /// Records time spent waiting on `limiter`, e.g. for a semaphore permit or
+    /// a retry backoff
+    pub fn record_wait(&self, limiter: &str, waited: Duration, concurrency: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        let stats = inner.limiters.entry(limiter.to_string()).or_default();
+        stats.waits += 1;
+        stats.waited += waited;
+        stats.concurrency = concurrency;
+    }
+
+    /// Sleeps for the fixed `interval` of `limiter` and records it
+    pub async fn throttle(&self, limiter: &str, interval: Duration) {
+        let started = Instant::now();
+        sleep(interval).await;
+        self.record_wait(limiter, started.elapsed(), 1);
+        self.inner
+            .lock()
+            .unwrap()
+            .limiters
+            .entry(limiter.to_string())
+            .or_default()
+            .interval = interval;
+    }
+
+    pub fn report(&self) -> RequestReport {
+        self.inner.lock().unwrap().clone()
+    }
+}
+
+mod millis {
+    use serde::Serializer;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u128(duration.as_millis())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use reqwest::{Client, Url};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Product token matched against the user-agent lines of robots.txt
+const ROBOTS_AGENT: &str = "gameharmony";
+
+/// The rules of a robots.txt that apply to us
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    /// `(allow, pattern)` pairs, the longest matching pattern wins
+    rules: Vec<(bool, String)>,
+    pub crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Parses the group for our agent, falling back to the `*` group
+    pub fn parse(content: &str) -> Self {
+        let mut specific = None;
+        let mut wildcard = None;
+        let mut agents: Vec<String> = Vec::new();
+        let mut group = RobotsRules::default();
+        let mut in_rules = false;
+
+        let mut finish = |agents: &[String], group: RobotsRules| {
+            if agents.iter().any(|agent| agent.contains(ROBOTS_AGENT)) {
+                specific.get_or_insert(group);
+            } else if agents.iter().any(|agent| agent == "*") {
+                wildcard.get_or_insert(group);
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        finish(&agents, std::mem::take(&mut group));
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_lowercase());
+                }
+                field @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if !value.is_empty() {
+                        group.rules.push((field == "allow", value.to_string()));
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    group.crawl_delay = value.parse::<f64>().ok().map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+        finish(&agents, group);
+
+        specific.or(wildcard).unwrap_or_default()
+    }
+
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| matches_pattern(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Matches robots.txt path patterns, which may contain `*` wildcards and end
+/// with `$` to anchor them
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(mut rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    let Some((last, middle)) = parts[1..].split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    // An anchored pattern has to match its last part at the end of the path,
+    // not where it first occurs
+    if anchored {
+        rest.ends_with(last)
+    } else {
+        rest.contains(last)
+    }
+}
+
+/// Fetches and caches the robots.txt of every host scraped
+pub struct RobotsChecker {
+    client: Client,
+    hosts: Mutex<HashMap<String, RobotsRules>>,
+}
+
+impl RobotsChecker {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The rules for the host of `url`. Hosts without a readable robots.txt
+    /// allow everything.
+    pub async fn rules(&self, url: &str) -> RobotsRules {
+        let Ok(url) = Url::parse(url) else {
+            return RobotsRules::default();
+        };
+        let origin = url.origin().ascii_serialization();
+
+        let mut hosts = self.hosts.lock().await;
+        if let Some(rules) = hosts.get(&origin) {
+            return rules.clone();
+        }
+
+        let robots_url = format!("{}/robots.txt", origin);
+        REQUEST_STATS.record_request(&robots_url);
+        let rules = match self.client.get(&robots_url).send().await {
+            Ok(response) if response.status().is_success() => {
+                RobotsRules::parse(&response.text().await.unwrap_or_default())
+            }
+            Ok(response) => {
+                info!("No robots.txt at {} ({})", origin, response.status());
+                RobotsRules::default()
+            }
+            Err(e) => {
+                warn!("Can't read robots.txt of {}: {}", origin, e);
+                RobotsRules::default()
+            }
+        };
+        hosts.insert(origin, rules.clone());
+        rules
+    }
+
+    pub async fn is_allowed(&self, url: &str) -> bool {
+        let path = Url::parse(url)
+            .map(|url| match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            })
+            .unwrap_or_default();
+        self.rules(url).await.is_allowed(&path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards_and_anchors() {
+        assert!(matches_pattern("/private", "/private/page"));
+        assert!(matches_pattern("/*.php", "/a/index.php?q=1"));
+        assert!(matches_pattern("/*.php$", "/a/index.php"));
+        assert!(!matches_pattern("/*.php$", "/a/index.php?q=1"));
+        assert!(matches_pattern("/list$", "/list"));
+        assert!(!matches_pattern("/list$", "/list/2"));
+        assert!(matches_pattern("/a*$", "/a/anything"));
+    }
+
+    #[test]
+    fn matches_anchored_patterns_at_the_end_of_repeated_suffixes() {
+        assert!(matches_pattern("/*.php$", "/a.php/b.php"));
+        assert!(!matches_pattern("/*.php$", "/a.php/b.html"));
+        assert!(matches_pattern("/*/*.php$", "/x/a.php/b.php"));
+
+        let rules = RobotsRules {
+            rules: vec![(false, "/*.php$".to_string())],
+            ..RobotsRules::default()
+        };
+        assert!(!rules.is_allowed("/a.php/b.php"));
+    }
+}
@@ -1,10 +1,47 @@
-use crate::domain::storage::Storage;
-use crate::error::Result;
-use reqwest::Client;
+use crate::domain::storage::{Storage, StorageKeys};
+use crate::domain::ReleaseStatus;
+use crate::error::{GameError, Result};
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use chrono::{NaiveDate, Utc};
+use futures::future::join_all;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::sync::Arc;
-use tracing::info;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+/// How often a request with a retryable response is attempted before giving up
+const REQUEST_ATTEMPTS: u32 = 3;
+
+/// Concurrent requests to the Steam store API when fetching regional prices
+const MAX_CONCURRENT_STORE_REQUESTS: usize = 4;
+
+/// Fewer reviews in the last 30 days say nothing about a trend
+const TREND_MIN_RECENT_REVIEWS: u64 = 20;
+/// Difference between the recent and the overall share of positive reviews
+/// that counts as a trend
+const TREND_THRESHOLD: f64 = 0.05;
+
+const STORE_APP_LIST_URL: &str = "https://api.steampowered.com/IStoreService/GetAppList/v1/";
+/// Most apps `IStoreService/GetAppList` returns per request
+const STORE_APP_LIST_PAGE_SIZE: u32 = 50_000;
+
+/// Age after which the cached app list is fetched again, unless overridden
+const DEFAULT_APP_LIST_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Values of `ExtendedPlatforms::steamdeck`, after Steam's resolved category
+pub const DECK_STATUS_VERIFIED: &str = "verified";
+pub const DECK_STATUS_PLAYABLE: &str = "playable";
+pub const DECK_STATUS_UNSUPPORTED: &str = "unsupported";
+/// Steam has no compatibility report for the game
+pub const DECK_STATUS_UNKNOWN: &str = "unknown";
+/// The compatibility report couldn't be fetched
+pub const DECK_STATUS_ERROR: &str = "error";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SteamApp {
@@ -22,6 +59,28 @@ pub struct SteamAppList {
     pub apps: Vec<SteamApp>,
 }
 
+/// The app list as of the last run, updated with the apps changed since
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SteamAppListCache {
+    /// Unix timestamp of the last update, the watermark for the next one
+    pub updated_at: i64,
+    pub apps: Vec<SteamApp>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreAppListResponse {
+    response: StoreAppListPage,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreAppListPage {
+    #[serde(default)]
+    apps: Vec<SteamApp>,
+    #[serde(default)]
+    have_more_results: bool,
+    last_appid: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SteamStoreData {
     pub success: bool,
@@ -30,10 +89,158 @@ pub struct SteamStoreData {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SteamStoreDetails {
+    #[serde(rename = "type")]
+    pub app_type: Option<String>,
+    pub fullgame: Option<FullGame>,
     pub price_overview: Option<PriceOverview>,
     pub platforms: Platforms,
     pub header_image: Option<String>,
     pub metacritic: Option<Metacritic>,
+    #[serde(default)]
+    pub short_description: Option<String>,
+    #[serde(default)]
+    pub screenshots: Vec<Screenshot>,
+    #[serde(default)]
+    pub movies: Vec<Movie>,
+    pub achievements: Option<Achievements>,
+    pub controller_support: Option<String>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub genres: Vec<Genre>,
+    pub release_date: Option<SteamReleaseDate>,
+}
+
+impl SteamStoreDetails {
+    fn release_status(&self) -> ReleaseStatus {
+        if self
+            .genres
+            .iter()
+            .any(|genre| genre.id == EARLY_ACCESS_GENRE)
+        {
+            ReleaseStatus::EarlyAccess
+        } else if self.release_date.as_ref().is_some_and(|d| d.coming_soon) {
+            ReleaseStatus::ComingSoon
+        } else {
+            ReleaseStatus::Released
+        }
+    }
+
+    /// Release date as YYYY-MM-DD like RAWG's, if Steam names the exact day
+    fn release_day(&self) -> Option<String> {
+        let date = &self.release_date.as_ref()?.date;
+        ["%d %b, %Y", "%b %d, %Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+            .map(|day| day.format("%Y-%m-%d").to_string())
+    }
+
+    /// Returns the appid of the base game when this app is not a game itself
+    /// (DLC, demo, soundtrack, ...) and Steam links to the full game.
+    fn base_game_id(&self) -> Option<u64> {
+        match self.app_type.as_deref() {
+            Some("game") | None => None,
+            Some(_) => self.fullgame.as_ref().and_then(|f| f.appid.parse().ok()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Achievements {
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Category {
+    pub id: u64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genre {
+    pub id: String,
+    pub description: String,
+}
+
+/// Steam lists early access as a genre
+const EARLY_ACCESS_GENRE: &str = "70";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamReleaseDate {
+    pub coming_soon: bool,
+    /// Like "14 Dec, 2021", or vaguer for unreleased games, e.g. "Q1 2025"
+    #[serde(default)]
+    pub date: String,
+}
+
+/// Steam category IDs of the features `GameFeatures` tracks
+mod category_ids {
+    pub const MULTIPLAYER: u64 = 1;
+    pub const SINGLE_PLAYER: u64 = 2;
+    pub const CO_OP: u64 = 9;
+    pub const SPLIT_SCREEN: u64 = 24;
+    pub const ONLINE_CO_OP: u64 = 38;
+    pub const SPLIT_SCREEN_CO_OP: u64 = 39;
+    pub const REMOTE_PLAY_TOGETHER: u64 = 44;
+}
+
+/// How a game can be played, derived from its Steam categories
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameFeatures {
+    pub single_player: bool,
+    pub multiplayer: bool,
+    pub co_op: bool,
+    pub split_screen: bool,
+    pub remote_play_together: bool,
+}
+
+impl GameFeatures {
+    fn from_categories(categories: &[Category]) -> Self {
+        use category_ids::*;
+        let has = |ids: &[u64]| categories.iter().any(|c| ids.contains(&c.id));
+        Self {
+            single_player: has(&[SINGLE_PLAYER]),
+            multiplayer: has(&[MULTIPLAYER]),
+            co_op: has(&[CO_OP, ONLINE_CO_OP, SPLIT_SCREEN_CO_OP]),
+            split_screen: has(&[SPLIT_SCREEN, SPLIT_SCREEN_CO_OP]),
+            remote_play_together: has(&[REMOTE_PLAY_TOGETHER]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Screenshot {
+    pub path_full: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Movie {
+    #[serde(default)]
+    pub highlight: bool,
+    pub mp4: Option<MovieFormats>,
+    pub webm: Option<MovieFormats>,
+    pub hls_h264: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovieFormats {
+    pub max: String,
+}
+
+impl Movie {
+    fn url(&self) -> Option<String> {
+        self.mp4
+            .as_ref()
+            .or(self.webm.as_ref())
+            .map(|formats| formats.max.clone())
+            .or_else(|| self.hls_h264.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullGame {
+    pub appid: String,
+    pub name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +264,61 @@ pub struct Platforms {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SteamReviewsResponse {
     pub query_summary: ReviewsSummary,
+    /// Filled in from the histogram endpoint, missing if it failed
+    #[serde(default)]
+    pub histogram: Option<ReviewHistogram>,
+}
+
+impl SteamReviewsResponse {
+    /// Compares the share of positive reviews of the last 30 days to that of
+    /// all reviews
+    pub fn recent_trend(&self) -> Option<ReviewTrend> {
+        let recent = &self.histogram.as_ref()?.recent;
+        let up: u64 = recent.iter().map(|day| day.recommendations_up).sum();
+        let down: u64 = recent.iter().map(|day| day.recommendations_down).sum();
+        let summary = &self.query_summary;
+        if up + down < TREND_MIN_RECENT_REVIEWS || summary.total_reviews == 0 {
+            return None;
+        }
+
+        let recent_share = up as f64 / (up + down) as f64;
+        let overall_share = summary.total_positive as f64 / summary.total_reviews as f64;
+        Some(if recent_share - overall_share > TREND_THRESHOLD {
+            ReviewTrend::Improving
+        } else if overall_share - recent_share > TREND_THRESHOLD {
+            ReviewTrend::Declining
+        } else {
+            ReviewTrend::Stable
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewHistogramResponse {
+    pub success: u64,
+    pub results: Option<ReviewHistogram>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewHistogram {
+    /// Daily review counts of the last 30 days
+    #[serde(default)]
+    pub recent: Vec<ReviewCounts>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewCounts {
+    pub recommendations_up: u64,
+    pub recommendations_down: u64,
+}
+
+/// Where the recent reviews are heading, e.g. recovering from a review bomb
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewTrend {
+    Improving,
+    Stable,
+    Declining,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,13 +329,13 @@ pub struct ReviewsSummary {
     pub review_score_desc: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SteamDeckVerifiedResponse {
     pub success: u64,
     pub results: Option<DeckResults>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeckResults {
     pub appid: u64,
     pub resolved_category: u64,
@@ -82,21 +344,76 @@ pub struct DeckResults {
     pub search_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl DeckResults {
+    /// Status of the resolved category: 1 unsupported, 2 playable and
+    /// 3 verified. Steam reports untested games as 0.
+    pub fn status(&self) -> &'static str {
+        match self.resolved_category {
+            1 => DECK_STATUS_UNSUPPORTED,
+            2 => DECK_STATUS_PLAYABLE,
+            3 => DECK_STATUS_VERIFIED,
+            _ => DECK_STATUS_UNKNOWN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeckResultItem {
     pub display_type: u64,
     pub loc_token: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StoreInfo {
+    /// Steam no longer sells the app, or not in this region, so the rest is
+    /// empty
+    #[serde(default)]
+    pub delisted: bool,
     pub price: Option<String>,
     pub platforms: ExtendedPlatforms,
     pub header_image: Option<String>,
-    pub user_score: u64,
-    pub total_reviews: u64,
+    /// Missing when the reviews couldn't be fetched, they are retried next run
+    #[serde(default)]
+    pub user_score: Option<u64>,
+    #[serde(default)]
+    pub total_reviews: Option<u64>,
+    /// Steam's summary of the reviews, like "Mostly Positive"
+    #[serde(default)]
+    pub review_score_desc: Option<String>,
+    #[serde(default)]
+    pub recent_review_trend: Option<ReviewTrend>,
     pub metacritic_score: Option<u64>,
     pub metacritic_url: Option<String>,
+    /// Set when the matched app was resolved to a different base game
+    #[serde(default)]
+    pub base_app_id: Option<u64>,
+    /// Steam's type of the matched app, like "game" or "dlc"
+    #[serde(default)]
+    pub app_type: Option<String>,
+    /// Formatted price per country code, `None` if the app isn't sold there
+    #[serde(default)]
+    pub regional_prices: HashMap<String, Option<String>>,
+    #[serde(default)]
+    pub short_description: Option<String>,
+    #[serde(default)]
+    pub screenshots: Vec<String>,
+    #[serde(default)]
+    pub trailer_url: Option<String>,
+    #[serde(default)]
+    pub achievements: Option<u64>,
+    /// "full" or "partial", missing without controller support
+    #[serde(default)]
+    pub controller_support: Option<String>,
+    #[serde(default)]
+    pub features: GameFeatures,
+    /// Names of all Steam categories, like "Steam Cloud"
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub release_status: Option<ReleaseStatus>,
+    /// YYYY-MM-DD
+    #[serde(default)]
+    pub release_date: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -120,21 +437,96 @@ impl From<Platforms> for ExtendedPlatforms {
     }
 }
 
+/// Outcome of an appdetails request
+enum StoreLookup {
+    Listed(Box<SteamStoreDetails>),
+    /// Steam answered, but no longer has the app or redirected away from it
+    Delisted,
+    /// The request failed, the app may well be listed
+    Unavailable,
+}
+
+impl StoreLookup {
+    fn details(&self) -> Option<&SteamStoreDetails> {
+        match self {
+            StoreLookup::Listed(details) => Some(details),
+            _ => None,
+        }
+    }
+}
+
 pub struct SteamClient {
     client: Client,
     store: Arc<dyn Storage>,
-    pub steam_apps: Vec<SteamApp>,
+    api_key: Option<String>,
+    /// How long the cached app list is used before it's fetched again
+    app_list_max_age: Duration,
+    /// Country codes to fetch prices for, in addition to the default region
+    regions: Vec<String>,
+    store_api_permits: Semaphore,
 }
 
 impl SteamClient {
-    pub async fn new(client: Client, store: Arc<dyn Storage>) -> Result<Self> {
-        let steam_apps = Self::fetch_combined_steam_apps(&client).await?;
-        info!("Created new Steam client and fetched steam apps from both endpoints");
-        Ok(Self {
+    /// The app list is updated incrementally from `IStoreService` with an
+    /// `api_key`, else fetched from both `ISteamApps` endpoints
+    pub fn new(client: Client, store: Arc<dyn Storage>, api_key: Option<&str>) -> Self {
+        Self {
             client,
             store,
-            steam_apps,
-        })
+            api_key: api_key.map(str::to_string),
+            app_list_max_age: DEFAULT_APP_LIST_MAX_AGE,
+            regions: Vec::new(),
+            store_api_permits: Semaphore::new(MAX_CONCURRENT_STORE_REQUESTS),
+        }
+    }
+
+    pub fn with_regions(mut self, regions: Vec<String>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    pub fn with_app_list_max_age(mut self, max_age: Duration) -> Self {
+        self.app_list_max_age = max_age;
+        self
+    }
+
+    /// Whether the cached app list is younger than the max age
+    pub fn app_list_is_fresh(&self) -> Result<bool> {
+        let cached = self.store.load_steam_app_list()?.unwrap_or_default();
+        Ok(self.is_fresh(&cached))
+    }
+
+    fn is_fresh(&self, cached: &SteamAppListCache) -> bool {
+        let age = Utc::now().timestamp() - cached.updated_at;
+        !cached.apps.is_empty() && age < self.app_list_max_age.as_secs() as i64
+    }
+
+    /// All Steam apps, from the cache while it's younger than the max age
+    pub async fn steam_apps(&self) -> Result<Vec<SteamApp>> {
+        let cached = self.store.load_steam_app_list()?.unwrap_or_default();
+        if self.is_fresh(&cached) {
+            let age = Utc::now().timestamp() - cached.updated_at;
+            info!(
+                "Using {} cached Steam apps, updated {}s ago",
+                cached.apps.len(),
+                age
+            );
+            return Ok(cached.apps);
+        }
+
+        match &self.api_key {
+            Some(api_key) => {
+                Self::update_steam_apps(&self.client, cached, api_key, &*self.store).await
+            }
+            None => {
+                let list = SteamAppListCache {
+                    updated_at: Utc::now().timestamp(),
+                    apps: Self::fetch_combined_steam_apps(&self.client).await?,
+                };
+                self.store.save_steam_app_list(&list)?;
+                Ok(list.apps)
+            }
+        }
     }
 
     async fn fetch_combined_steam_apps(client: &Client) -> Result<Vec<SteamApp>> {
@@ -147,9 +539,10 @@ impl SteamClient {
             legacy_apps.len()
         );
 
-        let mut unique_apps = HashMap::new();
+        let mut unique_apps = BTreeMap::new();
 
-        // Insert apps from both endpoints, using appid as key
+        // Insert apps from both endpoints, using appid as key. Ordered by
+        // appid, so the list and everything built from it is the same each run
         for app in v2_apps {
             unique_apps.insert(app.appid, app);
         }
@@ -164,86 +557,498 @@ impl SteamClient {
         Ok(combined_apps)
     }
 
+    /// The cached app list with the apps added or changed since it was last
+    /// updated. Removed apps are not reported by Steam and stay in the list.
+    async fn update_steam_apps(
+        client: &Client,
+        cached: SteamAppListCache,
+        api_key: &str,
+        store: &dyn Storage,
+    ) -> Result<Vec<SteamApp>> {
+        let updated_at = Utc::now().timestamp();
+        let since = (!cached.apps.is_empty()).then_some(cached.updated_at);
+        let changed = Self::fetch_store_apps(client, api_key, since).await?;
+        info!(
+            "Fetched {} new or changed apps for the {} cached ones",
+            changed.len(),
+            cached.apps.len()
+        );
+
+        // Ordered by appid, as the combined list
+        let mut apps: BTreeMap<u64, SteamApp> = cached
+            .apps
+            .into_iter()
+            .map(|app| (app.appid, app))
+            .collect();
+        for app in changed {
+            apps.insert(app.appid, app);
+        }
+
+        let list = SteamAppListCache {
+            updated_at,
+            apps: apps.into_values().collect(),
+        };
+        store.save_steam_app_list(&list)?;
+        Ok(list.apps)
+    }
+
+    /// Pages through `IStoreService/GetAppList`, only returning the apps
+    /// modified after `since` if given
+    async fn fetch_store_apps(
+        client: &Client,
+        api_key: &str,
+        since: Option<i64>,
+    ) -> Result<Vec<SteamApp>> {
+        let mut apps = Vec::new();
+        let mut last_appid = 0;
+        loop {
+            let mut query = vec![
+                ("key", api_key.to_string()),
+                ("max_results", STORE_APP_LIST_PAGE_SIZE.to_string()),
+                ("last_appid", last_appid.to_string()),
+                ("include_games", "true".to_string()),
+                ("include_dlc", "true".to_string()),
+                ("include_software", "true".to_string()),
+            ];
+            if let Some(since) = since {
+                query.push(("if_modified_since", since.to_string()));
+            }
+
+            REQUEST_STATS.record_request(STORE_APP_LIST_URL);
+            // The URL carries the API key, so it's left out of errors
+            let page: StoreAppListResponse = client
+                .get(STORE_APP_LIST_URL)
+                .query(&query)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| e.without_url())?
+                .json()
+                .await
+                .map_err(|e| e.without_url())?;
+
+            apps.extend(page.response.apps);
+            match page.response.last_appid {
+                Some(next) if page.response.have_more_results => last_appid = next,
+                _ => break,
+            }
+        }
+        Ok(apps)
+    }
+
     async fn fetch_steam_apps_v2(client: &Client) -> Result<Vec<SteamApp>> {
         let url = "https://api.steampowered.com/ISteamApps/GetAppList/v2/";
+        REQUEST_STATS.record_request(url);
         let response: SteamResponse = client.get(url).send().await?.json().await?;
         Ok(response.applist.apps)
     }
 
     async fn fetch_steam_apps_legacy(client: &Client) -> Result<Vec<SteamApp>> {
         let url = "https://api.steampowered.com/ISteamApps/GetAppList/v0002/";
+        REQUEST_STATS.record_request(url);
         let response: SteamResponse = client.get(url).send().await?.json().await?;
         Ok(response.applist.apps)
     }
 
-    pub async fn get_store_info(&self, app_id: u64) -> Result<Option<StoreInfo>> {
-        if let Some(cached) = self.store.load_app_info(app_id)? {
-            return Ok(Some(cached));
+    /// Fetches store data and reviews for an app. Cached data is used unless
+    /// `refresh` is set.
+    pub async fn get_store_info(&self, app_id: u64, refresh: bool) -> Result<Option<StoreInfo>> {
+        if !refresh {
+            if let Some(mut cached) = self.store.load_app_info(app_id)? {
+                if cached.delisted {
+                    return Ok(Some(cached));
+                }
+                let mut updated = self.fill_regional_prices(app_id, &mut cached).await;
+                // Entries cached before the review summary was kept lack it
+                if cached.user_score.is_none() || cached.review_score_desc.is_none() {
+                    let review_app_id = cached.base_app_id.unwrap_or(app_id);
+                    if let Some(reviews) = self.fetch_reviews(review_app_id).await? {
+                        cached.user_score = Some(reviews.query_summary.review_score);
+                        cached.total_reviews = Some(reviews.query_summary.total_reviews);
+                        cached.review_score_desc =
+                            Some(reviews.query_summary.review_score_desc.clone());
+                        cached.recent_review_trend = reviews.recent_trend();
+                        updated = true;
+                    }
+                }
+                if updated {
+                    self.store.save_app_info(app_id, cached.clone())?;
+                }
+                return Ok(Some(cached));
+            }
         }
 
-        let store_data = self.fetch_store_data(app_id).await?;
-        let reviews = self.fetch_reviews(app_id).await?;
-
-        let info = match (store_data, reviews) {
-            (Some(store), Some(reviews)) => Some(StoreInfo {
-                price: store.price_overview.map(|p| p.final_formatted),
-                platforms: store.platforms.into(),
-                header_image: store.header_image,
-                metacritic_score: store.metacritic.clone().map(|m| m.score),
-                metacritic_url: store.metacritic.map(|m| m.url),
-                user_score: reviews.query_summary.review_score,
-                total_reviews: reviews.query_summary.total_reviews,
-            }),
-            _ => None,
+        let mut lookup = self.fetch_store_data(app_id).await?;
+        let app_type = lookup.details().and_then(|s| s.app_type.clone());
+        let base_app_id = lookup.details().and_then(|s| s.base_game_id());
+        if let Some(base_id) = base_app_id {
+            info!(
+                "App {} is not a game, resolving to base game {}",
+                app_id, base_id
+            );
+            lookup = self.fetch_store_data(base_id).await?;
+        }
+        let store_data = match lookup {
+            StoreLookup::Listed(details) => Some(*details),
+            StoreLookup::Delisted => {
+                info!("App {} is delisted or not sold in this region", app_id);
+                let info = StoreInfo {
+                    delisted: true,
+                    base_app_id,
+                    app_type,
+                    ..StoreInfo::default()
+                };
+                self.store.save_app_info(app_id, info.clone())?;
+                return Ok(Some(info));
+            }
+            StoreLookup::Unavailable => None,
         };
+        let reviews = self.fetch_reviews(base_app_id.unwrap_or(app_id)).await?;
 
-        if let Some(store_info) = info.clone() {
-            self.store.save_app_info(app_id, store_info)?;
+        // Store data is kept even when the reviews failed
+        let mut info = store_data.map(|store| StoreInfo {
+            delisted: false,
+            release_status: Some(store.release_status()),
+            release_date: store.release_day(),
+            short_description: store.short_description.clone(),
+            screenshots: store
+                .screenshots
+                .iter()
+                .map(|screenshot| screenshot.path_full.clone())
+                .collect(),
+            // Prefer the highlighted trailer, Steam lists it first otherwise
+            trailer_url: store
+                .movies
+                .iter()
+                .find(|movie| movie.highlight)
+                .or(store.movies.first())
+                .and_then(Movie::url),
+            achievements: store.achievements.as_ref().map(|a| a.total),
+            controller_support: store.controller_support.clone(),
+            features: GameFeatures::from_categories(&store.categories),
+            categories: store
+                .categories
+                .iter()
+                .map(|category| category.description.clone())
+                .collect(),
+            price: store.price_overview.map(|p| p.final_formatted),
+            platforms: store.platforms.into(),
+            header_image: store.header_image,
+            metacritic_score: store.metacritic.clone().map(|m| m.score),
+            metacritic_url: store.metacritic.map(|m| m.url),
+            user_score: reviews.as_ref().map(|r| r.query_summary.review_score),
+            total_reviews: reviews.as_ref().map(|r| r.query_summary.total_reviews),
+            review_score_desc: reviews
+                .as_ref()
+                .map(|r| r.query_summary.review_score_desc.clone()),
+            recent_review_trend: reviews.as_ref().and_then(|r| r.recent_trend()),
+            base_app_id,
+            app_type,
+            regional_prices: HashMap::new(),
+        });
+
+        if let Some(store_info) = info.as_mut() {
+            self.fill_regional_prices(app_id, store_info).await;
+            self.store.save_app_info(app_id, store_info.clone())?;
         }
 
         Ok(info)
     }
 
-    async fn fetch_store_data(&self, app_id: u64) -> Result<Option<SteamStoreDetails>> {
+    /// Fetches prices for all configured regions missing in `info` concurrently.
+    /// Returns whether any price was added.
+    async fn fill_regional_prices(&self, app_id: u64, info: &mut StoreInfo) -> bool {
+        let price_app_id = info.base_app_id.unwrap_or(app_id);
+        let missing = self
+            .regions
+            .iter()
+            .filter(|region| !info.regional_prices.contains_key(*region));
+
+        let requests = missing.map(|region| async move {
+            let started = Instant::now();
+            let _permit = self.store_api_permits.acquire().await;
+            REQUEST_STATS.record_wait(
+                "steam_store_concurrency",
+                started.elapsed(),
+                MAX_CONCURRENT_STORE_REQUESTS,
+            );
+            match self.fetch_regional_price(price_app_id, region).await {
+                Ok(price) => Some((region.clone(), price)),
+                Err(e) => {
+                    warn!("Price for app {} in {} failed: {}", price_app_id, region, e);
+                    None
+                }
+            }
+        });
+
+        let prices: Vec<_> = join_all(requests).await.into_iter().flatten().collect();
+        let updated = !prices.is_empty();
+        info.regional_prices.extend(prices);
+        updated
+    }
+
+    async fn fetch_regional_price(&self, app_id: u64, region: &str) -> Result<Option<String>> {
         let url = format!(
-            "https://store.steampowered.com/api/appdetails?appids={}",
-            app_id
+            "https://store.steampowered.com/api/appdetails?appids={}&cc={}&filters=price_overview",
+            app_id, region
         );
 
+        REQUEST_STATS.record_request(&url);
         let response = self.client.get(&url).send().await?;
         if !response.status().is_success() {
-            return Ok(None);
+            return Err(GameError::Retryable {
+                url,
+                reason: format!("status {}", response.status()),
+            });
         }
 
-        let data: HashMap<String, SteamStoreData> = response.json().await?;
+        // Free or unavailable apps come back with an empty array as `data`
+        let data: HashMap<String, serde_json::Value> = response.json().await?;
         Ok(data
             .get(&app_id.to_string())
-            .filter(|d| d.success)
-            .map(|d| d.data.clone()))
+            .and_then(|app| app["data"]["price_overview"]["final_formatted"].as_str())
+            .map(str::to_string))
     }
 
-    async fn fetch_reviews(&self, app_id: u64) -> Result<Option<SteamReviewsResponse>> {
+    async fn fetch_store_data(&self, app_id: u64) -> Result<StoreLookup> {
         let url = format!(
-            "https://store.steampowered.com/appreviews/{}?json=1",
+            "https://store.steampowered.com/api/appdetails?appids={}",
             app_id
         );
 
+        REQUEST_STATS.record_request(&url);
         let response = self.client.get(&url).send().await?;
+        if response.url().as_str() != url {
+            return Ok(StoreLookup::Delisted);
+        }
         if !response.status().is_success() {
+            return Ok(StoreLookup::Unavailable);
+        }
+
+        // Delisted apps come back as `success: false` without data
+        let data: HashMap<String, serde_json::Value> = response.json().await?;
+        let Some(app) = data.get(&app_id.to_string()) else {
+            return Ok(StoreLookup::Unavailable);
+        };
+        if app["success"].as_bool() != Some(true) {
+            return Ok(StoreLookup::Delisted);
+        }
+        let app: SteamStoreData = serde_json::from_value(app.clone())?;
+        Ok(StoreLookup::Listed(Box::new(app.data)))
+    }
+
+    /// Fetches the review summary and histogram, retrying while Steam answers
+    /// with rate-limit or error pages. Gives up with `None` so the rest of the
+    /// store info isn't lost, a failed histogram only leaves out the trend.
+    async fn fetch_reviews(&self, app_id: u64) -> Result<Option<SteamReviewsResponse>> {
+        let url = format!(
+            "https://store.steampowered.com/appreviews/{}?json=1",
+            app_id
+        );
+
+        let description = format!("reviews for app {}", app_id);
+        let Some(mut reviews) = with_retries(&description, || self.try_fetch_reviews(&url))
+            .await?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        let url = format!(
+            "https://store.steampowered.com/appreviewhistogram/{}?l=english&review_score_preference=0",
+            app_id
+        );
+        let description = format!("review histogram for app {}", app_id);
+        match with_retries(&description, || self.try_fetch_histogram(&url)).await {
+            Ok(histogram) => reviews.histogram = histogram.flatten(),
+            Err(e) => warn!("Review histogram of app {} failed: {}", app_id, e),
+        }
+        Ok(Some(reviews))
+    }
+
+    async fn try_fetch_histogram(&self, url: &str) -> Result<Option<ReviewHistogram>> {
+        REQUEST_STATS.record_request(url);
+        let response = self.client.get(url).send().await?;
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err(GameError::Retryable {
+                url: url.to_string(),
+                reason: format!("status {}", status),
+            });
+        }
+        if !status.is_success() {
             return Ok(None);
         }
 
-        Ok(Some(response.json().await?))
+        let body = response.text().await?;
+        let histogram: ReviewHistogramResponse = parse_json_body(url, &body)?;
+        Ok(histogram.results.filter(|_| histogram.success == 1))
     }
 
-    pub async fn get_deck_verified(&self, app_id: String) -> Result<SteamDeckVerifiedResponse> {
+    async fn try_fetch_reviews(&self, url: &str) -> Result<Option<SteamReviewsResponse>> {
+        REQUEST_STATS.record_request(url);
+        let response = self.client.get(url).send().await?;
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err(GameError::Retryable {
+                url: url.to_string(),
+                reason: format!("status {}", status),
+            });
+        }
+        if !status.is_success() {
+            return Ok(None);
+        }
+
+        let body = response.text().await?;
+        parse_json_body(url, &body).map(Some)
+    }
+
+    /// When the cached store data of `app_id` was fetched
+    pub fn store_info_cached_at(&self, app_id: u64) -> Result<Option<SystemTime>> {
+        self.store
+            .cached_at(StorageKeys::STEAM_APPS_DIR, &app_id.to_string())
+    }
+
+    /// When the cached Steam Deck report of `app_id` was fetched
+    pub fn deck_info_cached_at(&self, app_id: u64) -> Result<Option<SystemTime>> {
+        self.store
+            .cached_at(StorageKeys::STEAM_DECK_DIR, &app_id.to_string())
+    }
+
+    /// Fetches the Steam Deck compatibility report. Cached reports are used
+    /// unless `refresh` is set, and failed requests are retried with backoff.
+    pub async fn get_deck_verified(
+        &self,
+        app_id: u64,
+        refresh: bool,
+    ) -> Result<SteamDeckVerifiedResponse> {
+        if !refresh {
+            if let Some(cached) = self.store.load_deck_info(app_id)? {
+                return Ok(cached);
+            }
+        }
+
         let url = format!(
             "https://store.steampowered.com/saleaction/ajaxgetdeckappcompatibilityreport?nAppID={app_id}"
         );
 
-        let response = self.client.get(&url).send().await?;
-        let deck_status: SteamDeckVerifiedResponse = response.json().await?;
+        let description = format!("Steam Deck status for app {}", app_id);
+        let deck_status = with_retries(&description, || self.try_fetch_deck_verified(&url))
+            .await?
+            .ok_or_else(|| GameError::Retryable {
+                url: url.clone(),
+                reason: "no valid response after retries".to_string(),
+            })?;
 
+        self.store.save_deck_info(app_id, &deck_status)?;
         Ok(deck_status)
     }
+
+    async fn try_fetch_deck_verified(&self, url: &str) -> Result<SteamDeckVerifiedResponse> {
+        REQUEST_STATS.record_request(url);
+        let response = self.client.get(url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(GameError::Retryable {
+                url: url.to_string(),
+                reason: format!("status {}", status),
+            });
+        }
+
+        let body = response.text().await?;
+        parse_json_body(url, &body)
+    }
+}
+
+/// Runs `request` until it returns something other than a retryable error,
+/// backing off exponentially in between. Returns `None` once all
+/// `REQUEST_ATTEMPTS` attempts were retryable.
+async fn with_retries<T, F, Fut>(description: &str, mut request: F) -> Result<Option<T>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    for attempt in 0..REQUEST_ATTEMPTS {
+        match request().await {
+            Err(GameError::Retryable { reason, .. }) if attempt + 1 == REQUEST_ATTEMPTS => {
+                warn!("Retryable response for {}: {}", description, reason);
+            }
+            Err(GameError::Retryable { reason, .. }) => {
+                let backoff = Duration::from_secs(1 << attempt);
+                warn!(
+                    "Retryable response for {}: {}, retrying in {:?}",
+                    description, reason, backoff
+                );
+                let started = Instant::now();
+                sleep(backoff).await;
+                REQUEST_STATS.record_wait("retry_backoff", started.elapsed(), 1);
+            }
+            result => return result.map(Some),
+        }
+    }
+
+    warn!("Giving up on {}", description);
+    Ok(None)
+}
+
+/// Parses a JSON body. Steam serves HTML pages (rate limiting, maintenance)
+/// with a success status, which are reported as retryable instead of as a JSON
+/// error.
+fn parse_json_body<T: DeserializeOwned>(url: &str, body: &str) -> Result<T> {
+    if !body.trim_start().starts_with('{') {
+        return Err(GameError::Retryable {
+            url: url.to_string(),
+            reason: "response is not JSON".to_string(),
+        });
+    }
+
+    Ok(serde_json::from_str(body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://store.steampowered.com/appreviews/620?json=1";
+
+    #[test]
+    fn rejects_html_error_pages_as_retryable() {
+        let body = include_str!("../../../tests/fixtures/steam/appreviews_error_page.html");
+        let parsed = parse_json_body::<SteamReviewsResponse>(URL, body);
+        assert!(
+            matches!(parsed, Err(GameError::Retryable { .. })),
+            "{:?}",
+            parsed
+        );
+    }
+
+    #[test]
+    fn parses_review_summaries() {
+        let body = include_str!("../../../tests/fixtures/steam/appreviews.json");
+        let reviews: SteamReviewsResponse = parse_json_body(URL, body).unwrap();
+        assert_eq!(reviews.query_summary.total_positive, 240517);
+        assert_eq!(reviews.query_summary.total_reviews, 244929);
+        assert!(reviews.histogram.is_none());
+    }
+
+    #[test]
+    fn maps_deck_categories_to_statuses() {
+        let cases = [
+            (0, DECK_STATUS_UNKNOWN),
+            (1, DECK_STATUS_UNSUPPORTED),
+            (2, DECK_STATUS_PLAYABLE),
+            (3, DECK_STATUS_VERIFIED),
+            (4, DECK_STATUS_UNKNOWN),
+        ];
+        for (resolved_category, status) in cases {
+            let results = DeckResults {
+                appid: 620,
+                resolved_category,
+                resolved_items: Vec::new(),
+                steam_deck_blog_url: String::new(),
+                search_id: None,
+            };
+            assert_eq!(results.status(), status, "category {}", resolved_category);
+        }
+    }
 }
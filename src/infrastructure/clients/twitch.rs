@@ -0,0 +1,203 @@
+use crate::domain::storage::Storage;
+use crate::error::{GameError, Result};
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use chrono::{DateTime, Local, TimeDelta};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::{error, info};
+
+const HELIX_URL: &str = "https://api.twitch.tv/helix";
+
+/// Viewer samples older than this don't count towards the recent average
+const RECENT_WINDOW: TimeDelta = TimeDelta::days(7);
+
+/// Streams counted per game, the most watched come first
+const STREAMS_PAGE_SIZE: &str = "100";
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixResponse<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixCategory {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixStream {
+    viewer_count: u64,
+}
+
+/// Viewer counts of a game's Twitch category seen over past runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwitchHistory {
+    pub category_id: String,
+    pub category_name: String,
+    #[serde(default)]
+    pub samples: Vec<TwitchSample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwitchSample {
+    pub at: String,
+    pub viewers: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwitchViewers {
+    pub current: u64,
+    /// Average over the samples of the last seven days
+    pub recent_average: u64,
+}
+
+impl TwitchHistory {
+    fn record(&mut self, viewers: u64) -> TwitchViewers {
+        let now = Local::now();
+        self.samples.retain(|sample| {
+            DateTime::parse_from_rfc3339(&sample.at)
+                .is_ok_and(|at| now.signed_duration_since(at) < RECENT_WINDOW)
+        });
+        self.samples.push(TwitchSample {
+            at: now.to_rfc3339(),
+            viewers,
+        });
+
+        let total: u64 = self.samples.iter().map(|sample| sample.viewers).sum();
+        TwitchViewers {
+            current: viewers,
+            recent_average: total / self.samples.len() as u64,
+        }
+    }
+}
+
+/// Counts live viewers per game through the Twitch Helix API
+pub struct TwitchClient {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    store: Arc<dyn Storage>,
+    token: OnceCell<String>,
+}
+
+impl TwitchClient {
+    pub fn new(
+        client: Client,
+        client_id: String,
+        client_secret: String,
+        store: Arc<dyn Storage>,
+    ) -> Self {
+        Self {
+            client,
+            client_id,
+            client_secret,
+            store,
+            token: OnceCell::new(),
+        }
+    }
+
+    /// Counts the current viewers of `title` and averages them with those of
+    /// earlier runs. The matching Twitch category is looked up once and cached.
+    pub async fn get_viewers(&self, title: &str) -> Result<Option<TwitchViewers>> {
+        let mut history = match self.store.load_twitch_history(title)? {
+            Some(history) => history,
+            None => match self.find_category(title).await? {
+                Some(category) => TwitchHistory {
+                    category_id: category.id,
+                    category_name: category.name,
+                    samples: Vec::new(),
+                },
+                None => {
+                    info!("No Twitch category found for: {}", title);
+                    return Ok(None);
+                }
+            },
+        };
+
+        let streams: Vec<HelixStream> = self
+            .helix(
+                "streams",
+                &[
+                    ("game_id", history.category_id.as_str()),
+                    ("first", STREAMS_PAGE_SIZE),
+                ],
+            )
+            .await?;
+        let viewers = history.record(streams.iter().map(|stream| stream.viewer_count).sum());
+        self.store.save_twitch_history(title, &history)?;
+
+        Ok(Some(viewers))
+    }
+
+    /// Searches the categories for one named exactly like `title`, Twitch
+    /// also returns loosely related ones
+    async fn find_category(&self, title: &str) -> Result<Option<HelixCategory>> {
+        let categories: Vec<HelixCategory> =
+            self.helix("search/categories", &[("query", title)]).await?;
+        Ok(categories
+            .into_iter()
+            .find(|category| category.name.eq_ignore_ascii_case(title)))
+    }
+
+    async fn helix<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let url = format!("{}/{}", HELIX_URL, endpoint);
+        let token = self.token().await?;
+        REQUEST_STATS.record_request(&url);
+        let response = self
+            .client
+            .get(&url)
+            .query(query)
+            .header("Client-Id", &self.client_id)
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            error!("Twitch API error: Status {}", response.status());
+            return Err(GameError::Other(format!(
+                "Twitch {} returned {}",
+                endpoint,
+                response.status()
+            )));
+        }
+
+        let response: HelixResponse<T> = response.json().await?;
+        Ok(response.data)
+    }
+
+    /// App access token, requested on first use
+    async fn token(&self) -> Result<&String> {
+        self.token
+            .get_or_try_init(|| async {
+                let url = "https://id.twitch.tv/oauth2/token";
+                REQUEST_STATS.record_request(url);
+                let response: TokenResponse = self
+                    .client
+                    .post(url)
+                    .form(&[
+                        ("client_id", self.client_id.as_str()),
+                        ("client_secret", self.client_secret.as_str()),
+                        ("grant_type", "client_credentials"),
+                    ])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(response.access_token)
+            })
+            .await
+    }
+}
@@ -0,0 +1,139 @@
+use crate::domain::storage::{Storage, StorageKeys};
+use crate::error::Result;
+use crate::infrastructure::clients::request_stats::REQUEST_STATS;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::{error, info};
+
+/// Search results to pick the trailer from
+const SEARCH_RESULTS: &str = "5";
+
+#[derive(Debug, Deserialize)]
+struct YoutubeSearchResponse {
+    #[serde(default)]
+    items: Vec<YoutubeSearchItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YoutubeSearchItem {
+    id: YoutubeVideoId,
+    snippet: YoutubeSnippet,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YoutubeVideoId {
+    video_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YoutubeSnippet {
+    title: String,
+    #[serde(default)]
+    thumbnails: YoutubeThumbnails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct YoutubeThumbnails {
+    high: Option<YoutubeThumbnail>,
+    default: Option<YoutubeThumbnail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YoutubeThumbnail {
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YoutubeTrailer {
+    pub video_id: String,
+    pub url: String,
+    pub thumbnail: Option<String>,
+}
+
+/// Finds game trailers through the YouTube Data API
+pub struct YoutubeClient {
+    client: Client,
+    api_key: String,
+    store: Arc<dyn Storage>,
+}
+
+impl YoutubeClient {
+    pub fn new(client: Client, api_key: String, store: Arc<dyn Storage>) -> Self {
+        Self {
+            client,
+            api_key,
+            store,
+        }
+    }
+
+    /// When the cached trailer of `title` was fetched
+    pub fn cached_at(&self, title: &str) -> Result<Option<SystemTime>> {
+        self.store.cached_at(StorageKeys::YOUTUBE_DIR, title)
+    }
+
+    /// Searches the trailer of `title`, preferring videos that call themselves
+    /// one. Cached data is used unless `refresh` is set.
+    pub async fn find_trailer(&self, title: &str, refresh: bool) -> Result<Option<YoutubeTrailer>> {
+        if !refresh {
+            if let Some(cached) = self.store.load_youtube_trailer(title)? {
+                info!("Using cached YouTube trailer for {}", title);
+                return Ok(Some(cached));
+            }
+        }
+
+        let search_url = "https://www.googleapis.com/youtube/v3/search";
+        REQUEST_STATS.record_request(search_url);
+        // The URL carries the API key, so it's left out of errors
+        let response = self
+            .client
+            .get(search_url)
+            .query(&[
+                ("key", self.api_key.as_str()),
+                ("part", "snippet"),
+                ("type", "video"),
+                ("maxResults", SEARCH_RESULTS),
+                ("q", &format!("{} official trailer", title)),
+            ])
+            .send()
+            .await
+            .map_err(|e| e.without_url())?;
+
+        if !response.status().is_success() {
+            error!("YouTube API error: Status {}", response.status());
+            return Ok(None);
+        }
+
+        let search: YoutubeSearchResponse = response.json().await.map_err(|e| e.without_url())?;
+        let is_trailer =
+            |item: &&YoutubeSearchItem| item.snippet.title.to_lowercase().contains("trailer");
+        let Some(item) = search
+            .items
+            .iter()
+            .find(is_trailer)
+            .or_else(|| search.items.first())
+        else {
+            info!("No YouTube trailer found for: {}", title);
+            return Ok(None);
+        };
+        let Some(video_id) = item.id.video_id.clone() else {
+            return Ok(None);
+        };
+
+        let thumbnails = &item.snippet.thumbnails;
+        let trailer = YoutubeTrailer {
+            url: format!("https://www.youtube.com/watch?v={}", video_id),
+            video_id,
+            thumbnail: thumbnails
+                .high
+                .as_ref()
+                .or(thumbnails.default.as_ref())
+                .map(|thumbnail| thumbnail.url.clone()),
+        };
+        self.store.save_youtube_trailer(title, &trailer)?;
+
+        Ok(Some(trailer))
+    }
+}
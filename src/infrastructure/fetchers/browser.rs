@@ -0,0 +1,53 @@
+use super::{Fetcher, Page};
+use crate::config::Website;
+use crate::error::{GameError, Result};
+use crate::infrastructure::REQUEST_STATS;
+use futures::future::BoxFuture;
+use tokio::process::Command;
+
+/// Fetches pages with a headless Chrome or Chromium, for sites that render
+/// their lists with JavaScript
+pub struct BrowserFetcher<'a> {
+    binary: &'a str,
+}
+
+impl<'a> BrowserFetcher<'a> {
+    pub fn new(binary: &'a str) -> Self {
+        Self { binary }
+    }
+
+    /// The page as the browser rendered it. The browser reports no status,
+    /// so pages it could load count as 200.
+    async fn render(&self, website: &Website, url: &str) -> Result<Page> {
+        REQUEST_STATS.record_request(url);
+        let mut command = Command::new(self.binary);
+        command.args(["--headless", "--disable-gpu", "--dump-dom"]);
+        if let Some(user_agent) = &website.user_agent {
+            command.arg(format!("--user-agent={}", user_agent));
+        }
+        if let Some(proxy) = &website.proxy {
+            command.arg(format!("--proxy-server={}", proxy));
+        }
+
+        let output = command.arg(url).output().await.map_err(|e| {
+            GameError::Other(format!("Can't run the browser {:?}: {}", self.binary, e))
+        })?;
+        if !output.status.success() {
+            return Err(GameError::Other(format!(
+                "The browser failed to load {}: {}",
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(Page {
+            status: 200,
+            body: String::from_utf8_lossy(&output.stdout).into_owned(),
+        })
+    }
+}
+
+impl Fetcher for BrowserFetcher<'_> {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(self.render(website, url))
+    }
+}
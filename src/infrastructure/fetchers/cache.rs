@@ -0,0 +1,82 @@
+use super::{Fetcher, Page};
+use crate::config::Website;
+use crate::domain::storage::Storage;
+use crate::error::Result;
+use chrono::{DateTime, Local, TimeDelta};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::info;
+
+/// A page as saved to the cache, keyed by its URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub fetched_at: String,
+    pub status: u16,
+    pub body: String,
+}
+
+impl CachedPage {
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        let max_age = TimeDelta::from_std(max_age).unwrap_or(TimeDelta::max_value());
+        DateTime::parse_from_rfc3339(&self.fetched_at)
+            .is_ok_and(|at| Local::now().signed_duration_since(at) < max_age)
+    }
+}
+
+/// Saves the pages another fetcher gets to the cache, and serves those fetched
+/// less than `max_age` ago from it, so changed selectors can be tried on the
+/// saved pages without downloading them again
+pub struct CachingFetcher<'a> {
+    inner: Box<dyn Fetcher + 'a>,
+    store: &'a dyn Storage,
+    /// Pages are only saved, never served, if unset
+    max_age: Option<Duration>,
+}
+
+impl<'a> CachingFetcher<'a> {
+    pub fn new(
+        inner: Box<dyn Fetcher + 'a>,
+        store: &'a dyn Storage,
+        max_age: Option<Duration>,
+    ) -> Self {
+        Self {
+            inner,
+            store,
+            max_age,
+        }
+    }
+
+    async fn fetch_cached(&self, website: &Website, url: &str) -> Result<Page> {
+        if let Some(max_age) = self.max_age {
+            if let Some(cached) = self.store.load_page(url)? {
+                if cached.is_fresh(max_age) {
+                    info!("Using the page of {} fetched at {}", url, cached.fetched_at);
+                    return Ok(Page {
+                        status: cached.status,
+                        body: cached.body,
+                    });
+                }
+            }
+        }
+
+        let page = self.inner.fetch(website, url).await?;
+        if (200..300).contains(&page.status) {
+            self.store.save_page(
+                url,
+                &CachedPage {
+                    fetched_at: Local::now().to_rfc3339(),
+                    status: page.status,
+                    body: page.body.clone(),
+                },
+            )?;
+        }
+        Ok(page)
+    }
+}
+
+impl Fetcher for CachingFetcher<'_> {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(self.fetch_cached(website, url))
+    }
+}
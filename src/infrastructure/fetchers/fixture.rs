@@ -0,0 +1,67 @@
+use super::{Fetcher, Page};
+use crate::config::Website;
+use crate::error::{GameError, Result};
+use crate::services::identity::slugify;
+use futures::future::BoxFuture;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File of the page at `url` in the fixtures `dir`
+pub fn fixture_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(format!("{}.html", slugify(url)))
+}
+
+/// Reads pages saved by `RecordingFetcher` instead of fetching them
+pub struct FixtureFetcher<'a> {
+    dir: &'a Path,
+}
+
+impl<'a> FixtureFetcher<'a> {
+    pub fn new(dir: &'a Path) -> Self {
+        Self { dir }
+    }
+
+    fn read(&self, url: &str) -> Result<Page> {
+        let path = fixture_path(self.dir, url);
+        let body = fs::read_to_string(&path).map_err(|e| {
+            GameError::Other(format!("No fixture {} of {}: {}", path.display(), url, e))
+        })?;
+        Ok(Page { status: 200, body })
+    }
+}
+
+impl Fetcher for FixtureFetcher<'_> {
+    fn fetch<'a>(&'a self, _website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(async move { self.read(url) })
+    }
+}
+
+/// Saves every page another fetcher gets to the fixtures `dir`
+pub struct RecordingFetcher<'a> {
+    inner: Box<dyn Fetcher + 'a>,
+    dir: &'a Path,
+}
+
+impl<'a> RecordingFetcher<'a> {
+    pub fn new(inner: Box<dyn Fetcher + 'a>, dir: &'a Path) -> Self {
+        Self { inner, dir }
+    }
+
+    async fn record(&self, website: &Website, url: &str) -> Result<Page> {
+        let page = self.inner.fetch(website, url).await?;
+        let path = fixture_path(self.dir, url);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, &page.body)?;
+        info!("Recorded {} to {}", url, path.display());
+        Ok(page)
+    }
+}
+
+impl Fetcher for RecordingFetcher<'_> {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(self.record(website, url))
+    }
+}
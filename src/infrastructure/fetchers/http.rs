@@ -0,0 +1,77 @@
+use super::{Fetcher, Page};
+use crate::config::{build_http_client, UserAgentPool, Website};
+use crate::error::Result;
+use crate::infrastructure::REQUEST_STATS;
+use futures::future::BoxFuture;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, RequestBuilder};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Fetches pages with plain GET requests
+pub struct HttpFetcher {
+    client: Client,
+    user_agents: UserAgentPool,
+    /// Clients of the sites with their own proxy, keyed by proxy URL
+    proxy_clients: Mutex<HashMap<String, Client>>,
+}
+
+impl HttpFetcher {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            user_agents: UserAgentPool::default(),
+            proxy_clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends every request with the next user agent of `user_agents`, unless
+    /// the site configures its own
+    pub fn with_user_agents(mut self, user_agents: UserAgentPool) -> Self {
+        self.user_agents = user_agents;
+        self
+    }
+
+    /// Request to a page of `website`, through its proxy and with its user
+    /// agent if it has one
+    pub fn get(&self, website: &Website, url: &str) -> Result<RequestBuilder> {
+        let client = match &website.proxy {
+            Some(proxy) => {
+                let mut clients = self.proxy_clients.lock().unwrap();
+                match clients.get(proxy) {
+                    Some(client) => client.clone(),
+                    None => {
+                        let client = build_http_client(Some(proxy))?;
+                        clients.insert(proxy.clone(), client.clone());
+                        client
+                    }
+                }
+            }
+            None => self.client.clone(),
+        };
+
+        let request = client.get(url);
+        let user_agent = website
+            .user_agent
+            .as_deref()
+            .or_else(|| self.user_agents.next());
+        Ok(match user_agent {
+            Some(user_agent) => request.header(USER_AGENT, user_agent),
+            None => request,
+        })
+    }
+
+    async fn get_page(&self, website: &Website, url: &str) -> Result<Page> {
+        REQUEST_STATS.record_request(url);
+        let response = self.get(website, url)?.send().await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        Ok(Page { status, body })
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(self.get_page(website, url))
+    }
+}
@@ -0,0 +1,27 @@
+use crate::config::Website;
+use crate::error::Result;
+use futures::future::BoxFuture;
+
+pub(crate) mod browser;
+pub(crate) mod cache;
+pub(crate) mod fixture;
+pub(crate) mod http;
+pub(crate) mod wayback;
+
+/// A fetched page of a list article
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Gets the pages of a website, from the site itself or from elsewhere
+pub trait Fetcher: Send + Sync {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>>;
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for &F {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        (**self).fetch(website, url)
+    }
+}
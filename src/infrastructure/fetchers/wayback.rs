@@ -0,0 +1,71 @@
+use super::http::HttpFetcher;
+use super::{Fetcher, Page};
+use crate::config::Website;
+use crate::error::{GameError, Result};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+const AVAILABILITY_API: &str = "https://archive.org/wayback/available";
+
+/// Fetches pages from their Wayback Machine capture, for lists that went
+/// offline or changed since
+pub struct WaybackFetcher<'a> {
+    http: &'a HttpFetcher,
+    /// Capture closest to this time, e.g. "20240101", the latest if unset
+    timestamp: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct Availability {
+    archived_snapshots: Snapshots,
+}
+
+#[derive(Deserialize)]
+struct Snapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    available: bool,
+    timestamp: String,
+}
+
+impl<'a> WaybackFetcher<'a> {
+    pub fn new(http: &'a HttpFetcher, timestamp: Option<&'a str>) -> Self {
+        Self { http, timestamp }
+    }
+
+    async fn fetch_capture(&self, website: &Website, url: &str) -> Result<Page> {
+        let mut query = vec![("url", url)];
+        query.extend(self.timestamp.map(|timestamp| ("timestamp", timestamp)));
+        let availability: Availability = self
+            .http
+            .get(website, AVAILABILITY_API)?
+            .query(&query)
+            .send()
+            .await?
+            .json()
+            .await?;
+        let snapshot = availability
+            .archived_snapshots
+            .closest
+            .filter(|snapshot| snapshot.available)
+            .ok_or_else(|| {
+                GameError::Other(format!("The Wayback Machine has no capture of {}", url))
+            })?;
+
+        // The id_ flag serves the page as captured, without the archive's toolbar
+        let capture = format!(
+            "https://web.archive.org/web/{}id_/{}",
+            snapshot.timestamp, url
+        );
+        self.http.fetch(website, &capture).await
+    }
+}
+
+impl Fetcher for WaybackFetcher<'_> {
+    fn fetch<'a>(&'a self, website: &'a Website, url: &'a str) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(self.fetch_capture(website, url))
+    }
+}
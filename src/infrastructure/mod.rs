@@ -1,13 +1,34 @@
 mod clients;
+mod fetchers;
 mod scrapers;
 mod storage;
 
 pub use clients::{
-    rawg::{RawgClient, RawgGameDetailed},
-    steam::{ExtendedPlatforms, SteamApp, SteamClient, SteamDeckVerifiedResponse, StoreInfo},
+    backloggd::{BackloggdClient, BackloggdInfo},
+    rawg::{RawgClient, RawgGameDetailed, RawgMiss, RawgUsage, DEFAULT_RAWG_MIN_SIMILARITY},
+    reddit::RedditClient,
+    request_stats::{RequestReport, REQUEST_STATS},
+    robots::RobotsChecker,
+    steam::{
+        ExtendedPlatforms, GameFeatures, ReviewTrend, SteamApp, SteamAppListCache, SteamClient,
+        SteamDeckVerifiedResponse, StoreInfo, DECK_STATUS_ERROR, DECK_STATUS_UNKNOWN,
+        DECK_STATUS_VERIFIED,
+    },
+    twitch::{TwitchClient, TwitchHistory, TwitchViewers},
+    youtube::{YoutubeClient, YoutubeTrailer},
+};
+pub use fetchers::{
+    browser::BrowserFetcher,
+    cache::{CachedPage, CachingFetcher},
+    fixture::{FixtureFetcher, RecordingFetcher},
+    http::HttpFetcher,
+    wayback::WaybackFetcher,
+    Fetcher,
 };
 pub use scrapers::{
-    eurogamer::EurogamerScraper, ign::IGNScraper, pcgamer::PCGamerScraper, polygon::PolygonScraper,
-    polygon_ps5_top25::PolygonPS5Top25, rockpapershotgun::RPSScraper, Selectors, WebsiteScraper,
+    backloggd::BackloggdScraper, eurogamer::EurogamerScraper, ign::IGNScraper,
+    local::read_local_list, pcgamer::PCGamerScraper, polygon::PolygonScraper,
+    polygon_ps5_top25::PolygonPS5Top25, rockpapershotgun::RPSScraper, ExtractedGame, Selectors,
+    WebsiteScraper,
 };
-pub use storage::fs_store::FileSystemStore;
+pub use storage::fs_store::{CacheEntry, FileSystemStore};
@@ -0,0 +1,41 @@
+use super::{ExtractedGame, Selectors, WebsiteScraper};
+use crate::domain::numbers::rank_from_index;
+use crate::error::Result;
+use scraper::{ElementRef, Html};
+
+/// Backloggd's popular and top rated pages, a grid of covers in rank order
+pub struct BackloggdScraper;
+
+impl WebsiteScraper for BackloggdScraper {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
+        let mut games = Vec::new();
+
+        // Covers only carry the name in their alt text
+        let names: Vec<(String, ElementRef)> = document
+            .select(&selectors.name)
+            .filter_map(|el| {
+                let text = el.text().collect::<String>().trim().to_string();
+                if !text.is_empty() {
+                    return Some((text, el));
+                }
+                el.value()
+                    .attr("alt")
+                    .or(el.value().attr("title"))
+                    .map(|name| (name.trim().to_string(), el))
+            })
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+
+        for (i, (name, element)) in names.into_iter().enumerate() {
+            if let Some(rank) = rank_from_index(i) {
+                games.push(ExtractedGame::new(name, rank, element));
+            }
+        }
+
+        Ok(games)
+    }
+}
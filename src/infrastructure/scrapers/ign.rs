@@ -1,4 +1,4 @@
-use super::{Selectors, WebsiteScraper};
+use super::{ExtractedGame, Selectors, WebsiteScraper};
 use crate::error::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -6,21 +6,22 @@ use scraper::Html;
 
 pub struct IGNScraper;
 
-static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)\.\s*(.+)").unwrap());
+static RANK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)\.").unwrap());
+static NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+\.\s*(.+)").unwrap());
 
 impl WebsiteScraper for IGNScraper {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>> {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
         let mut games = Vec::new();
 
         for element in document.select(&selectors.name) {
             let text = element.text().collect::<String>();
-            if let Some(caps) = REGEX.captures(&text) {
-                if let (Some(rank_str), Some(name_str)) = (caps.get(1), caps.get(2)) {
-                    if let Ok(rank) = rank_str.as_str().parse::<u64>() {
-                        if (1..=100).contains(&rank) {
-                            games.push((name_str.as_str().trim().to_string(), rank));
-                        }
-                    }
+            if let Some((name, rank)) = selectors.extract(&text, &RANK_REGEX, &NAME_REGEX) {
+                if (1..=100).contains(&rank) {
+                    games.push(ExtractedGame::new(name, rank, element));
                 }
             }
         }
@@ -0,0 +1,96 @@
+use crate::domain::numbers::{is_valid_rank, parse_rank, rank_from_index, Rank};
+use crate::error::{GameError, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct LocalEntry {
+    name: String,
+    rank: Option<Rank>,
+}
+
+/// Reads a list kept on disk, either a JSON array of `{name, rank}` objects or
+/// a CSV file with `name,rank` rows. Entries without a rank are ranked by their
+/// position in the file.
+pub fn read_local_list(path: &Path) -> Result<Vec<(String, Rank)>> {
+    let content = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+
+    let entries = if is_json {
+        serde_json::from_str::<Vec<LocalEntry>>(&content)?
+    } else {
+        parse_csv(&content)?
+    };
+
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let rank = match entry.rank {
+                Some(rank) if is_valid_rank(rank) => Some(rank),
+                Some(_) => None,
+                None => rank_from_index(i),
+            };
+            match rank {
+                Some(rank) => Ok((entry.name, rank)),
+                None => Err(GameError::Other(format!(
+                    "Invalid rank of {} in {:?}",
+                    entry.name, path
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Rows are `name,rank` or just `name`. Names containing commas are quoted,
+/// and a `name,rank` header is skipped.
+fn parse_csv(content: &str) -> Result<Vec<LocalEntry>> {
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, rest) = split_name(line);
+        if i == 0 && name.eq_ignore_ascii_case("name") {
+            continue;
+        }
+
+        let rank = match rest.map(str::trim).filter(|rank| !rank.is_empty()) {
+            Some(rank) => Some(parse_rank(rank).ok_or_else(|| {
+                GameError::Other(format!("Invalid rank {:?} on line {}", rank, i + 1))
+            })?),
+            None => None,
+        };
+        entries.push(LocalEntry { name, rank });
+    }
+    Ok(entries)
+}
+
+/// Splits the name field off a CSV row, returning the rest after its comma
+fn split_name(line: &str) -> (String, Option<&str>) {
+    let Some(quoted) = line.strip_prefix('"') else {
+        return match line.split_once(',') {
+            Some((name, rest)) => (name.trim().to_string(), Some(rest)),
+            None => (line.to_string(), None),
+        };
+    };
+
+    // A doubled quote inside a quoted field is a literal quote
+    let mut name = String::new();
+    let mut chars = quoted.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' {
+            name.push(c);
+        } else if chars.next_if(|&(_, next)| next == '"').is_some() {
+            name.push('"');
+        } else {
+            let rest = &quoted[i + 1..];
+            return (name, rest.trim_start().strip_prefix(','));
+        }
+    }
+    (name, None)
+}
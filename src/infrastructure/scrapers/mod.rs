@@ -1,20 +1,92 @@
-use crate::error::Result;
-use scraper::{Html, Selector};
+use crate::domain::numbers::{parse_rank, Rank};
+use crate::error::{GameError, Result};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
 
+pub(crate) mod backloggd;
 pub(crate) mod eurogamer;
 pub(crate) mod ign;
+pub(crate) mod local;
 pub(crate) mod pcgamer;
 pub(crate) mod polygon;
 pub(crate) mod polygon_ps5_top25;
 pub(crate) mod rockpapershotgun;
 
+/// A game of a list and the name element it was extracted from
+pub struct ExtractedGame<'a> {
+    pub name: String,
+    pub rank: Rank,
+    pub element: ElementRef<'a>,
+}
+
+impl<'a> ExtractedGame<'a> {
+    pub fn new(name: String, rank: Rank, element: ElementRef<'a>) -> Self {
+        Self {
+            name,
+            rank,
+            element,
+        }
+    }
+}
+
 pub trait WebsiteScraper {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>>;
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>>;
+
+    /// The reviewer's blurb about each of `games`: the first element matching
+    /// the blurb selector after the game's name, before the next name
+    fn extract_blurbs(
+        &self,
+        document: &Html,
+        selectors: &Selectors,
+        games: &[ExtractedGame],
+    ) -> Vec<Option<String>> {
+        let Some(blurb) = &selectors.blurb else {
+            return vec![None; games.len()];
+        };
+
+        // Blurbs by the name element they follow, in document order
+        let mut blurbs: Vec<(ElementRef, Option<String>)> = Vec::new();
+        for element in document
+            .root_element()
+            .descendants()
+            .filter_map(ElementRef::wrap)
+        {
+            if selectors.name.matches(&element) {
+                blurbs.push((element, None));
+            } else if blurb.matches(&element) {
+                if let Some((_, text @ None)) = blurbs.last_mut() {
+                    let paragraph = element.text().collect::<Vec<_>>().join(" ");
+                    let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+                    *text = (!paragraph.is_empty()).then_some(paragraph);
+                }
+            }
+        }
+
+        games
+            .iter()
+            .map(|game| {
+                blurbs
+                    .iter()
+                    .find(|(candidate, _)| candidate.id() == game.element.id())
+                    .and_then(|(_, text)| text.clone())
+            })
+            .collect()
+    }
 }
 
 pub struct Selectors {
     pub name: Selector,
     pub rank: Option<Selector>,
+    /// `rank_regex` and `name_regex` of the website, replacing the built-in
+    /// regexes of the scraper
+    pub rank_regex: Option<Regex>,
+    pub name_regex: Option<Regex>,
+    /// `blurb_selector` of the website, for the reviewer's paragraph per entry
+    pub blurb: Option<Selector>,
 }
 
 impl Selectors {
@@ -33,6 +105,89 @@ impl Selectors {
             None
         };
 
-        Ok(Self { name, rank })
+        Ok(Self {
+            name,
+            rank,
+            rank_regex: None,
+            name_regex: None,
+            blurb: None,
+        })
+    }
+
+    pub fn with_blurb(mut self, blurb_selector: Option<&str>) -> Result<Self> {
+        self.blurb = blurb_selector
+            .map(|selector| {
+                Selector::parse(selector).map_err(|e| GameError::Selector(e.to_string()))
+            })
+            .transpose()?;
+        Ok(self)
+    }
+
+    pub fn with_regexes(
+        mut self,
+        rank_regex: Option<&str>,
+        name_regex: Option<&str>,
+    ) -> Result<Self> {
+        let compile =
+            |pattern: &str| Regex::new(pattern).map_err(|e| GameError::Selector(e.to_string()));
+        self.rank_regex = rank_regex.map(compile).transpose()?;
+        self.name_regex = name_regex.map(compile).transpose()?;
+        Ok(self)
+    }
+
+    /// Finds rank and name in `text` with the website's regexes, falling back
+    /// to the scraper's `rank_regex` and `name_regex`
+    pub fn extract(
+        &self,
+        text: &str,
+        rank_regex: &Regex,
+        name_regex: &Regex,
+    ) -> Option<(String, Rank)> {
+        let rank_regex = self.rank_regex.as_ref().unwrap_or(rank_regex);
+        let name_regex = self.name_regex.as_ref().unwrap_or(name_regex);
+        let rank = parse_rank(first_group(rank_regex, text)?)?;
+        let name = first_group(name_regex, text)?.trim();
+        (!name.is_empty()).then(|| (name.to_string(), rank))
+    }
+}
+
+/// The first capture group of `regex` in `text`, the whole match if the regex
+/// has no groups
+fn first_group<'a>(regex: &Regex, text: &'a str) -> Option<&'a str> {
+    let caps = regex.captures(text)?;
+    caps.get(1).or_else(|| caps.get(0)).map(|m| m.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ign::IGNScraper;
+    use super::*;
+
+    const IGN_PAGE: &str = include_str!(
+        "../../../tests/fixtures/pages/https-www-ign-com-articles-the-best-100-video-games-of-all-time.html"
+    );
+
+    #[test]
+    fn blurbs_follow_the_element_each_game_was_extracted_from() {
+        let document = Html::parse_document(IGN_PAGE);
+        let selectors = Selectors::new("h2[data-cy='title2'] strong", "")
+            .unwrap()
+            .with_blurb(Some("p"))
+            .unwrap();
+
+        let games = IGNScraper.extract_games(&document, &selectors).unwrap();
+        let blurbs = IGNScraper.extract_blurbs(&document, &selectors, &games);
+
+        let names: Vec<&str> = games.iter().map(|game| game.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "The Legend of Zelda: Breath of the Wild",
+                "Portal 2",
+                "Portal"
+            ]
+        );
+        assert_eq!(blurbs[1].as_deref(), Some("Co-op puzzling at its best."));
+        assert_eq!(blurbs[2].as_deref(), Some("The cake is a lie."));
     }
 }
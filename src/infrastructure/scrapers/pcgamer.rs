@@ -1,4 +1,4 @@
-use super::{Selectors, WebsiteScraper};
+use super::{ExtractedGame, Selectors, WebsiteScraper};
 use crate::error::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -6,7 +6,10 @@ use scraper::Html;
 
 pub struct PCGamerScraper;
 
-static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)-([a-zA-Z0-9-]+?)(-\d+)?$").unwrap());
+// Element IDs look like "12-baldurs-gate-3", sometimes with a "-2" suffix
+static RANK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)-").unwrap());
+static NAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\d+-([a-zA-Z0-9-]+?)(?:-\d+)?$").unwrap());
 
 fn clean_name(raw_name: &str) -> String {
     raw_name
@@ -16,24 +19,18 @@ fn clean_name(raw_name: &str) -> String {
 }
 
 impl WebsiteScraper for PCGamerScraper {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>> {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
         let mut games = Vec::new();
 
         for element in document.select(&selectors.name) {
             if let Some(id) = element.value().attr("id") {
-                // Match the ID using regex
-                if let Some(caps) = REGEX.captures(id) {
-                    let rank_str = caps.get(1).map(|m| m.as_str());
-                    let name_str = caps.get(2).map(|m| m.as_str());
-
-                    // Parse rank and clean name
-                    if let (Some(rank_str), Some(name_str)) = (rank_str, name_str) {
-                        if let Ok(rank) = rank_str.parse::<u64>() {
-                            if (1..=100).contains(&rank) {
-                                let clean_name = clean_name(name_str);
-                                games.push((clean_name, rank));
-                            }
-                        }
+                if let Some((name, rank)) = selectors.extract(id, &RANK_REGEX, &NAME_REGEX) {
+                    if (1..=100).contains(&rank) {
+                        games.push(ExtractedGame::new(clean_name(&name), rank, element));
                     }
                 }
             }
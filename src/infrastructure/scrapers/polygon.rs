@@ -1,4 +1,5 @@
-use super::{Selectors, WebsiteScraper};
+use super::{ExtractedGame, Selectors, WebsiteScraper};
+use crate::domain::numbers::parse_rank;
 use crate::error::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -9,16 +10,24 @@ pub struct PolygonScraper;
 static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)\.\s*(.+)").unwrap());
 
 impl WebsiteScraper for PolygonScraper {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>> {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
         let mut games = Vec::new();
 
         for element in document.select(&selectors.name) {
             let text = element.text().collect::<String>();
             if let Some(caps) = REGEX.captures(&text) {
                 if let (Some(rank_str), Some(name)) = (caps.get(1), caps.get(2)) {
-                    if let Ok(rank) = rank_str.as_str().parse::<u64>() {
+                    if let Some(rank) = parse_rank(rank_str.as_str()) {
                         if (1..=50).contains(&rank) {
-                            games.push((name.as_str().trim().to_string(), rank));
+                            games.push(ExtractedGame::new(
+                                name.as_str().trim().to_string(),
+                                rank,
+                                element,
+                            ));
                         }
                     }
                 }
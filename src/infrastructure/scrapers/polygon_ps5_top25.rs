@@ -1,20 +1,27 @@
-use super::{Selectors, WebsiteScraper};
+use super::{ExtractedGame, Selectors, WebsiteScraper};
+use crate::domain::numbers::rank_from_index;
 use crate::error::Result;
-use scraper::Html;
+use scraper::{ElementRef, Html};
 
 pub struct PolygonPS5Top25;
 
 impl WebsiteScraper for PolygonPS5Top25 {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>> {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
         let mut games = Vec::new();
 
-        let names: Vec<String> = document
+        let names: Vec<(String, ElementRef)> = document
             .select(&selectors.name)
-            .map(|el| el.text().collect::<String>().trim().to_string())
+            .map(|el| (el.text().collect::<String>().trim().to_string(), el))
             .collect();
 
-        for (i, name) in names.into_iter().enumerate() {
-            games.push((name, (i + 1) as u64));
+        for (i, (name, element)) in names.into_iter().enumerate() {
+            if let Some(rank) = rank_from_index(i) {
+                games.push(ExtractedGame::new(name, rank, element));
+            }
         }
 
         Ok(games)
@@ -1,30 +1,35 @@
-use super::{Selectors, WebsiteScraper};
+use super::{ExtractedGame, Selectors, WebsiteScraper};
+use crate::domain::numbers::{parse_rank, Rank};
 use crate::error::Result;
-use scraper::Html;
+use scraper::{ElementRef, Html};
 
 pub struct RPSScraper;
 
 impl WebsiteScraper for RPSScraper {
-    fn extract_games(&self, document: &Html, selectors: &Selectors) -> Result<Vec<(String, u64)>> {
+    fn extract_games<'a>(
+        &self,
+        document: &'a Html,
+        selectors: &Selectors,
+    ) -> Result<Vec<ExtractedGame<'a>>> {
         let mut games = Vec::new();
 
-        let names: Vec<String> = document
+        let names: Vec<(String, ElementRef)> = document
             .select(&selectors.name)
-            .map(|el| el.text().collect::<String>().trim().to_string())
+            .map(|el| (el.text().collect::<String>().trim().to_string(), el))
             .collect();
 
-        let ranks: Vec<u64> = document
+        let ranks: Vec<Rank> = document
             .select(selectors.rank.as_ref().unwrap())
             .filter_map(|el| {
                 let rank_str = el.text().collect::<String>();
-                rank_str.trim().parse::<u64>().ok()
+                parse_rank(&rank_str)
             })
             .collect();
 
-        for (i, name) in names.into_iter().enumerate() {
+        for (i, (name, element)) in names.into_iter().enumerate() {
             if let Some(&rank) = ranks.get(i) {
                 if (1..=100).contains(&rank) {
-                    games.push((name, rank));
+                    games.push(ExtractedGame::new(name, rank, element));
                 }
             }
         }
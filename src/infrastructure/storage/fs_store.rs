@@ -1,17 +1,70 @@
+use crate::domain::numbers::Score;
 use crate::domain::storage::{Storage, StorageKeys};
-use crate::domain::{Game, Manifest};
-use crate::error::Result;
-use crate::infrastructure::{RawgGameDetailed, StoreInfo};
-use crate::services::matching::{GameWithSteamId, IndexedGames};
-use crate::services::merging::MergedGame;
+use crate::domain::{Game, Manifest, RunRecord};
+use crate::error::{GameError, Result};
+use crate::infrastructure::{
+    BackloggdInfo, CachedPage, RawgGameDetailed, RawgMiss, RawgUsage, RequestReport,
+    SteamAppListCache, SteamDeckVerifiedResponse, StoreInfo, TwitchHistory, YoutubeTrailer,
+};
+use crate::services::identity::slugify;
+use crate::services::matching::{FilteredApp, GameWithSteamId, IndexedGames, NameFst};
+use crate::services::merging::{MergeReport, MergedGame};
 use crate::services::scraping::WebsiteGames;
-use std::fs;
-use std::path::PathBuf;
+use crate::services::text_utils::TitleNormalizer;
+use memmap2::Mmap;
+use std::collections::BTreeMap;
+use std::fs::{self, TryLockError};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Frame header every zstd stream starts with
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_LEVEL: i32 = 3;
+
+/// Locked while a run uses the dir, see `FileSystemStore::lock`
+const LOCK_FILE: &str = ".gameharmony.lock";
+
+/// Header of binary cache files, followed by the schema version
+const BINARY_MAGIC: &[u8; 4] = b"GHIX";
+/// Bump whenever the layout of a binary cached type changes
+const BINARY_SCHEMA_VERSION: u32 = 2;
+
+/// A single file inside the cache directory
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+impl CacheEntry {
+    /// Storage key the entry was written under
+    pub fn key(&self) -> Option<&str> {
+        let name = self.path.file_name()?.to_str()?;
+        let name = name.strip_suffix(".zst").unwrap_or(name);
+        name.strip_suffix(".json")
+    }
+
+    /// Cache directory the entry lives in, like `steam_apps`
+    pub fn dir(&self) -> Option<&str> {
+        self.path.parent()?.file_name()?.to_str()
+    }
+}
+
+/// Locks of `FileSystemStore::lock`, released on drop
+pub struct StoreLock {
+    _files: Vec<fs::File>,
+}
 
-#[derive(Clone)]
 pub struct FileSystemStore {
     data_dir: PathBuf,
     cache_dir: PathBuf,
+    compress: bool,
+    /// Key of the current run, see `begin_run`
+    run_key: Mutex<Option<String>>,
 }
 
 impl FileSystemStore {
@@ -19,7 +72,79 @@ impl FileSystemStore {
         Self {
             data_dir: data_dir.into(),
             cache_dir: cache_dir.into(),
+            compress: false,
+            run_key: Mutex::new(None),
+        }
+    }
+
+    /// Takes advisory locks on the data and cache dirs, held until the returned
+    /// guard is dropped. Fails if another run holds them, unless `wait` is set.
+    pub fn lock(&self, wait: bool) -> Result<StoreLock> {
+        let mut files = Vec::new();
+        for dir in [&self.data_dir, &self.cache_dir] {
+            self.ensure_dir(dir)?;
+            let path = dir.join(LOCK_FILE);
+            let file = fs::File::create(&path)?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) if wait => {
+                    info!("Waiting for the run holding {:?}", path);
+                    file.lock()?;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(GameError::Other(format!(
+                        "{:?} is locked by another run, pass --wait-for-lock to wait for it",
+                        path
+                    )));
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+            files.push(file);
+        }
+        Ok(StoreLock { _files: files })
+    }
+
+    /// Writes cache entries zstd compressed. Entries in the data dir are always
+    /// written as plain JSON, and reading detects both formats.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Lists all files in the cache directory, or only in `subdir` if given
+    pub fn cache_entries(&self, subdir: Option<&str>) -> Result<Vec<CacheEntry>> {
+        let dir = match subdir {
+            Some(dir) => self.cache_dir.join(dir),
+            None => self.cache_dir.clone(),
+        };
+
+        let mut entries = Vec::new();
+        if dir.exists() {
+            Self::collect_entries(&dir, &mut entries)?;
+        }
+        Ok(entries)
+    }
+
+    pub fn remove_cache_entry(&self, entry: &CacheEntry) -> Result<()> {
+        fs::remove_file(&entry.path)?;
+        Ok(())
+    }
+
+    fn collect_entries(dir: &Path, entries: &mut Vec<CacheEntry>) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                Self::collect_entries(&entry.path(), entries)?;
+            } else if entry.file_name() != LOCK_FILE {
+                entries.push(CacheEntry {
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified()?,
+                });
+            }
         }
+        Ok(())
     }
 
     fn get_path_for_key(&self, key: &str, subdir: Option<&str>, use_data_dir: bool) -> PathBuf {
@@ -36,6 +161,34 @@ impl FileSystemStore {
         }
     }
 
+    /// Writes a file of the current run to its directory under `runs/`, and
+    /// copies it to the stable path in the data dir
+    fn write_run_file<T: serde::Serialize + ?Sized>(&self, key: &str, data: &T) -> Result<()> {
+        let run_key = self.run_key.lock().unwrap().clone();
+        let Some(run_key) = run_key else {
+            return self.write_json_file(key, None, data, true);
+        };
+
+        let subdir = format!("{}/{}", StorageKeys::RUN_DIRS, run_key);
+        self.write_json_file(key, Some(&subdir), data, true)?;
+        fs::copy(
+            self.get_path_for_key(key, Some(&subdir), true),
+            self.get_path_for_key(key, None, true),
+        )?;
+        Ok(())
+    }
+
+    /// Writes a snapshot of a stage of the current run to its directory under
+    /// `runs/`, so each run keeps the data it was built from
+    fn write_run_snapshot<T: serde::Serialize + ?Sized>(&self, key: &str, data: &T) -> Result<()> {
+        let run_key = self.run_key.lock().unwrap().clone();
+        let Some(run_key) = run_key else {
+            return Ok(());
+        };
+        let subdir = format!("{}/{}", StorageKeys::RUN_DIRS, run_key);
+        self.write_json_file(key, Some(&subdir), data, true)
+    }
+
     fn ensure_dir(&self, dir: &PathBuf) -> Result<()> {
         if !dir.exists() {
             fs::create_dir_all(dir)?;
@@ -61,11 +214,97 @@ impl FileSystemStore {
         }
 
         let path = self.get_path_for_key(key, subdir, use_data_dir);
-        let content = serde_json::to_string_pretty(data)?;
-        fs::write(path, content)?;
+        let compressed_path = path.with_extension("json.zst");
+
+        if self.compress && !use_data_dir {
+            let content = serde_json::to_vec(data)?;
+            let compressed = zstd::encode_all(content.as_slice(), ZSTD_LEVEL)?;
+            fs::write(compressed_path, compressed)?;
+            // Don't leave a stale uncompressed entry around
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        } else {
+            // Streamed, so large manifests aren't held in memory as a string
+            let mut writer = BufWriter::new(fs::File::create(&path)?);
+            serde_json::to_writer_pretty(&mut writer, data)?;
+            writer.flush()?;
+            if compressed_path.exists() {
+                fs::remove_file(compressed_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn name_fst_path(&self, version: u32) -> PathBuf {
+        self.cache_dir
+            .join(StorageKeys::STEAM_APPS_DIR)
+            .join(format!(
+                "{}_v{}.fst",
+                normalized_key(StorageKeys::STEAM_APPS_INDEX),
+                version
+            ))
+    }
+
+    fn binary_path(&self, key: &str, subdir: &str) -> PathBuf {
+        self.cache_dir.join(subdir).join(format!("{}.bin", key))
+    }
+
+    fn write_binary_file<T: serde::Serialize>(
+        &self,
+        key: &str,
+        subdir: &str,
+        data: &T,
+    ) -> Result<()> {
+        self.ensure_dir(&self.cache_dir.join(subdir))?;
+
+        let mut content = BINARY_MAGIC.to_vec();
+        content.extend_from_slice(&BINARY_SCHEMA_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut content, data)
+            .map_err(|e| GameError::Other(format!("Binary serialization failed: {}", e)))?;
+
+        fs::write(self.binary_path(key, subdir), content)?;
         Ok(())
     }
 
+    /// Reads a binary cache file. Files with an unknown header or schema version
+    /// are treated as missing, so callers rebuild or fall back to JSON.
+    fn read_binary_file<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+        subdir: &str,
+    ) -> Result<Option<T>> {
+        let path = self.binary_path(key, subdir);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read(&path)?;
+        let header_len = BINARY_MAGIC.len() + 4;
+        if content.len() < header_len || !content.starts_with(BINARY_MAGIC) {
+            warn!("Ignoring binary cache file with unknown format: {:?}", path);
+            return Ok(None);
+        }
+
+        let version =
+            u32::from_le_bytes(content[BINARY_MAGIC.len()..header_len].try_into().unwrap());
+        if version != BINARY_SCHEMA_VERSION {
+            warn!(
+                "Ignoring binary cache file {:?} with schema version {} (expected {})",
+                path, version, BINARY_SCHEMA_VERSION
+            );
+            return Ok(None);
+        }
+
+        match bincode::deserialize(&content[header_len..]) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) => {
+                warn!("Failed to decode binary cache file {:?}: {}", path, e);
+                Ok(None)
+            }
+        }
+    }
+
     fn read_json_file<T: serde::de::DeserializeOwned>(
         &self,
         key: &str,
@@ -73,30 +312,87 @@ impl FileSystemStore {
         use_data_dir: bool,
     ) -> Result<Option<T>> {
         let path = self.get_path_for_key(key, subdir, use_data_dir);
-        if path.exists() {
-            let content = fs::read_to_string(path)?;
-            Ok(Some(serde_json::from_str(&content)?))
+        let compressed_path = path.with_extension("json.zst");
+
+        let content = if compressed_path.exists() {
+            fs::read(compressed_path)?
+        } else if path.exists() {
+            fs::read(path)?
+        } else {
+            return Ok(None);
+        };
+
+        if content.starts_with(&ZSTD_MAGIC) {
+            let decompressed = zstd::decode_all(content.as_slice())?;
+            Ok(Some(serde_json::from_slice(&decompressed)?))
         } else {
-            Ok(None)
+            Ok(Some(serde_json::from_slice(&content)?))
         }
     }
 }
 
+/// Key of a cache built from normalized titles, which only holds for the
+/// normalization rules it was built with
+fn normalized_key(key: &str) -> String {
+    format!("{}_{:016x}", key, TitleNormalizer::rules_fingerprint())
+}
+
 impl Storage for FileSystemStore {
     fn save_indexed_games(&self, indexed_games: &IndexedGames) -> Result<()> {
-        self.write_json_file(
-            StorageKeys::STEAM_APPS_INDEX,
-            Some(StorageKeys::STEAM_APPS_DIR),
+        self.write_binary_file(
+            &normalized_key(StorageKeys::STEAM_APPS_INDEX),
+            StorageKeys::STEAM_APPS_DIR,
             indexed_games,
-            false,
         )
     }
 
     fn load_indexed_games(&self) -> Result<Option<IndexedGames>> {
-        self.read_json_file(
-            StorageKeys::STEAM_APPS_INDEX,
-            Some(StorageKeys::STEAM_APPS_DIR),
-            false,
+        // Indices written before the binary format can't tell which rules
+        // they were built with, so they are rebuilt
+        self.read_binary_file(
+            &normalized_key(StorageKeys::STEAM_APPS_INDEX),
+            StorageKeys::STEAM_APPS_DIR,
+        )
+    }
+
+    fn load_steam_app_list(&self) -> Result<Option<SteamAppListCache>> {
+        self.read_binary_file(StorageKeys::STEAM_APP_LIST, StorageKeys::STEAM_APPS_DIR)
+    }
+
+    fn load_name_fst(&self, version: u32) -> Result<Option<NameFst>> {
+        let path = self.name_fst_path(version);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = fs::File::open(&path)?;
+        // SAFETY: save_name_fst never writes to an existing FST in place, it
+        // renames a new file over it, so the mapped inode stays unchanged
+        // even if another run replaces the FST while this one reads it
+        let mmap = unsafe { Mmap::map(&file)? };
+        match NameFst::new(mmap) {
+            Ok(map) => Ok(Some(map)),
+            Err(e) => {
+                warn!("Ignoring unreadable title FST {:?}: {}", path, e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn save_name_fst(&self, version: u32, fst: &[u8]) -> Result<()> {
+        self.ensure_dir(&self.cache_dir.join(StorageKeys::STEAM_APPS_DIR))?;
+        let path = self.name_fst_path(version);
+        let tmp = path.with_extension("fst.tmp");
+        fs::write(&tmp, fst)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn save_steam_app_list(&self, list: &SteamAppListCache) -> Result<()> {
+        self.write_binary_file(
+            StorageKeys::STEAM_APP_LIST,
+            StorageKeys::STEAM_APPS_DIR,
+            list,
         )
     }
 
@@ -105,7 +401,7 @@ impl Storage for FileSystemStore {
             let filename = game.source.replace('/', "_");
             self.write_json_file(&filename, Some(StorageKeys::SOURCES_DIR), game, false)?;
         }
-        Ok(())
+        self.write_run_snapshot(StorageKeys::SOURCES_DIR, website_games)
     }
 
     fn load_website_games(&self, url: String) -> Result<Option<WebsiteGames>> {
@@ -113,9 +409,17 @@ impl Storage for FileSystemStore {
         self.read_json_file(&filename, Some(StorageKeys::SOURCES_DIR), false)
     }
 
+    fn load_page(&self, url: &str) -> Result<Option<CachedPage>> {
+        self.read_json_file(&slugify(url), Some(StorageKeys::PAGES_DIR), false)
+    }
+
+    fn save_page(&self, url: &str, page: &CachedPage) -> Result<()> {
+        self.write_json_file(&slugify(url), Some(StorageKeys::PAGES_DIR), page, false)
+    }
+
     fn load_merged_games(&self) -> Result<Option<Vec<MergedGame>>> {
         self.read_json_file(
-            StorageKeys::MERGED_GAMES,
+            &normalized_key(StorageKeys::MERGED_GAMES),
             Some(StorageKeys::ENHANCEMENTS_DIR),
             false,
         )
@@ -123,16 +427,25 @@ impl Storage for FileSystemStore {
 
     fn save_merged_games(&self, games: &[MergedGame]) -> Result<()> {
         self.write_json_file(
-            StorageKeys::MERGED_GAMES,
+            &normalized_key(StorageKeys::MERGED_GAMES),
             Some(StorageKeys::ENHANCEMENTS_DIR),
             games,
             false,
-        )
+        )?;
+        self.write_run_snapshot(StorageKeys::MERGED_GAMES, games)
+    }
+
+    fn save_merge_report(&self, report: &MergeReport) -> Result<()> {
+        self.write_run_file(StorageKeys::MERGE_REPORT, report)
+    }
+
+    fn save_filtered_apps(&self, apps: &[FilteredApp]) -> Result<()> {
+        self.write_run_file(StorageKeys::FILTERED_APPS, apps)
     }
 
     fn load_matched_games(&self) -> Result<Option<Vec<GameWithSteamId>>> {
         self.read_json_file(
-            StorageKeys::MERGED_GAMES_WITH_STEAM_ID,
+            &normalized_key(StorageKeys::MERGED_GAMES_WITH_STEAM_ID),
             Some(StorageKeys::ENHANCEMENTS_DIR),
             false,
         )
@@ -140,11 +453,12 @@ impl Storage for FileSystemStore {
 
     fn save_matched_games(&self, games: &[GameWithSteamId]) -> Result<()> {
         self.write_json_file(
-            StorageKeys::MERGED_GAMES_WITH_STEAM_ID,
+            &normalized_key(StorageKeys::MERGED_GAMES_WITH_STEAM_ID),
             Some(StorageKeys::ENHANCEMENTS_DIR),
             games,
             false,
-        )
+        )?;
+        self.write_run_snapshot(StorageKeys::MERGED_GAMES_WITH_STEAM_ID, games)
     }
 
     fn load_app_info(&self, app_id: u64) -> Result<Option<StoreInfo>> {
@@ -164,6 +478,23 @@ impl Storage for FileSystemStore {
         )
     }
 
+    fn load_deck_info(&self, app_id: u64) -> Result<Option<SteamDeckVerifiedResponse>> {
+        self.read_json_file(
+            &app_id.to_string(),
+            Some(StorageKeys::STEAM_DECK_DIR),
+            false,
+        )
+    }
+
+    fn save_deck_info(&self, app_id: u64, deck_info: &SteamDeckVerifiedResponse) -> Result<()> {
+        self.write_json_file(
+            &app_id.to_string(),
+            Some(StorageKeys::STEAM_DECK_DIR),
+            deck_info,
+            false,
+        )
+    }
+
     fn load_rawg_info(&self, name: &str) -> Result<Option<RawgGameDetailed>> {
         self.read_json_file(name, Some(StorageKeys::RAWG_APPS_DIR), false)
     }
@@ -172,6 +503,62 @@ impl Storage for FileSystemStore {
         self.write_json_file(name, Some(StorageKeys::RAWG_APPS_DIR), &rawg_info, false)
     }
 
+    fn load_rawg_miss(&self, name: &str) -> Result<Option<RawgMiss>> {
+        self.read_json_file(name, Some(StorageKeys::RAWG_MISSES_DIR), false)
+    }
+
+    fn save_rawg_miss(&self, name: &str, miss: &RawgMiss) -> Result<()> {
+        self.write_json_file(name, Some(StorageKeys::RAWG_MISSES_DIR), miss, false)
+    }
+
+    fn load_youtube_trailer(&self, name: &str) -> Result<Option<YoutubeTrailer>> {
+        self.read_json_file(name, Some(StorageKeys::YOUTUBE_DIR), false)
+    }
+
+    fn save_youtube_trailer(&self, name: &str, trailer: &YoutubeTrailer) -> Result<()> {
+        self.write_json_file(name, Some(StorageKeys::YOUTUBE_DIR), trailer, false)
+    }
+
+    fn load_twitch_history(&self, name: &str) -> Result<Option<TwitchHistory>> {
+        self.read_json_file(name, Some(StorageKeys::TWITCH_DIR), false)
+    }
+
+    fn save_twitch_history(&self, name: &str, history: &TwitchHistory) -> Result<()> {
+        self.write_json_file(name, Some(StorageKeys::TWITCH_DIR), history, false)
+    }
+
+    fn load_backloggd_info(&self, name: &str) -> Result<Option<BackloggdInfo>> {
+        self.read_json_file(name, Some(StorageKeys::BACKLOGGD_DIR), false)
+    }
+
+    fn save_backloggd_info(&self, name: &str, info: &BackloggdInfo) -> Result<()> {
+        self.write_json_file(name, Some(StorageKeys::BACKLOGGD_DIR), info, false)
+    }
+
+    fn cached_at(&self, subdir: &str, key: &str) -> Result<Option<SystemTime>> {
+        let path = self.get_path_for_key(key, Some(subdir), false);
+        for path in [path.with_extension("json.zst"), path] {
+            if path.exists() {
+                return Ok(Some(fs::metadata(path)?.modified()?));
+            }
+        }
+        Ok(None)
+    }
+
+    fn load_rawg_usage(&self) -> Result<RawgUsage> {
+        Ok(self
+            .read_json_file(StorageKeys::RAWG_USAGE, None, true)?
+            .unwrap_or_default())
+    }
+
+    fn save_rawg_usage(&self, usage: &RawgUsage) -> Result<()> {
+        self.write_json_file(StorageKeys::RAWG_USAGE, None, usage, true)
+    }
+
+    fn save_request_report(&self, report: &RequestReport) -> Result<()> {
+        self.write_run_file(StorageKeys::REQUEST_REPORT, report)
+    }
+
     fn load_enriched_games(&self) -> Result<Option<Vec<Game>>> {
         self.read_json_file(
             StorageKeys::ENRICHED_GAMES,
@@ -186,15 +573,132 @@ impl Storage for FileSystemStore {
             Some(StorageKeys::ENHANCEMENTS_DIR),
             games,
             false,
-        )
+        )?;
+        self.write_run_snapshot(StorageKeys::ENRICHED_GAMES, games)?;
+        // The complete result supersedes the progress of the run
+        self.clear_enriched_progress()
+    }
+
+    fn append_enriched_game(&self, game: &Game) -> Result<()> {
+        let dir = self.cache_dir.join(StorageKeys::ENHANCEMENTS_DIR);
+        self.ensure_dir(&dir)?;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(StorageKeys::ENRICHED_GAMES_PROGRESS))?;
+        let mut line = serde_json::to_vec(game)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    fn clear_enriched_progress(&self) -> Result<()> {
+        let path = self
+            .cache_dir
+            .join(StorageKeys::ENHANCEMENTS_DIR)
+            .join(StorageKeys::ENRICHED_GAMES_PROGRESS);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn load_enriched_progress(&self) -> Result<Vec<Game>> {
+        let path = self
+            .cache_dir
+            .join(StorageKeys::ENHANCEMENTS_DIR)
+            .join(StorageKeys::ENRICHED_GAMES_PROGRESS);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut games = Vec::new();
+        for line in fs::read_to_string(&path)?.lines() {
+            match serde_json::from_str(line) {
+                Ok(game) => games.push(game),
+                // The last line is cut off when the run was killed mid-write
+                Err(e) => warn!("Skipping unreadable line of {:?}: {}", path, e),
+            }
+        }
+        Ok(games)
+    }
+
+    fn load_scores(&self) -> Result<Option<BTreeMap<String, Score>>> {
+        self.read_json_file(StorageKeys::SCORES, None, true)
+    }
+
+    fn save_scores(&self, scores: &BTreeMap<String, Score>) -> Result<()> {
+        self.write_json_file(StorageKeys::SCORES, None, scores, true)
+    }
+
+    fn load_harmony_ids(&self) -> Result<Option<BTreeMap<String, String>>> {
+        self.read_json_file(StorageKeys::HARMONY_IDS, None, true)
+    }
+
+    fn save_harmony_ids(&self, ids: &BTreeMap<String, String>) -> Result<()> {
+        self.write_json_file(StorageKeys::HARMONY_IDS, None, ids, true)
+    }
+
+    fn load_manifest(&self) -> Result<Option<Manifest>> {
+        self.read_json_file(StorageKeys::MANIFEST, None, true)
     }
 
     fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        self.write_run_file(StorageKeys::MANIFEST, manifest)?;
+        if manifest.selected_fields.is_some() {
+            return self.write_run_file(StorageKeys::MANIFEST_PROJECTION, &manifest.projection());
+        }
+        // A projection of an earlier run would be taken for this one otherwise
+        let projection = self.get_path_for_key(StorageKeys::MANIFEST_PROJECTION, None, true);
+        if projection.exists() {
+            fs::remove_file(projection)?;
+        }
+        Ok(())
+    }
+
+    fn save_manifest_chunks(&self, manifest: &Manifest, chunk_size: usize) -> Result<()> {
+        // Chunks of an earlier, longer manifest would linger otherwise
+        let dir = self.data_dir.join(StorageKeys::MANIFEST_CHUNKS_DIR);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+
+        let (index, chunks) = manifest.chunks(chunk_size, |i| format!("games_{:04}", i));
+        for (key, chunk) in &chunks {
+            self.write_json_file(key, Some(StorageKeys::MANIFEST_CHUNKS_DIR), chunk, true)?;
+        }
+        self.write_json_file(StorageKeys::MANIFEST_INDEX, None, &index, true)
+    }
+
+    fn save_genre_manifest(&self, genre: &str, manifest: &Manifest) -> Result<()> {
         self.write_json_file(
-            StorageKeys::MANIFEST,
-            None,
-            manifest,
-            true, // Use data_dir
+            &slugify(genre),
+            Some(StorageKeys::GENRE_MANIFESTS_DIR),
+            &manifest.projection(),
+            true,
         )
     }
+
+    fn begin_run(&self, key: &str) {
+        *self.run_key.lock().unwrap() = Some(key.to_string());
+    }
+
+    fn remove_run(&self, key: &str) -> Result<()> {
+        let dir = self.data_dir.join(StorageKeys::RUN_DIRS).join(key);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    fn load_runs(&self) -> Result<Vec<RunRecord>> {
+        Ok(self
+            .read_json_file(StorageKeys::RUNS, None, true)?
+            .unwrap_or_default())
+    }
+
+    fn save_runs(&self, runs: &[RunRecord]) -> Result<()> {
+        self.write_json_file(StorageKeys::RUNS, None, runs, true)
+    }
 }
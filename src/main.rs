@@ -1,77 +1,245 @@
-mod config;
-mod domain;
-mod error;
-mod infrastructure;
-mod services;
-
-use crate::config::cli::{Args, Commands};
-use crate::config::Config;
-use crate::domain::storage::Storage;
-use crate::error::Result;
-use crate::infrastructure::FileSystemStore;
-use crate::infrastructure::RawgClient;
-use crate::infrastructure::SteamClient;
-use crate::services::enrichment::Enrichment;
-use crate::services::game_service::GameService;
-use crate::services::matching::{MatchingConfig, MatchingService};
-use crate::services::merging::MergingService;
-use crate::services::publish::PublishService;
-use crate::services::scraping::ScrapingService;
+use gameharmony::config::cli::{Args, CacheCommand, Commands, RunsCommand};
+use gameharmony::config::{
+    build_http_client, CasingRules, Config, DeckTokens, MatchOverrides, NormalizationRules,
+    ScraperConfig, UserAgentPool,
+};
+use gameharmony::domain::storage::Storage;
+use gameharmony::error::{GameError, Result};
+use gameharmony::infrastructure::BackloggdClient;
+use gameharmony::infrastructure::FileSystemStore;
+use gameharmony::infrastructure::RawgClient;
+use gameharmony::infrastructure::SteamClient;
+use gameharmony::infrastructure::TwitchClient;
+use gameharmony::infrastructure::YoutubeClient;
+use gameharmony::services::cache::CacheService;
+use gameharmony::services::dataset::DatasetPublisher;
+use gameharmony::services::digest::DigestRenderer;
+use gameharmony::services::enrichment::{Enrichment, BACKLOGGD_PROVIDER};
+use gameharmony::services::game_service::GameService;
+use gameharmony::services::interactive_match::InteractiveMatch;
+use gameharmony::services::match_eval::MatchEval;
+use gameharmony::services::matching::{MatchingConfig, MatchingService};
+use gameharmony::services::merging::MergingService;
+use gameharmony::services::progress;
+use gameharmony::services::publish::PublishService;
+use gameharmony::services::runs::RunService;
+use gameharmony::services::scraping::ScrapingService;
+use gameharmony::services::text_utils::TitleNormalizer;
 use clap::Parser;
+use std::fs::OpenOptions;
 use std::sync::Arc;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    tracing_subscriber::fmt::init();
+    init_logging(&args)?;
+    progress::init(args.quiet);
+    TitleNormalizer::set_rules(&NormalizationRules::load(&args.normalization_rules_file)?);
 
     match &args.command {
         Some(Commands::Publish {
             manifest,
             username,
             repo,
+            screenshots,
+            cards,
         }) => {
-            let prepare_service = PublishService::new(username.clone(), repo.clone());
+            let prepare_service = PublishService::new(username.clone(), repo.clone())
+                .with_screenshots(*screenshots)
+                .with_cards(*cards);
             prepare_service.prepare(manifest).await?;
         }
+        Some(Commands::Digest {
+            manifest,
+            previous,
+            output,
+        }) => {
+            DigestRenderer::write(manifest, previous.as_deref(), output)?;
+        }
+        Some(Commands::PublishDataset {
+            manifest,
+            token,
+            title,
+            creators,
+            sandbox,
+        }) => {
+            let store = Arc::new(FileSystemStore::new(
+                args.data_dir.clone(),
+                args.cache_dir.clone(),
+            ));
+            DatasetPublisher::new(store, token.clone(), *sandbox)
+                .publish(manifest, title, creators)
+                .await?;
+        }
+        Some(Commands::CheckConfig) => {
+            let scraper_config = ScraperConfig::load(&args.config_file)?;
+            CasingRules::load(&args.casing_rules_file)?;
+            MatchOverrides::load(&args.overrides_file)?;
+            println!(
+                "Config is valid: {} websites",
+                scraper_config.websites.len()
+            );
+        }
+        Some(Commands::Scrape { only, debug }) => {
+            let scraper_config = ScraperConfig::load(&args.config_file)?;
+            let website = scraper_config
+                .websites
+                .iter()
+                .find(|w| {
+                    w.display_name == *only
+                        || w.source_name() == *only
+                        || w.pattern == *only
+                        || w.url == *only
+                })
+                .ok_or_else(|| GameError::Other(format!("No website {:?} in the config", only)))?;
+            let store = FileSystemStore::new(args.data_dir.clone(), args.cache_dir.clone());
+            let _lock = store.lock(args.wait_for_lock)?;
+            ScrapingService::new(build_http_client(args.proxy.as_deref())?)
+                .with_ignore_robots(args.ignore_robots)
+                .with_browser(args.browser.clone())
+                .with_page_cache(Arc::new(store), args.page_max_age)
+                .with_fixtures(args.fixtures())
+                .preview(website, *debug)
+                .await?;
+        }
+        Some(Commands::Cache { action }) => {
+            let store = FileSystemStore::new(args.data_dir.clone(), args.cache_dir.clone());
+            let _lock = store.lock(args.wait_for_lock)?;
+            let cache = CacheService::new(store);
+            match action {
+                CacheCommand::Stats => cache.stats()?,
+                CacheCommand::Prune {
+                    older_than,
+                    orphan_retention,
+                } => cache.prune(*older_than, *orphan_retention)?,
+                CacheCommand::Clear { category } => cache.clear(*category)?,
+            }
+        }
+        Some(Commands::InteractiveMatch { min_confidence }) => {
+            let store = FileSystemStore::new(args.data_dir.clone(), args.cache_dir.clone());
+            let _lock = store.lock(args.wait_for_lock)?;
+            let store: Arc<dyn Storage> = Arc::new(store);
+            let matching_config = MatchingConfig {
+                backend: args.match_index,
+                ..MatchingConfig::default()
+            };
+            let matching = MatchingService::from_cache(Arc::clone(&store), matching_config)?
+                .ok_or_else(|| {
+                    GameError::Other("No Steam index cached, run the pipeline first".to_string())
+                })?;
+            let overrides = MatchOverrides::load(&args.overrides_file)?;
+            InteractiveMatch::new(matching, store, overrides, args.overrides_file.clone())
+                .run(*min_confidence)?;
+        }
+        Some(Commands::MatchEval { corpus }) => {
+            let store = FileSystemStore::new(args.data_dir.clone(), args.cache_dir.clone());
+            let _lock = store.lock(args.wait_for_lock)?;
+            let store: Arc<dyn Storage> = Arc::new(store);
+            let matching_config = MatchingConfig {
+                backend: args.match_index,
+                ..MatchingConfig::default()
+            };
+            let matching =
+                MatchingService::from_cache(store, matching_config)?.ok_or_else(|| {
+                    GameError::Other("No Steam index cached, run the pipeline first".to_string())
+                })?;
+            MatchEval::new(matching).run(corpus)?;
+        }
+        Some(Commands::Runs { action }) => {
+            let runs = RunService::new(Arc::new(FileSystemStore::new(
+                args.data_dir.clone(),
+                args.cache_dir.clone(),
+            )));
+            match action {
+                RunsCommand::List => runs.list()?,
+                RunsCommand::Show { id } => runs.show(*id)?,
+            }
+        }
         None => {
             let config = Config::new()?;
             config.ensure_directories()?;
 
-            let store: Arc<dyn Storage> = Arc::new(FileSystemStore::new(
-                config.args.data_dir.clone(),
-                config.args.cache_dir.clone(),
-            ));
+            let store =
+                FileSystemStore::new(config.args.data_dir.clone(), config.args.cache_dir.clone())
+                    .with_compression(config.args.compress_cache);
+            let _lock = store.lock(config.args.wait_for_lock)?;
+            let store: Arc<dyn Storage> = Arc::new(store);
 
-            let steam_client =
-                SteamClient::new(config.http_client.clone(), Arc::clone(&store)).await?;
-            let scraping = ScrapingService::new(config.http_client.clone());
-            let merging = MergingService::new(Arc::clone(&store), &config.scraper_config);
-            let matching = MatchingService::new(
-                steam_client.steam_apps.clone(),
+            let steam_client = SteamClient::new(
+                config.http_client.clone(),
                 Arc::clone(&store),
-                MatchingConfig::default(),
-            )?;
+                config.args.steam_api_key.as_deref(),
+            )
+            .with_regions(config.args.regions.clone());
+            let user_agents = if config.args.rotate_user_agents {
+                UserAgentPool::load(config.args.user_agents_file.as_deref())?
+            } else {
+                UserAgentPool::default()
+            };
+            let scraping = ScrapingService::new(config.http_client.clone())
+                .with_ignore_robots(config.args.ignore_robots)
+                .with_user_agents(user_agents)
+                .with_browser(config.args.browser.clone())
+                .with_page_cache(Arc::clone(&store), config.args.page_max_age)
+                .with_fixtures(config.args.fixtures());
+            let merging = MergingService::new(Arc::clone(&store), &config.scraper_config);
+            let enrichment_providers = config.enrichment_providers();
+            let selects_backloggd = enrichment_providers
+                .as_ref()
+                .is_some_and(|providers| providers.iter().any(|p| p == BACKLOGGD_PROVIDER));
             let enrichment = Enrichment::new(
                 steam_client,
-                RawgClient::new(
-                    config.http_client.clone(),
-                    config
-                        .args
-                        .rawg_api_key
-                        .clone()
-                        .expect("No RAWG API key given"),
-                    Arc::clone(&store),
-                ),
-                Arc::clone(&store),
-            );
+                config.casing_rules.clone(),
+                DeckTokens::new(&config.args.deck_language),
+            )
+            .with_rawg(
+                config
+                    .args
+                    .rawg_api_key
+                    .clone()
+                    .filter(|_| !config.args.no_rawg)
+                    .map(|api_key| {
+                        RawgClient::new(config.http_client.clone(), api_key, Arc::clone(&store))
+                            .with_budget(config.args.rawg_budget)
+                            .with_min_similarity(config.args.rawg_min_similarity)
+                    }),
+            )
+            .with_youtube(config.args.youtube_api_key.clone().map(|api_key| {
+                YoutubeClient::new(config.http_client.clone(), api_key, Arc::clone(&store))
+            }))
+            .with_twitch(
+                config
+                    .args
+                    .twitch_client_id
+                    .clone()
+                    .zip(config.args.twitch_client_secret.clone())
+                    .map(|(client_id, client_secret)| {
+                        TwitchClient::new(
+                            config.http_client.clone(),
+                            client_id,
+                            client_secret,
+                            Arc::clone(&store),
+                        )
+                    }),
+            )
+            .with_backloggd(
+                (config.args.backloggd || selects_backloggd)
+                    .then(|| BackloggdClient::new(config.http_client.clone(), Arc::clone(&store))),
+            )
+            .with_providers(enrichment_providers)?
+            .with_provenance(config.args.with_provenance)
+            .with_conflict_policies(config.scraper_config.enrichment.conflicts.clone());
+            let keep_runs = config.args.keep_runs;
             let service = GameService::new(
                 config,
                 Arc::clone(&store),
                 scraping,
                 merging,
-                matching,
                 enrichment,
+                RunService::new(Arc::clone(&store)).with_retention(keep_runs),
             );
             service.process().await?;
         }
@@ -79,3 +247,23 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Logs at `--log-level`, or only warnings and errors with `--quiet`, to
+/// stdout and `--log-file` if given
+fn init_logging(args: &Args) -> Result<()> {
+    let directives = if args.quiet { "warn" } else { &args.log_level };
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| GameError::Other(format!("Invalid log level {:?}: {}", directives, e)))?;
+    let log_file = args
+        .log_file
+        .as_ref()
+        .map(|path| OpenOptions::new().create(true).append(true).open(path))
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(log_file.map(|file| fmt::layer().with_ansi(false).with_writer(Arc::new(file))))
+        .init();
+    Ok(())
+}
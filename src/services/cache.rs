@@ -0,0 +1,154 @@
+use crate::config::cli::CacheCategory;
+use crate::domain::storage::{Storage, StorageKeys};
+use crate::error::Result;
+use crate::infrastructure::{CacheEntry, FileSystemStore};
+use crate::services::enrichment::rawg_queries;
+use clap::ValueEnum;
+use indicatif::HumanBytes;
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+use tracing::info;
+
+pub struct CacheService {
+    store: FileSystemStore,
+}
+
+impl CacheService {
+    pub fn new(store: FileSystemStore) -> Self {
+        Self { store }
+    }
+
+    pub fn stats(&self) -> Result<()> {
+        for category in CacheCategory::value_variants() {
+            let entries = self.store.cache_entries(Some(category.dir()))?;
+            println!(
+                "{:?}: {} entries, {}",
+                category,
+                entries.len(),
+                HumanBytes(total_size(&entries))
+            );
+        }
+
+        let entries = self.store.cache_entries(None)?;
+        println!(
+            "Total: {} entries, {}",
+            entries.len(),
+            HumanBytes(total_size(&entries))
+        );
+        Ok(())
+    }
+
+    /// Removes every cache entry that was last written more than `older_than`
+    /// ago, and entries of games no longer part of any source once they are
+    /// older than `orphan_retention`
+    pub fn prune(&self, older_than: Duration, orphan_retention: Duration) -> Result<()> {
+        let cutoff = SystemTime::now() - older_than;
+        let orphan_cutoff = SystemTime::now() - orphan_retention;
+        let referenced = self.referenced_keys()?;
+        if referenced.is_none() {
+            info!("No matched games cached, skipping orphan removal");
+        }
+
+        let mut orphans = Vec::new();
+        let mut stale = Vec::new();
+        for entry in self.store.cache_entries(None)? {
+            if entry.modified < cutoff {
+                stale.push(entry);
+            } else if entry.modified < orphan_cutoff
+                && referenced
+                    .as_ref()
+                    .is_some_and(|referenced| is_orphan(&entry, referenced))
+            {
+                orphans.push(entry);
+            }
+        }
+
+        info!(
+            "Found {} orphaned entries, {}",
+            orphans.len(),
+            HumanBytes(total_size(&orphans))
+        );
+        stale.extend(orphans);
+        self.remove(&stale)
+    }
+
+    /// Keys of the per-game cache entries still used by the current matched
+    /// games, as `(dir, key)`
+    fn referenced_keys(&self) -> Result<Option<HashSet<(&'static str, String)>>> {
+        let Some(matched) = self.store.load_matched_games()? else {
+            return Ok(None);
+        };
+
+        let mut keys = HashSet::new();
+        for game in matched {
+            keys.insert((StorageKeys::YOUTUBE_DIR, game.name.clone()));
+            keys.insert((StorageKeys::TWITCH_DIR, game.name.clone()));
+            keys.insert((StorageKeys::BACKLOGGD_DIR, game.name.clone()));
+            // RAWG entries are keyed by each title the game was searched under
+            for query in rawg_queries(&game.name, &game.original_names) {
+                keys.insert((StorageKeys::RAWG_MISSES_DIR, query.clone()));
+                keys.insert((StorageKeys::RAWG_APPS_DIR, query));
+            }
+            let Some(app_id) = game.steam_id.and_then(|id| id.parse::<u64>().ok()) else {
+                continue;
+            };
+            let mut app_ids = vec![app_id];
+            // Deck reports are stored for the base game of DLCs and editions
+            if let Some(base_app_id) = self
+                .store
+                .load_app_info(app_id)?
+                .and_then(|info| info.base_app_id)
+            {
+                app_ids.push(base_app_id);
+            }
+            for app_id in app_ids {
+                keys.insert((StorageKeys::STEAM_APPS_DIR, app_id.to_string()));
+                keys.insert((StorageKeys::STEAM_DECK_DIR, app_id.to_string()));
+            }
+        }
+        Ok(Some(keys))
+    }
+
+    pub fn clear(&self, category: CacheCategory) -> Result<()> {
+        let entries = self.store.cache_entries(Some(category.dir()))?;
+        self.remove(&entries)
+    }
+
+    fn remove(&self, entries: &[CacheEntry]) -> Result<()> {
+        for entry in entries {
+            self.store.remove_cache_entry(entry)?;
+        }
+
+        println!(
+            "Removed {} entries, reclaimed {}",
+            entries.len(),
+            HumanBytes(total_size(entries))
+        );
+        Ok(())
+    }
+}
+
+/// Whether `entry` is a per-game entry that no current game refers to.
+/// Entries outside the per-game directories are never orphans.
+fn is_orphan(entry: &CacheEntry, referenced: &HashSet<(&'static str, String)>) -> bool {
+    let (Some(dir), Some(key)) = (entry.dir(), entry.key()) else {
+        return false;
+    };
+    // Steam entries are keyed by app ID, anything else there is the app index
+    let is_app_id = key.parse::<u64>().is_ok();
+    let dir = match dir {
+        StorageKeys::STEAM_APPS_DIR if is_app_id => StorageKeys::STEAM_APPS_DIR,
+        StorageKeys::STEAM_DECK_DIR if is_app_id => StorageKeys::STEAM_DECK_DIR,
+        StorageKeys::RAWG_APPS_DIR => StorageKeys::RAWG_APPS_DIR,
+        StorageKeys::RAWG_MISSES_DIR => StorageKeys::RAWG_MISSES_DIR,
+        StorageKeys::YOUTUBE_DIR => StorageKeys::YOUTUBE_DIR,
+        StorageKeys::TWITCH_DIR => StorageKeys::TWITCH_DIR,
+        StorageKeys::BACKLOGGD_DIR => StorageKeys::BACKLOGGD_DIR,
+        _ => return false,
+    };
+    !referenced.contains(&(dir, key.to_string()))
+}
+
+fn total_size(entries: &[CacheEntry]) -> u64 {
+    entries.iter().map(|entry| entry.size).sum()
+}
@@ -0,0 +1,175 @@
+use crate::domain::Game;
+use crate::error::{GameError, Result};
+use font8x8::{UnicodeFonts, BASIC_FONTS, LATIN_FONTS};
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+
+/// Size Open Graph and Twitter previews are displayed at
+pub const CARD_WIDTH: u32 = 1200;
+pub const CARD_HEIGHT: u32 = 630;
+
+const MARGIN: u32 = 48;
+const COVER_WIDTH: u32 = 540;
+const COVER_HEIGHT: u32 = 252;
+/// Glyphs are 8x8 pixels, drawn at a multiple of that
+const GLYPH_SIZE: u32 = 8;
+const TITLE_SCALE: u32 = 5;
+const TITLE_LINES: usize = 2;
+const BADGE_SCALE: u32 = 2;
+const BADGE_PADDING: u32 = 12;
+
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 27, 255]);
+const TEXT: Rgba<u8> = Rgba([250, 250, 250, 255]);
+const MUTED: Rgba<u8> = Rgba([161, 161, 170, 255]);
+const ACCENT: Rgba<u8> = Rgba([34, 211, 238, 255]);
+const BADGE: Rgba<u8> = Rgba([63, 63, 70, 255]);
+
+/// Renders the social preview card of a game as PNG: its cover, title,
+/// harmony score and a badge per source ranking it. `cover` is the encoded
+/// header image, the card is drawn without it if it can't be decoded.
+pub fn render_card(game: &Game, cover: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut card = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, BACKGROUND);
+
+    if let Some(cover) = cover.and_then(|bytes| image::load_from_memory(bytes).ok()) {
+        let cover = cover.resize(COVER_WIDTH, COVER_HEIGHT, FilterType::Triangle);
+        imageops::overlay(&mut card, &cover.to_rgba8(), MARGIN as i64, MARGIN as i64);
+    }
+
+    let score_x = MARGIN * 2 + COVER_WIDTH;
+    draw_text(&mut card, "HARMONY SCORE", score_x, MARGIN, 2, MUTED);
+    draw_text(
+        &mut card,
+        &game.harmony_score.to_string(),
+        score_x,
+        MARGIN + 32,
+        10,
+        ACCENT,
+    );
+
+    let title_width = (CARD_WIDTH - 2 * MARGIN) / (GLYPH_SIZE * TITLE_SCALE);
+    let title_y = MARGIN * 2 + COVER_HEIGHT;
+    for (i, line) in wrap(&game.title, title_width as usize, TITLE_LINES)
+        .iter()
+        .enumerate()
+    {
+        let y = title_y + i as u32 * GLYPH_SIZE * (TITLE_SCALE + 2);
+        draw_text(&mut card, line, MARGIN, y, TITLE_SCALE, TEXT);
+    }
+
+    draw_badges(&mut card, game);
+    draw_text(
+        &mut card,
+        "gameharmony",
+        CARD_WIDTH - MARGIN - 11 * GLYPH_SIZE * 2,
+        CARD_HEIGHT - MARGIN / 2 - GLYPH_SIZE * 2,
+        2,
+        MUTED,
+    );
+
+    let mut png = Vec::new();
+    card.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| GameError::Other(format!("Can't encode card of {}: {}", game.title, e)))?;
+    Ok(png)
+}
+
+/// One badge per source, best rank first, as many as fit in a row
+fn draw_badges(card: &mut RgbaImage, game: &Game) {
+    let mut rankings: Vec<(&String, &Option<u64>)> = game.rankings.iter().collect();
+    rankings.sort_by_key(|(source, rank)| (rank.unwrap_or(u64::MAX), source.as_str()));
+
+    let glyph = GLYPH_SIZE * BADGE_SCALE;
+    let height = glyph + 2 * BADGE_PADDING;
+    let y = CARD_HEIGHT - MARGIN - height - glyph;
+    let mut x = MARGIN;
+    for (source, rank) in rankings {
+        let label = match rank {
+            Some(rank) => format!("{} #{}", source, rank),
+            None => source.clone(),
+        };
+        let width = label.chars().count() as u32 * glyph + 2 * BADGE_PADDING;
+        if x + width > CARD_WIDTH - MARGIN {
+            break;
+        }
+        fill_rect(card, x, y, width, height, BADGE);
+        draw_text(
+            card,
+            &label,
+            x + BADGE_PADDING,
+            y + BADGE_PADDING,
+            BADGE_SCALE,
+            TEXT,
+        );
+        x += width + BADGE_PADDING;
+    }
+}
+
+/// Breaks `text` into at most `max_lines` lines of `width` characters,
+/// ending the last one with "..." if the text doesn't fit
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let needed = current.chars().count() + word.chars().count() + 1;
+        if !current.is_empty() && needed > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        let kept: String = last.chars().take(width.saturating_sub(3)).collect();
+        *last = format!("{}...", kept.trim_end());
+    }
+    for line in &mut lines {
+        if line.chars().count() > width {
+            *line = line
+                .chars()
+                .take(width.saturating_sub(3))
+                .collect::<String>()
+                + "...";
+        }
+    }
+    lines
+}
+
+fn draw_text(card: &mut RgbaImage, text: &str, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph = BASIC_FONTS
+            .get(c)
+            .or_else(|| LATIN_FONTS.get(c))
+            .or_else(|| BASIC_FONTS.get('?'))
+            .unwrap_or_default();
+        let glyph_x = x + i as u32 * GLYPH_SIZE * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_SIZE {
+                if bits & (1 << col) != 0 {
+                    fill_rect(
+                        card,
+                        glyph_x + col * scale,
+                        y + row as u32 * scale,
+                        scale,
+                        scale,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn fill_rect(card: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(card.height()) {
+        for px in x..(x + width).min(card.width()) {
+            card.put_pixel(px, py, color);
+        }
+    }
+}
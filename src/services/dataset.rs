@@ -0,0 +1,189 @@
+use crate::domain::storage::Storage;
+use crate::domain::Manifest;
+use crate::error::{GameError, Result};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+const ZENODO_URL: &str = "https://zenodo.org/api";
+const ZENODO_SANDBOX_URL: &str = "https://sandbox.zenodo.org/api";
+
+#[derive(Debug, Deserialize)]
+struct Deposition {
+    id: u64,
+    links: DepositionLinks,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepositionLinks {
+    bucket: String,
+    #[serde(default)]
+    html: Option<String>,
+}
+
+/// Publishes the manifest and the run history as a versioned dataset on Zenodo
+pub struct DatasetPublisher {
+    client: Client,
+    store: Arc<dyn Storage>,
+    base_url: &'static str,
+    token: String,
+}
+
+impl DatasetPublisher {
+    pub fn new(store: Arc<dyn Storage + 'static>, token: String, sandbox: bool) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            store,
+            base_url: if sandbox {
+                ZENODO_SANDBOX_URL
+            } else {
+                ZENODO_URL
+            },
+            token,
+        }
+    }
+
+    pub async fn publish(
+        &self,
+        manifest_path: &Path,
+        title: &str,
+        creators: &[String],
+    ) -> Result<()> {
+        info!("Reading manifest from {:?}", manifest_path);
+        let manifest_content = tokio::fs::read_to_string(manifest_path).await?;
+        let manifest: Manifest = serde_json::from_str(&manifest_content)?;
+        let runs = serde_json::to_vec_pretty(&self.store.load_runs()?)?;
+
+        let deposition: Deposition = Self::json(
+            self.authorized(
+                self.client
+                    .post(format!("{}/deposit/depositions", self.base_url))
+                    .json(&json!({})),
+            )
+            .send()
+            .await?,
+        )
+        .await?;
+        info!("Created deposition {}", deposition.id);
+
+        self.upload(&deposition, "manifest.json", manifest_content.into_bytes())
+            .await?;
+        self.upload(&deposition, "runs.json", runs).await?;
+
+        let version = match manifest.metadata.run_id {
+            Some(run_id) => format!("{}+run.{}", manifest.metadata.version, run_id),
+            None => manifest.metadata.version.clone(),
+        };
+        let mut description = format!(
+            "<p>Aggregated rankings of {} games from {} sources, last updated {}.</p>",
+            manifest.total_games,
+            manifest.metadata.sources.len(),
+            manifest.last_updated
+        );
+        for source in &manifest.metadata.sources {
+            let credit = [
+                source.attribution.as_deref(),
+                source.license_note.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ");
+            if credit.is_empty() {
+                description.push_str(&format!("<p>{}</p>", source.name));
+            } else {
+                description.push_str(&format!("<p>{}: {}</p>", source.name, credit));
+            }
+        }
+        let creators: Vec<_> = creators
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect();
+        let metadata = json!({
+            "metadata": {
+                "title": title,
+                "upload_type": "dataset",
+                "description": description,
+                "creators": creators,
+                "version": version,
+                "publication_date": manifest.last_updated.get(..10),
+                "keywords": ["video games", "rankings"],
+            }
+        });
+        Self::check(
+            self.authorized(
+                self.client
+                    .put(format!(
+                        "{}/deposit/depositions/{}",
+                        self.base_url, deposition.id
+                    ))
+                    .json(&metadata),
+            )
+            .send()
+            .await?,
+        )
+        .await?;
+
+        let published: Deposition = Self::json(
+            self.authorized(self.client.post(format!(
+                "{}/deposit/depositions/{}/actions/publish",
+                self.base_url, deposition.id
+            )))
+            .send()
+            .await?,
+        )
+        .await?;
+        info!(
+            "Published dataset version {} at {}",
+            version,
+            published.links.html.unwrap_or_default()
+        );
+
+        Ok(())
+    }
+
+    async fn upload(&self, deposition: &Deposition, filename: &str, body: Vec<u8>) -> Result<()> {
+        info!("Uploading {} ({} bytes)", filename, body.len());
+        Self::check(
+            self.authorized(
+                self.client
+                    .put(format!("{}/{}", deposition.links.bucket, filename))
+                    .body(body),
+            )
+            .send()
+            .await?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        request.bearer_auth(&self.token)
+    }
+
+    async fn check(response: Response) -> Result<Response> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(GameError::Other(format!(
+            "Zenodo request failed with {}: {}",
+            status, body
+        )))
+    }
+
+    async fn json<T: serde::de::DeserializeOwned>(response: Response) -> Result<T> {
+        Ok(Self::check(response).await?.json().await?)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::domain::{Game, Manifest};
+use crate::error::Result;
+use crate::services::text_utils::TitleNormalizer;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use tracing::info;
+
+const TOP_GAMES: usize = 10;
+const MAX_NEWCOMERS: usize = 5;
+const MAX_PRICE_DROPS: usize = 5;
+
+/// Renders a compact HTML digest of a manifest for newsletters: the top games
+/// by harmony score and, compared to a previous manifest, newcomers and the
+/// biggest price drops. Only inline styles are used, so mail clients render
+/// it as well.
+pub struct DigestRenderer;
+
+impl DigestRenderer {
+    pub fn write(manifest_path: &Path, previous_path: Option<&Path>, output: &Path) -> Result<()> {
+        let manifest = Self::read_manifest(manifest_path)?;
+        let previous = previous_path.map(Self::read_manifest).transpose()?;
+
+        if let Some(dir) = output.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(output, Self::render(&manifest, previous.as_ref()))?;
+        info!("Saved digest to {:?}", output);
+        Ok(())
+    }
+
+    pub fn render(manifest: &Manifest, previous: Option<&Manifest>) -> String {
+        let mut html = String::new();
+        html.push_str(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>GameHarmony digest</title></head>\n\
+             <body style=\"margin:0;padding:24px;background:#f4f4f5;font-family:Helvetica,Arial,sans-serif;color:#18181b\">\n\
+             <div style=\"max-width:600px;margin:0 auto;background:#ffffff;border-radius:8px;padding:24px\">\n",
+        );
+        let _ = writeln!(
+            html,
+            "<h1 style=\"margin:0 0 4px;font-size:22px\">Top {} games</h1>\n\
+             <p style=\"margin:0 0 16px;color:#71717a;font-size:13px\">{} games ranked, updated {}</p>",
+            TOP_GAMES,
+            manifest.total_games,
+            escape(&manifest.last_updated)
+        );
+
+        let mut top: Vec<&Game> = manifest.games.iter().collect();
+        top.sort_by(|a, b| a.cmp_by_score(b));
+        let rows: Vec<(String, String)> = top
+            .iter()
+            .take(TOP_GAMES)
+            .enumerate()
+            .map(|(i, game)| {
+                (
+                    format!("{}. {}", i + 1, game.title),
+                    format!("{} pts", game.harmony_score),
+                )
+            })
+            .collect();
+        Self::table(&mut html, &rows);
+
+        if let Some(previous) = previous {
+            let previous: HashMap<String, &Game> = previous
+                .games
+                .iter()
+                .map(|game| (TitleNormalizer::normalize(&game.title), game))
+                .collect();
+
+            let newcomers: Vec<(String, String)> = top
+                .iter()
+                .filter(|game| !previous.contains_key(&TitleNormalizer::normalize(&game.title)))
+                .take(MAX_NEWCOMERS)
+                .map(|game| (game.title.clone(), format!("{} pts", game.harmony_score)))
+                .collect();
+            if !newcomers.is_empty() {
+                Self::section(&mut html, "Newcomers");
+                Self::table(&mut html, &newcomers);
+            }
+
+            let mut drops: Vec<(&Game, &str, u64)> = manifest
+                .games
+                .iter()
+                .filter_map(|game| {
+                    let before = previous.get(&TitleNormalizer::normalize(&game.title))?;
+                    let old_price = before.price.as_deref()?;
+                    let drop = parse_price(old_price)?
+                        .checked_sub(parse_price(game.price.as_deref()?)?)?;
+                    (drop > 0).then_some((game, old_price, drop))
+                })
+                .collect();
+            drops.sort_by(|(a, _, a_drop), (b, _, b_drop)| {
+                b_drop.cmp(a_drop).then_with(|| a.cmp_by_score(b))
+            });
+            let drops: Vec<(String, String)> = drops
+                .into_iter()
+                .take(MAX_PRICE_DROPS)
+                .map(|(game, old_price, _)| {
+                    (
+                        game.title.clone(),
+                        format!(
+                            "{} → {}",
+                            old_price,
+                            game.price.as_deref().unwrap_or_default()
+                        ),
+                    )
+                })
+                .collect();
+            if !drops.is_empty() {
+                Self::section(&mut html, "Biggest price drops");
+                Self::table(&mut html, &drops);
+            }
+        }
+
+        html.push_str("</div>\n</body>\n</html>\n");
+        html
+    }
+
+    fn section(html: &mut String, title: &str) {
+        let _ = writeln!(
+            html,
+            "<h2 style=\"margin:24px 0 8px;font-size:17px\">{}</h2>",
+            escape(title)
+        );
+    }
+
+    fn table(html: &mut String, rows: &[(String, String)]) {
+        html.push_str("<table style=\"width:100%;border-collapse:collapse;font-size:14px\">\n");
+        for (label, value) in rows {
+            let _ = writeln!(
+                html,
+                "<tr><td style=\"padding:6px 0;border-bottom:1px solid #e4e4e7\">{}</td>\
+                 <td style=\"padding:6px 0;border-bottom:1px solid #e4e4e7;text-align:right;color:#52525b\">{}</td></tr>",
+                escape(label),
+                escape(value)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn read_manifest(path: &Path) -> Result<Manifest> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// Reads a formatted price like "19,99€" or "$19.99" as cents
+fn parse_price(formatted: &str) -> Option<u64> {
+    let digits: String = formatted.chars().filter(char::is_ascii_digit).collect();
+    // Formatted prices without decimals, e.g. "2000 ₩", are taken as whole units
+    let has_decimals = formatted.rfind(['.', ',']).is_some_and(|i| {
+        formatted[i + 1..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .count()
+            == 2
+    });
+    let value: u64 = digits.parse().ok()?;
+    Some(if has_decimals { value } else { value * 100 })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
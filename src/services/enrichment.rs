@@ -1,72 +1,650 @@
-use crate::domain::storage::Storage;
-use crate::domain::Game;
-use crate::error::Result;
-use crate::infrastructure::{RawgClient, SteamClient};
+use crate::config::{CasingRules, ConflictPolicy, DeckTokens};
+use crate::domain::{FieldConflict, Game, CONFLICT_FIELDS};
+use crate::error::{ErrorSummary, GameError, Result};
+use crate::infrastructure::{
+    BackloggdClient, RawgClient, SteamClient, TwitchClient, YoutubeClient, DECK_STATUS_ERROR,
+    REQUEST_STATS,
+};
+use crate::services::identity::{harmony_id, slugify};
 use crate::services::matching::GameWithSteamId;
-use crate::services::scoring::calculate_harmony_score;
+use crate::services::sanitize::{strip_html, summarize, SUMMARY_MAX_CHARS};
+use crate::services::scoring::HarmonyScorer;
 use crate::services::text_utils::TitleNormalizer;
-use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+use chrono::DateTime;
+use futures::future::BoxFuture;
+use indicatif::ProgressBar;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+pub const STEAM_PROVIDER: &str = "steam";
+pub const STEAM_DECK_PROVIDER: &str = "steam_deck";
+pub const RAWG_PROVIDER: &str = "rawg";
+pub const YOUTUBE_PROVIDER: &str = "youtube";
+pub const TWITCH_PROVIDER: &str = "twitch";
+pub const BACKLOGGD_PROVIDER: &str = "backloggd";
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What a provider did for a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnrichmentOutcome {
+    /// The provider's data was added to the game, with the fields it set
+    Enriched(Vec<&'static str>),
+    /// The provider has no data on the game
+    NotFound,
+    /// The provider doesn't apply to the game, e.g. Steam for games without a
+    /// Steam ID
+    Skipped,
+}
+
+/// An upstream that adds its data to games. Providers are queried in the order
+/// they were added, so later ones see what earlier ones filled in.
+pub trait EnrichmentProvider: Send + Sync {
+    /// Name used by `--enrich`, the enrichment timestamps and the error summary
+    fn name(&self) -> &str;
+
+    /// How long the provider's data stays valid during incremental runs, `None`
+    /// for snapshots that are queried on every run
+    fn max_age(&self) -> Option<Duration>;
+
+    /// When the cached data `enrich` would use for `game` was fetched, `None`
+    /// if the provider has to be queried or keeps no cache
+    fn cached_at(&self, _game: &Game) -> Result<Option<SystemTime>> {
+        Ok(None)
+    }
+
+    /// Adds the provider's data to `game`, bypassing the provider's cache if
+    /// `refresh` is set
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>>;
+}
+
+impl<P: EnrichmentProvider + ?Sized> EnrichmentProvider for Arc<P> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        (**self).max_age()
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        (**self).cached_at(game)
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        (**self).enrich(game, refresh)
+    }
+}
+
+/// Prices and reviews from the Steam store
+struct SteamStoreProvider {
+    client: Arc<SteamClient>,
+}
+
+impl SteamStoreProvider {
+    async fn enrich_game(&self, game: &mut Game, refresh: bool) -> Result<EnrichmentOutcome> {
+        let Some(app_id) = game.steam_id else {
+            return Ok(EnrichmentOutcome::Skipped);
+        };
+        let Some(store_info) = self.client.get_store_info(app_id, refresh).await? else {
+            return Ok(EnrichmentOutcome::NotFound);
+        };
+        // Reviews and Deck status should refer to the base game
+        let mut fields = Vec::new();
+        if let Some(base_app_id) = store_info.base_app_id {
+            game.steam_id = Some(base_app_id);
+            fields.push("steam_id");
+        }
+        fields.extend(game.apply_steam_info(store_info));
+        Ok(EnrichmentOutcome::Enriched(fields))
+    }
+}
+
+impl EnrichmentProvider for SteamStoreProvider {
+    fn name(&self) -> &str {
+        STEAM_PROVIDER
+    }
+
+    /// Prices change often, so Steam store data expires quickly
+    fn max_age(&self) -> Option<Duration> {
+        Some(DAY)
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        match game.steam_id {
+            Some(app_id) => self.client.store_info_cached_at(app_id),
+            None => Ok(None),
+        }
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(self.enrich_game(game, refresh))
+    }
+}
+
+struct SteamDeckProvider {
+    client: Arc<SteamClient>,
+    tokens: DeckTokens,
+}
+
+impl SteamDeckProvider {
+    async fn enrich_game(&self, game: &mut Game, refresh: bool) -> Result<EnrichmentOutcome> {
+        let Some(app_id) = game.steam_id else {
+            return Ok(EnrichmentOutcome::Skipped);
+        };
+        match self.client.get_deck_verified(app_id, refresh).await {
+            Ok(deck_status) => {
+                let fields = game.apply_steam_deck_info(deck_status, app_id, &self.tokens);
+                Ok(EnrichmentOutcome::Enriched(fields))
+            }
+            Err(e) => {
+                game.platforms.steamdeck = DECK_STATUS_ERROR.to_string();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl EnrichmentProvider for SteamDeckProvider {
+    fn name(&self) -> &str {
+        STEAM_DECK_PROVIDER
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(30 * DAY)
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        match game.steam_id {
+            Some(app_id) => self.client.deck_info_cached_at(app_id),
+            None => Ok(None),
+        }
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(self.enrich_game(game, refresh))
+    }
+}
+
+impl EnrichmentProvider for RawgClient {
+    fn name(&self) -> &str {
+        RAWG_PROVIDER
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(90 * DAY)
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        self.cached_at(&rawg_queries(&game.title, &game.alternate_titles))
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(async move {
+            let titles: Vec<String> = std::iter::once(game.title.clone())
+                .chain(game.alternate_titles.iter().cloned())
+                .collect();
+            let mut rejected = None;
+            for query in rawg_queries(&game.title, &game.alternate_titles) {
+                match self.get_game_info(&query, &titles, refresh).await {
+                    Ok(Some(detailed)) => {
+                        let mut fields = game.apply_rawg_info(&detailed);
+                        if query != game.title {
+                            info!("Found {} on RAWG as {:?}", game.title, query);
+                            game.rawg_query = Some(query);
+                            fields.push("rawg_query");
+                        }
+                        return Ok(EnrichmentOutcome::Enriched(fields));
+                    }
+                    Ok(None) => {}
+                    // Another query may still find the right game
+                    Err(e @ GameError::LowConfidenceMatch { .. }) => {
+                        rejected.get_or_insert(e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            rejected.map_or(Ok(EnrichmentOutcome::NotFound), Err)
+        })
+    }
+}
+
+impl EnrichmentProvider for YoutubeClient {
+    fn name(&self) -> &str {
+        YOUTUBE_PROVIDER
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(90 * DAY)
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        self.cached_at(&game.title)
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(async move {
+            let Some(trailer) = self.find_trailer(&game.title, refresh).await? else {
+                return Ok(EnrichmentOutcome::NotFound);
+            };
+            let fields = game.apply_youtube_trailer(trailer);
+            Ok(EnrichmentOutcome::Enriched(fields))
+        })
+    }
+}
+
+impl EnrichmentProvider for TwitchClient {
+    fn name(&self) -> &str {
+        TWITCH_PROVIDER
+    }
+
+    /// Viewer counts are a snapshot, so they are queried on every run
+    fn max_age(&self) -> Option<Duration> {
+        None
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        _refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(async move {
+            let Some(viewers) = self.get_viewers(&game.title).await? else {
+                return Ok(EnrichmentOutcome::NotFound);
+            };
+            game.twitch_viewers = Some(viewers);
+            Ok(EnrichmentOutcome::Enriched(vec!["twitch_viewers"]))
+        })
+    }
+}
+
+impl EnrichmentProvider for BackloggdClient {
+    fn name(&self) -> &str {
+        BACKLOGGD_PROVIDER
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(30 * DAY)
+    }
+
+    fn cached_at(&self, game: &Game) -> Result<Option<SystemTime>> {
+        self.cached_at(&game.title)
+    }
+
+    fn enrich<'a>(
+        &'a self,
+        game: &'a mut Game,
+        refresh: bool,
+    ) -> BoxFuture<'a, Result<EnrichmentOutcome>> {
+        Box::pin(async move {
+            let Some(info) = self.get_info(&game.title, refresh).await? else {
+                return Ok(EnrichmentOutcome::NotFound);
+            };
+            game.backloggd = Some(info);
+            Ok(EnrichmentOutcome::Enriched(vec!["backloggd"]))
+        })
+    }
+}
+
+/// Titles RAWG is searched for until one is found: the title, the other
+/// original names, the title without its subtitle and the normalized title.
+/// Punctuated titles are often missed under their full name.
+pub fn rawg_queries(title: &str, alternate_titles: &[String]) -> Vec<String> {
+    let (main_title, _) = TitleNormalizer::split_subtitle(title);
+    let candidates = std::iter::once(title.to_string())
+        .chain(alternate_titles.iter().cloned())
+        .chain([main_title.to_string(), TitleNormalizer::normalize(title)]);
+
+    let mut queries: Vec<String> = Vec::new();
+    for candidate in candidates {
+        if !candidate.is_empty() && !queries.iter().any(|q| q.eq_ignore_ascii_case(&candidate)) {
+            queries.push(candidate);
+        }
+    }
+    queries
+}
 
 pub struct Enrichment {
-    pub steam_client: SteamClient,
-    pub rawg_client: RawgClient,
-    pub store: Arc<dyn Storage>,
+    providers: Vec<Box<dyn EnrichmentProvider>>,
+    /// Kept next to its provider for the RAWG usage report
+    pub rawg_client: Option<Arc<RawgClient>>,
+    /// Names of the providers to query, all added ones if unset
+    pub selected: Option<Vec<String>>,
+    /// Whether to record the provider of each field in `Game::provenance`
+    pub with_provenance: bool,
+    /// How disagreeing providers are resolved, by field, `prefer-steam` if unset
+    pub conflict_policies: BTreeMap<String, ConflictPolicy>,
+    /// Disagreements between providers, for the run record
+    pub conflicts: Mutex<Vec<FieldConflict>>,
+    pub casing_rules: CasingRules,
+    /// Provider failures, which leave the game without that provider's data
+    pub errors: ErrorSummary,
 }
 
 impl Enrichment {
+    /// Enrichment with the Steam store and Steam Deck providers
     pub fn new(
         steam_client: SteamClient,
-        rawg_client: RawgClient,
-        store: Arc<dyn Storage + 'static>,
+        casing_rules: CasingRules,
+        deck_tokens: DeckTokens,
     ) -> Self {
+        let steam_client = Arc::new(steam_client);
         Self {
-            steam_client,
-            rawg_client,
-            store,
+            providers: Vec::new(),
+            rawg_client: None,
+            selected: None,
+            with_provenance: false,
+            conflict_policies: BTreeMap::new(),
+            conflicts: Mutex::new(Vec::new()),
+            casing_rules,
+            errors: ErrorSummary::default(),
         }
+        .with_provider(SteamStoreProvider {
+            client: Arc::clone(&steam_client),
+        })
+        .with_provider(SteamDeckProvider {
+            client: steam_client,
+            tokens: deck_tokens,
+        })
     }
 
-    pub(crate) async fn enrich_games(
-        &self,
-        games_with_ids: Vec<GameWithSteamId>,
-    ) -> Result<Vec<Game>> {
-        if let Some(cached) = self.store.load_enriched_games()? {
-            return Ok(cached);
+    /// Queries `provider` after the providers added so far
+    pub fn with_provider(mut self, provider: impl EnrichmentProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    pub fn with_rawg(mut self, rawg_client: Option<RawgClient>) -> Self {
+        let Some(rawg_client) = rawg_client.map(Arc::new) else {
+            return self;
+        };
+        self.rawg_client = Some(Arc::clone(&rawg_client));
+        self.with_provider(rawg_client)
+    }
+
+    pub fn with_youtube(self, youtube_client: Option<YoutubeClient>) -> Self {
+        match youtube_client {
+            Some(youtube_client) => self.with_provider(youtube_client),
+            None => self,
+        }
+    }
+
+    pub fn with_twitch(self, twitch_client: Option<TwitchClient>) -> Self {
+        match twitch_client {
+            Some(twitch_client) => self.with_provider(twitch_client),
+            None => self,
         }
+    }
 
-        let mut enriched_games = Vec::new();
-        for game in games_with_ids {
-            let harmony_score = calculate_harmony_score(&game.rankings);
-            let mut entry = Game::new(game.name, game.rankings, harmony_score);
-            entry.steam_id = game.steam_id.as_ref().map(|id| id.parse().unwrap());
-
-            if let Some(steam_id) = &game.steam_id {
-                if let Ok(Some(store_info)) = self
-                    .steam_client
-                    .get_store_info(steam_id.parse().unwrap())
-                    .await
-                {
-                    entry = entry.with_steam_info(store_info);
-                }
+    pub fn with_backloggd(self, backloggd_client: Option<BackloggdClient>) -> Self {
+        match backloggd_client {
+            Some(backloggd_client) => self.with_provider(backloggd_client),
+            None => self,
+        }
+    }
+
+    /// Only queries the `selected` providers, which must have been added
+    pub fn with_providers(mut self, selected: Option<Vec<String>>) -> Result<Self> {
+        let added: Vec<&str> = self.providers.iter().map(|p| p.name()).collect();
+        for provider in selected.iter().flatten() {
+            if !added.contains(&provider.as_str()) {
+                return Err(GameError::Other(format!(
+                    "Unknown or unconfigured enrichment provider {:?}, the available ones are {}",
+                    provider,
+                    added.join(", ")
+                )));
+            }
+        }
+        self.selected = selected;
+        Ok(self)
+    }
+
+    pub fn with_provenance(mut self, with_provenance: bool) -> Self {
+        self.with_provenance = with_provenance;
+        self
+    }
+
+    pub fn with_conflict_policies(
+        mut self,
+        conflict_policies: BTreeMap<String, ConflictPolicy>,
+    ) -> Self {
+        self.conflict_policies = conflict_policies;
+        self
+    }
+
+    fn selects(&self, provider: &str) -> bool {
+        self.selected
+            .as_ref()
+            .is_none_or(|selected| selected.iter().any(|p| p == provider))
+    }
+
+    /// The added providers that were selected, in the order they are queried
+    fn active_providers(&self) -> impl Iterator<Item = &dyn EnrichmentProvider> {
+        self.providers
+            .iter()
+            .map(|provider| provider.as_ref())
+            .filter(|provider| self.selects(provider.name()))
+    }
+
+    /// Whether `provider` is queried during enrichment
+    pub fn uses(&self, provider: &str) -> bool {
+        self.active_providers().any(|p| p.name() == provider)
+    }
+
+    /// Names of the providers queried during enrichment
+    pub fn used_providers(&self) -> Vec<String> {
+        self.active_providers()
+            .map(|provider| provider.name().to_string())
+            .collect()
+    }
+
+    /// Indexes the enriched games of an earlier run by normalized title, for
+    /// looking up the previous entry of a game in `enrich_game`
+    pub(crate) fn index_previous(previous: Vec<Game>) -> HashMap<String, Game> {
+        previous
+            .into_iter()
+            .map(|game| (TitleNormalizer::normalize(&game.title), game))
+            .collect()
+    }
+
+    /// Enriches a game with data of the selected providers.
+    ///
+    /// If the game was enriched in an earlier run (found in `previous`), provider
+    /// data is only re-queried once it is older than the provider's max age,
+    /// otherwise the provider caches are used.
+    pub(crate) async fn enrich_game(
+        &self,
+        game: GameWithSteamId,
+        previous: &HashMap<String, Game>,
+        scorer: &HarmonyScorer,
+        progress: &ProgressBar,
+    ) -> Game {
+        let title = game.name.clone();
+        let step = |provider: &str| progress.set_message(format!("{} ({})", title, provider));
+        let harmony_score = scorer.score(&game.rankings);
+        let previous_entry = previous.get(&TitleNormalizer::normalize(&game.name));
+        let is_stale = |provider: &str, max_age: Duration| {
+            previous_entry.is_some_and(|p| !p.is_fresh(provider, max_age))
+        };
 
-                if let Ok(deck_status) = self.steam_client.get_deck_verified(steam_id.clone()).await
-                {
-                    entry = entry.with_steam_deck_info(deck_status, steam_id.clone());
+        let mut entry = Game::new(game.name, game.rankings, harmony_score);
+        entry.ranking_links = game.ranking_links;
+        entry.quotes = game.quotes;
+        entry.steam_id = game.steam_id.as_ref().map(|id| id.parse().unwrap());
+        entry.alternate_titles = game
+            .original_names
+            .into_iter()
+            .filter(|name| *name != entry.title)
+            .collect();
+
+        // Provider and value of each conflict field so far
+        let mut field_sources = HashMap::new();
+        for provider in self.active_providers() {
+            let name = provider.name();
+            let refresh = provider
+                .max_age()
+                .is_none_or(|max_age| is_stale(name, max_age));
+            step(name);
+            // Looked up before enriching, which may change the cache keys
+            let cached_at = if refresh {
+                None
+            } else {
+                provider.cached_at(&entry).unwrap_or_else(|e| {
+                    warn!("Can't tell when the {} cache was written: {}", name, e);
+                    None
+                })
+            };
+            match provider.enrich(&mut entry, refresh).await {
+                Ok(EnrichmentOutcome::Enriched(fields)) => {
+                    entry.mark_enriched(name, previous_entry, cached_at, refresh);
+                    self.resolve_conflicts(&mut entry, name, &mut field_sources);
+                    if self.with_provenance {
+                        record_provenance(&mut entry, &fields, name, &field_sources);
+                    }
                 }
+                Ok(EnrichmentOutcome::NotFound | EnrichmentOutcome::Skipped) => {}
+                Err(e) => self.fail(&entry.title, name, e),
             }
+        }
+
+        // Store descriptions may carry markup and tracking pixels
+        if let Some(description) = entry.short_description.take() {
+            entry.summary = summarize(&description, SUMMARY_MAX_CHARS);
+            entry.short_description = Some(strip_html(&description)).filter(|d| !d.is_empty());
+            if let Some(provider) = entry.provenance.get("short_description").cloned() {
+                entry.provenance.insert("summary".to_string(), provider);
+            }
+        }
 
-            if let Ok(Some(detailed)) = self.rawg_client.get_game_info(&entry.title).await {
-                entry = entry.with_rawg_info(&detailed);
+        entry.harmony_id = harmony_id(&entry.title, entry.steam_id);
+        entry.title = TitleNormalizer::format_for_display(&entry.title, &self.casing_rules);
+        entry.slug = slugify(&entry.title);
+        if entry.slug.is_empty() {
+            entry.slug = entry.harmony_id.clone();
+        }
+        REQUEST_STATS
+            .throttle("enrichment", Duration::from_millis(650))
+            .await;
+        entry
+    }
+
+    /// Merges the conflict fields `provider` just set with the values of the
+    /// providers before it, following the field's `ConflictPolicy`
+    fn resolve_conflicts(
+        &self,
+        entry: &mut Game,
+        provider: &str,
+        field_sources: &mut HashMap<&'static str, (String, String)>,
+    ) {
+        for field in CONFLICT_FIELDS {
+            let value = entry.conflict_value(field);
+            let Some((earlier, earlier_value)) = field_sources.get(field).cloned() else {
+                if let Some(value) = value {
+                    field_sources.insert(field, (provider.to_string(), value));
+                }
+                continue;
+            };
+            let Some(value) = value else {
+                // Providers don't clear what an earlier one found
+                entry.set_conflict_value(field, Some(earlier_value));
+                continue;
+            };
+            if value == earlier_value {
+                continue;
             }
 
-            entry.title = TitleNormalizer::format_for_display(&entry.title);
-            enriched_games.push(entry);
-            sleep(Duration::from_millis(650)).await;
+            let policy = self
+                .conflict_policies
+                .get(field)
+                .copied()
+                .unwrap_or_default();
+            let keep_later = match policy {
+                ConflictPolicy::PreferSteam | ConflictPolicy::KeepBoth => {
+                    provider == STEAM_PROVIDER
+                }
+                ConflictPolicy::PreferNewest => {
+                    let fetched_at = |provider: &str| {
+                        entry
+                            .last_enriched_at
+                            .get(provider)
+                            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    };
+                    fetched_at(provider) >= fetched_at(&earlier)
+                }
+            };
+            let values = BTreeMap::from([
+                (earlier.clone(), earlier_value.clone()),
+                (provider.to_string(), value.clone()),
+            ]);
+            if policy == ConflictPolicy::KeepBoth {
+                entry
+                    .alternate_values
+                    .entry(field.to_string())
+                    .or_default()
+                    .extend(values.clone());
+            }
+            let kept = if keep_later {
+                (provider.to_string(), value)
+            } else {
+                (earlier, earlier_value)
+            };
+            entry.set_conflict_value(field, Some(kept.1.clone()));
+            self.conflicts.lock().unwrap().push(FieldConflict {
+                title: entry.title.clone(),
+                field: field.to_string(),
+                values,
+                kept: kept.0.clone(),
+            });
+            field_sources.insert(field, kept);
         }
+    }
 
-        enriched_games.sort_by(|a, b| b.harmony_score.cmp(&a.harmony_score));
-        self.store.save_enriched_games(&enriched_games)?;
-        Ok(enriched_games)
+    fn fail(&self, title: &str, provider: &str, cause: GameError) {
+        self.errors
+            .record(GameError::enrichment_failed(title, provider, cause));
+    }
+}
+
+/// Records `provider` as the source of the `fields` it set, and the provider
+/// whose value was kept as the source of each conflict field
+fn record_provenance(
+    game: &mut Game,
+    fields: &[&str],
+    provider: &str,
+    field_sources: &HashMap<&'static str, (String, String)>,
+) {
+    for field in fields {
+        game.provenance
+            .insert(field.to_string(), provider.to_string());
+    }
+    for (field, (source, _)) in field_sources {
+        game.provenance.insert(field.to_string(), source.clone());
     }
 }
@@ -1,24 +1,57 @@
 use crate::config::Config;
+use crate::domain::numbers::{Rank, Score};
 use crate::domain::storage::Storage;
-use crate::domain::{Game, Manifest};
-use crate::error::Result;
-use crate::services::matching::GameWithSteamId;
+use crate::domain::{AppIdMerge, EnrichmentInfo, Game, Manifest, SourceDrift};
+use crate::error::{GameError, Result};
+use crate::infrastructure::{SteamClient, REQUEST_STATS};
+use crate::services::enrichment::{RAWG_PROVIDER, STEAM_PROVIDER};
+use crate::services::matching::{GameWithSteamId, MatchingConfig};
 use crate::services::merging::MergedGame;
-use crate::services::scraping::WebsiteGames;
+use crate::services::progress;
+use crate::services::scoring::{smooth_harmony_score, HarmonyScorer};
+use crate::services::scraping::{SourceFetch, WebsiteGames};
+use crate::services::text_utils::TitleNormalizer;
 use crate::services::{
-    enrichment::Enrichment, matching::MatchingService, merging::MergingService,
+    enrichment::Enrichment, matching::MatchingService, merging::MergingService, runs::RunService,
     scraping::ScrapingService,
 };
-use std::sync::Arc;
-use tracing::info;
+use indicatif::ProgressBar;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OnceCell};
+use tracing::{info, info_span, warn, Instrument};
+
+/// Number of games buffered between two pipeline stages
+const STAGE_CHANNEL_CAPACITY: usize = 32;
+/// Games matched per batch, so the first games reach enrichment early while
+/// the batch still runs in parallel
+const MATCH_CHUNK_SIZE: usize = 16;
+
+/// Share of its previous games a source may lose before the scrape counts as
+/// drifted, e.g. because the site changed its layout
+const DRIFT_THRESHOLD: f64 = 0.8;
+/// Sources that previously listed fewer games are never considered drifted
+const DRIFT_MIN_GAMES: usize = 5;
 
 pub struct GameService {
     config: Config,
     store: Arc<dyn Storage>,
     scraping: ScrapingService,
     merging: MergingService,
-    matching: MatchingService,
+    /// Built on first use, so runs served from the matched games cache neither
+    /// load the Steam index nor fetch the app list
+    matching: OnceCell<MatchingService>,
     enrichment: Enrichment,
+    runs: RunService,
+    /// Normalized `exclude_titles` and `pin_titles` of the scraper config
+    excluded: HashSet<String>,
+    pinned: HashSet<String>,
+    /// Sources of this run that drifted, for the run record
+    drifted: Mutex<Vec<SourceDrift>>,
+    /// Games of this run merged for sharing a Steam app, for the run record
+    merged_app_ids: Mutex<Vec<AppIdMerge>>,
 }
 
 impl GameService {
@@ -27,20 +60,100 @@ impl GameService {
         store: Arc<dyn Storage + 'static>,
         scraping: ScrapingService,
         merging: MergingService,
-        matching: MatchingService,
         enrichment: Enrichment,
+        runs: RunService,
     ) -> Self {
+        let normalized = |titles: &[String]| {
+            titles
+                .iter()
+                .map(|t| TitleNormalizer::normalize(t))
+                .collect()
+        };
+        let excluded = normalized(&config.scraper_config.exclude_titles);
+        let pinned = normalized(&config.scraper_config.pin_titles);
         Self {
             config,
             store,
             scraping,
             merging,
-            matching,
+            matching: OnceCell::new(),
             enrichment,
+            runs,
+            excluded,
+            pinned,
+            drifted: Mutex::new(Vec::new()),
+            merged_app_ids: Mutex::new(Vec::new()),
         }
     }
 
     pub async fn process(&self) -> Result<()> {
+        let mut run = self.runs.start()?;
+        info!("Starting run {} ({})", run.id, run.key);
+        let started = Instant::now();
+
+        // Interrupting drops the pipeline, cancelling its requests. Storage
+        // writes are synchronous, so none is left half done.
+        let outcome = tokio::select! {
+            outcome = self
+                .run_pipeline(run.id)
+                .instrument(info_span!("run", id = run.id)) => outcome,
+            signal = shutdown_signal() => {
+                warn!("Interrupted by {}, the next run resumes the enriched games", signal);
+                Err(GameError::Interrupted(signal))
+            }
+        };
+
+        self.enrichment.errors.log();
+        run.drifted = std::mem::take(&mut *self.drifted.lock().unwrap());
+        run.merged_app_ids = std::mem::take(&mut *self.merged_app_ids.lock().unwrap());
+        run.field_conflicts = std::mem::take(&mut *self.enrichment.conflicts.lock().unwrap());
+        self.runs.finish(&mut run, &outcome)?;
+        self.record_rawg_usage(run.id)?;
+        self.report_requests(started.elapsed())?;
+        outcome.map(|_| ())
+    }
+
+    /// Logs where the run spent its requests and rate limiter waits, including
+    /// the shortest duration the current limits allow. If the run took about
+    /// that long, more concurrency or API keys would help; otherwise they won't.
+    fn report_requests(&self, elapsed: Duration) -> Result<()> {
+        let report = REQUEST_STATS.report();
+        for (host, requests) in &report.hosts {
+            info!("{}: {} requests", host, requests);
+        }
+        for (name, limiter) in &report.limiters {
+            info!(
+                "Limiter {}: waited {:.1}s over {} waits, needs at least {:.1}s",
+                name,
+                limiter.waited.as_secs_f64(),
+                limiter.waits,
+                limiter.minimum_duration().as_secs_f64()
+            );
+        }
+        info!(
+            "Run took {:.1}s, current limits allow at least {:.1}s",
+            elapsed.as_secs_f64(),
+            report.minimum_duration().as_secs_f64()
+        );
+        self.store.save_request_report(&report)
+    }
+
+    fn record_rawg_usage(&self, run_id: u64) -> Result<()> {
+        let Some(rawg_client) = &self.enrichment.rawg_client else {
+            return Ok(());
+        };
+        let requests = rawg_client.requests_made();
+        let mut usage = self.store.load_rawg_usage()?;
+        usage.record(run_id, requests);
+        self.store.save_rawg_usage(&usage)?;
+        info!(
+            "Made {} RAWG requests, {} in total",
+            requests, usage.total_requests
+        );
+        Ok(())
+    }
+
+    async fn run_pipeline(&self, run_id: u64) -> Result<usize> {
         info!("Starting game data processing pipeline");
 
         let website_games = self.scrape_websites().await?;
@@ -48,6 +161,34 @@ impl GameService {
             "Website games processing completed: {} sources",
             website_games.len()
         );
+        let fetches: HashMap<String, (SourceFetch, usize)> = website_games
+            .iter()
+            .map(|website| {
+                (
+                    website.source.clone(),
+                    (website.fetch.clone(), website.games.len()),
+                )
+            })
+            .collect();
+
+        let scorer = HarmonyScorer::new(
+            website_games
+                .iter()
+                .map(|website| {
+                    (
+                        TitleNormalizer::normalize_source(
+                            &website.source,
+                            &self.config.scraper_config,
+                        ),
+                        website.list_length(),
+                    )
+                })
+                .collect(),
+        )
+        .with_strategy(
+            self.config.scraper_config.scoring.strategy,
+            self.config.scraper_config.scoring.compare_strategies,
+        );
 
         let merged_games = self.merge_games(website_games).await?;
         info!(
@@ -55,16 +196,23 @@ impl GameService {
             merged_games.len()
         );
 
-        let games_with_steam = self.add_steam_ids(merged_games).await?;
-        info!("Steam matching completed");
+        let mut enriched_games = self.match_and_enrich(merged_games, &scorer).await?;
+        info!("Game matching and enrichment completed");
+        self.keep_harmony_ids(&mut enriched_games)?;
+        self.score_genres(&mut enriched_games, &scorer);
 
-        let enriched_games = self.enrich_games(games_with_steam).await?;
-        info!("Game enrichment completed");
+        if let Some(alpha) = self.config.args.smoothing_alpha {
+            self.smooth_scores(&mut enriched_games, alpha);
+        }
+        HarmonyScorer::estimate_confidence(&mut enriched_games);
+        self.save_scores(&enriched_games)?;
 
-        self.save_final_manifest(enriched_games).await?;
+        let games = self.filter_output(enriched_games);
+        let total_games = games.len();
+        self.save_final_manifest(games, run_id, &fetches).await?;
         info!("Processing pipeline completed successfully");
 
-        Ok(())
+        Ok(total_games)
     }
 
     /// This method is going through all sources in the `scraper_config.json`,
@@ -78,14 +226,17 @@ impl GameService {
 
         if !self.config.args.skip_cache {
             for website in self.config.scraper_config.websites.clone() {
-                if let Some(website) = self.store.load_website_games(website.clone().url)? {
+                // Local lists are cheap to read and may have been edited
+                if website.is_local() {
+                    to_scrape.push(website);
+                } else if let Some(website) = self.store.load_website_games(website.clone().url)? {
                     website_games.push(website);
                 } else {
                     to_scrape.push(website);
                 }
             }
 
-            let mut games = self.scraping.scrape_all(&to_scrape).await?;
+            let mut games = self.without_drifted(self.scraping.scrape_all(&to_scrape).await?)?;
 
             games.extend(website_games);
 
@@ -97,52 +248,679 @@ impl GameService {
             .scraping
             .scrape_all(&self.config.scraper_config.websites)
             .await?;
+        let games = self.without_drifted(games)?;
 
         self.store.save_website_games(&games)?;
 
         Ok(games)
     }
 
+    /// Replaces the scrapes that lost most of the games of the source's
+    /// previous scrape with the previous one, so a broken selector doesn't
+    /// drop the source from the rankings. Local lists are taken as they are.
+    fn without_drifted(&self, games: Vec<WebsiteGames>) -> Result<Vec<WebsiteGames>> {
+        let mut checked = Vec::with_capacity(games.len());
+        for website in games {
+            let is_local = self
+                .config
+                .scraper_config
+                .websites
+                .iter()
+                .any(|w| w.url == website.source && w.is_local());
+            let previous = match self.store.load_website_games(website.source.clone())? {
+                Some(previous) if !is_local && Self::has_drifted(&previous, &website) => previous,
+                _ => {
+                    checked.push(website);
+                    continue;
+                }
+            };
+
+            let drift = SourceDrift {
+                source: website.source.clone(),
+                previous_games: previous.games.len(),
+                games: website.games.len(),
+                content_changed: previous.fetch.fingerprint != website.fetch.fingerprint,
+            };
+            warn!(
+                "{} drifted from {} to {} games, using the previous scrape",
+                drift.source, drift.previous_games, drift.games
+            );
+            self.drifted.lock().unwrap().push(drift);
+            checked.push(previous);
+        }
+        Ok(checked)
+    }
+
+    fn has_drifted(previous: &WebsiteGames, current: &WebsiteGames) -> bool {
+        let previous = previous.games.len();
+        previous >= DRIFT_MIN_GAMES
+            && (current.games.len() as f64) < previous as f64 * (1.0 - DRIFT_THRESHOLD)
+    }
+
     async fn merge_games(&self, website_games: Vec<WebsiteGames>) -> Result<Vec<MergedGame>> {
         if !self.config.args.skip_cache {
             if let Some(games) = self.store.load_merged_games()? {
                 info!("Using cached merged games data");
-                return Ok(games);
+                return Ok(self.without_excluded(games));
             }
         }
 
         let games = self.merging.merge_games(website_games)?;
         self.store.save_merged_games(&games)?;
+        Ok(self.without_excluded(games))
+    }
+
+    fn without_excluded(&self, mut games: Vec<MergedGame>) -> Vec<MergedGame> {
+        if self.excluded.is_empty() {
+            return games;
+        }
+        let before = games.len();
+        games.retain(|game| {
+            !self.excluded.contains(&game.normalized_name)
+                && !game
+                    .original_names
+                    .iter()
+                    .any(|name| self.excluded.contains(&TitleNormalizer::normalize(name)))
+        });
+        info!("Excluded {} games", before - games.len());
+        games
+    }
+
+    /// Streams the merged games through Steam matching, enrichment and
+    /// scoring. The stages run concurrently and talk through bounded channels,
+    /// so a slow enrichment holds back matching instead of piling up games.
+    ///
+    /// With `--incremental`, the cached enriched games are not reused as a whole.
+    /// Instead every game is rebuilt, and only provider data that exceeded its
+    /// max age gets queried again. Games an interrupted run already enriched
+    /// are taken over as they are.
+    async fn match_and_enrich(
+        &self,
+        merged_games: Vec<MergedGame>,
+        scorer: &HarmonyScorer,
+    ) -> Result<Vec<Game>> {
+        let mut previous = Vec::new();
+        let mut resumed = Vec::new();
+        if !self.config.args.skip_cache {
+            if let Some(mut games) = self.store.load_enriched_games()? {
+                if !self.config.args.incremental {
+                    info!("Using cached enriched games data");
+                    // The strategy or the lists may have changed since
+                    scorer.score_all(&mut games);
+                    games.sort_by(Game::cmp_by_score);
+                    return Ok(games);
+                }
+                info!("Incrementally re-enriching {} cached games", games.len());
+                previous = games;
+            }
+
+            resumed = self.store.load_enriched_progress()?;
+            if !resumed.is_empty() {
+                info!(
+                    "Resuming {} games enriched by an interrupted run",
+                    resumed.len()
+                );
+            }
+        }
+
+        let (matched_tx, matched_rx) = mpsc::channel(STAGE_CHANNEL_CAPACITY);
+        let (enriched_tx, enriched_rx) = mpsc::channel(STAGE_CHANNEL_CAPACITY);
+
+        let enrich_progress = progress::bar("enriching", merged_games.len() as u64);
+        let (_, _, games) = tokio::try_join!(
+            self.match_stage(merged_games, matched_tx, scorer),
+            self.enrich_stage(
+                matched_rx,
+                enriched_tx,
+                Enrichment::index_previous(previous),
+                Enrichment::index_previous(resumed),
+                scorer,
+                &enrich_progress,
+            ),
+            self.score_stage(enriched_rx, scorer),
+        )?;
+        enrich_progress.finish_and_clear();
         Ok(games)
     }
 
-    async fn add_steam_ids(&self, merged_games: Vec<MergedGame>) -> Result<Vec<GameWithSteamId>> {
+    async fn match_stage(
+        &self,
+        merged_games: Vec<MergedGame>,
+        tx: mpsc::Sender<GameWithSteamId>,
+        scorer: &HarmonyScorer,
+    ) -> Result<()> {
         if !self.config.args.skip_cache {
             if let Some(games) = self.store.load_matched_games()? {
                 info!("Using cached Steam-matched games data");
-                return Ok(games);
+                let mut games = self.config.match_overrides.apply(games);
+                games.sort_by_cached_key(|game| {
+                    (
+                        Reverse(scorer.score(&game.rankings)),
+                        game.name.clone(),
+                        game.steam_id.clone(),
+                    )
+                });
+                for game in games {
+                    if tx.send(game).await.is_err() {
+                        break;
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        let matching = self.matching().await?;
+        let progress = progress::bar("matching", merged_games.len() as u64);
+        let mut matched_games = Vec::with_capacity(merged_games.len());
+        let mut pending = Self::by_priority(merged_games, scorer).into_iter();
+        loop {
+            let chunk: Vec<MergedGame> = pending.by_ref().take(MATCH_CHUNK_SIZE).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            // Matching is CPU-bound, keep it off the async workers
+            let matched = tokio::task::block_in_place(|| matching.match_games(chunk));
+            for game in matched {
+                progress.set_message(game.name.clone());
+                progress.inc(1);
+                matched_games.push(game.clone());
+                if tx.send(game).await.is_err() {
+                    // A later stage failed, its error is reported by try_join
+                    return Ok(());
+                }
+            }
+        }
+        progress.finish_and_clear();
+        info!("Steam matching completed");
+
+        self.store.save_matched_games(&matched_games)?;
+        Ok(())
+    }
+
+    async fn matching(&self) -> Result<&MatchingService> {
+        self.matching.get_or_try_init(|| self.load_matching()).await
+    }
+
+    /// The matching service over the cached Steam index, rebuilt from the app
+    /// list if there is none or the app list is older than its max age
+    async fn load_matching(&self) -> Result<MatchingService> {
+        let args = &self.config.args;
+        let matching_config = || MatchingConfig {
+            backend: args.match_index,
+            ..MatchingConfig::default()
+        };
+        let steam = SteamClient::new(
+            self.config.http_client.clone(),
+            Arc::clone(&self.store),
+            args.steam_api_key.as_deref(),
+        )
+        .with_app_list_max_age(args.app_list_max_age);
+
+        let cached = if steam.app_list_is_fresh()? {
+            MatchingService::from_cache(Arc::clone(&self.store), matching_config())?
+        } else {
+            None
+        };
+        let matching = match cached {
+            Some(matching) => matching,
+            None => MatchingService::new(
+                steam.steam_apps().await?,
+                Arc::clone(&self.store),
+                matching_config(),
+            )?,
+        };
+        Ok(matching.with_overrides(self.config.match_overrides.clone()))
+    }
+
+    /// Orders games by harmony score, so a limited enrichment budget goes to
+    /// the games that matter most
+    fn by_priority(mut games: Vec<MergedGame>, scorer: &HarmonyScorer) -> Vec<MergedGame> {
+        games.sort_by_cached_key(|game| {
+            (
+                Reverse(scorer.score(&game.rankings)),
+                game.normalized_name.clone(),
+            )
+        });
+        games
+    }
+
+    async fn enrich_stage(
+        &self,
+        mut rx: mpsc::Receiver<GameWithSteamId>,
+        tx: mpsc::Sender<Game>,
+        previous: HashMap<String, Game>,
+        mut resumed: HashMap<String, Game>,
+        scorer: &HarmonyScorer,
+        progress: &ProgressBar,
+    ) -> Result<()> {
+        while let Some(game) = rx.recv().await {
+            let game = match resumed.remove(&TitleNormalizer::normalize(&game.name)) {
+                Some(resumed) => resumed,
+                None => {
+                    self.enrichment
+                        .enrich_game(game, &previous, scorer, progress)
+                        .await
+                }
+            };
+            progress.inc(1);
+            if tx.send(game).await.is_err() {
+                break;
             }
         }
+        Ok(())
+    }
 
-        let games = self.matching.match_games(merged_games).await?;
-        self.store.save_matched_games(&games)?;
+    /// Collects the enriched games, scores them with the configured strategy
+    /// and ranks them. Every game is written to storage as it arrives, so an
+    /// aborted run keeps its progress.
+    async fn score_stage(
+        &self,
+        mut rx: mpsc::Receiver<Game>,
+        scorer: &HarmonyScorer,
+    ) -> Result<Vec<Game>> {
+        self.store.clear_enriched_progress()?;
+
+        let mut games = Vec::new();
+        while let Some(game) = rx.recv().await {
+            self.store.append_enriched_game(&game)?;
+            games.push(game);
+        }
+
+        let mut games = self.merge_app_id_collisions(games);
+        scorer.score_all(&mut games);
+        games.sort_by(Game::cmp_by_score);
+        self.store.save_enriched_games(&games)?;
         Ok(games)
     }
 
-    async fn enrich_games(&self, games_with_steam: Vec<GameWithSteamId>) -> Result<Vec<Game>> {
-        if !self.config.args.skip_cache {
-            if let Some(games) = self.store.load_enriched_games()? {
-                info!("Using cached enriched games data");
-                return Ok(games);
+    /// Merges games matched to the same Steam app, e.g. a GOTY edition listed
+    /// under its own title, into the first of them. Each source keeps its
+    /// best rank of the merged entries. Titles of `editions.keep_separate`
+    /// are never merged.
+    fn merge_app_id_collisions(&self, games: Vec<Game>) -> Vec<Game> {
+        let mut kept: Vec<Game> = Vec::with_capacity(games.len());
+        let mut by_app_id: HashMap<u64, usize> = HashMap::new();
+        let mut merges: BTreeMap<u64, AppIdMerge> = BTreeMap::new();
+        for game in games {
+            let Some(steam_id) = game.steam_id else {
+                kept.push(game);
+                continue;
+            };
+            let Some(&i) = by_app_id.get(&steam_id) else {
+                by_app_id.insert(steam_id, kept.len());
+                kept.push(game);
+                continue;
+            };
+            if self.is_kept_separate(&game.title) || self.is_kept_separate(&kept[i].title) {
+                kept.push(game);
+                continue;
             }
+
+            let target = &mut kept[i];
+            for (source, rank) in game.rankings {
+                let best = target.rankings.entry(source).or_insert(rank);
+                if let (Some(current), Some(rank)) = (*best, rank) {
+                    *best = Some(current.min(rank));
+                } else if best.is_none() {
+                    *best = rank;
+                }
+            }
+            for (source, link) in game.ranking_links {
+                target.ranking_links.entry(source).or_insert(link);
+            }
+            for quote in game.quotes {
+                if !target.quotes.iter().any(|q| q.source == quote.source) {
+                    target.quotes.push(quote);
+                }
+            }
+            for title in std::iter::once(&game.title).chain(&game.alternate_titles) {
+                if *title != target.title && !target.alternate_titles.contains(title) {
+                    target.alternate_titles.push(title.clone());
+                }
+            }
+            merges
+                .entry(steam_id)
+                .or_insert_with(|| AppIdMerge {
+                    steam_id,
+                    kept: target.title.clone(),
+                    merged: Vec::new(),
+                })
+                .merged
+                .push(game.title);
+        }
+
+        for merge in merges.values() {
+            warn!(
+                "Merged {:?} into {} for sharing Steam app {}",
+                merge.merged, merge.kept, merge.steam_id
+            );
+        }
+        self.merged_app_ids
+            .lock()
+            .unwrap()
+            .extend(merges.into_values());
+        kept
+    }
+
+    /// Whether `title`, or the game it is an edition of, is one of
+    /// `editions.keep_separate`
+    fn is_kept_separate(&self, title: &str) -> bool {
+        let editions = &self.config.scraper_config.editions;
+        let (base, _) = TitleNormalizer::split_edition(title, &editions.suffixes);
+        let titles = [
+            TitleNormalizer::normalize(title),
+            TitleNormalizer::normalize(base),
+        ];
+        editions
+            .keep_separate
+            .iter()
+            .any(|separate| titles.contains(&TitleNormalizer::normalize(separate)))
+    }
+
+    /// Scores every game per genre with the configured strategy, from the
+    /// rankings of that genre's sources alone, so niche lists aren't drowned
+    /// out by the general ones
+    fn score_genres(&self, games: &mut [Game], scorer: &HarmonyScorer) {
+        let genres: HashMap<String, &str> = self
+            .config
+            .scraper_config
+            .websites
+            .iter()
+            .filter_map(|w| Some((w.source_name(), w.genre.as_deref()?)))
+            .collect();
+        if genres.is_empty() {
+            return;
+        }
+
+        // Rankings of each genre's sources, by genre and game
+        let mut by_genre: BTreeMap<&str, BTreeMap<usize, HashMap<String, Option<Rank>>>> =
+            BTreeMap::new();
+        for (i, game) in games.iter().enumerate() {
+            for (source, rank) in &game.rankings {
+                if let Some(genre) = genres.get(source) {
+                    by_genre
+                        .entry(genre)
+                        .or_default()
+                        .entry(i)
+                        .or_default()
+                        .insert(source.clone(), *rank);
+                }
+            }
+        }
+
+        for game in games.iter_mut() {
+            game.genre_scores.clear();
+        }
+        for (genre, rankings) in by_genre {
+            let scores = scorer.score_rankings(&rankings.values().collect::<Vec<_>>());
+            for (i, score) in rankings.keys().zip(scores) {
+                games[*i].genre_scores.insert(genre.to_string(), score);
+            }
+        }
+    }
+
+    /// Blends the harmony scores with those of the previous run, keeping the
+    /// unsmoothed score next to it
+    fn smooth_scores(&self, games: &mut [Game], alpha: f64) {
+        let previous = match self.previous_scores() {
+            Ok(Some(previous)) => previous,
+            Ok(None) => {
+                warn!("No scores of a previous run, scores stay unsmoothed");
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "Can't read the previous scores, scores stay unsmoothed: {}",
+                    e
+                );
+                return;
+            }
+        };
+
+        for game in games.iter_mut() {
+            if let Some(previous_score) = previous.get(&TitleNormalizer::normalize(&game.title)) {
+                let raw = game.harmony_score;
+                game.harmony_score = smooth_harmony_score(raw, *previous_score, alpha);
+                game.raw_harmony_score = Some(raw);
+            }
+        }
+        games.sort_by(Game::cmp_by_score);
+        info!("Smoothed harmony scores with alpha {}", alpha);
+    }
+
+    /// Scores of the previous run by normalized title, from the manifest for
+    /// runs that didn't save their scores yet
+    fn previous_scores(&self) -> Result<Option<BTreeMap<String, Score>>> {
+        if let Some(scores) = self.store.load_scores()? {
+            return Ok(Some(scores));
+        }
+        Ok(self
+            .store
+            .load_manifest()?
+            .map(|manifest| Self::scores(&manifest.games)))
+    }
+
+    /// Gives every game the harmony ID it got in an earlier run, found by its
+    /// Steam app or any of its titles, so changed normalization rules don't
+    /// change the IDs of published games
+    fn keep_harmony_ids(&self, games: &mut [Game]) -> Result<()> {
+        let mut ids = self.store.load_harmony_ids()?.unwrap_or_default();
+        let mut taken = HashSet::new();
+        for game in games.iter_mut() {
+            let keys = Self::harmony_id_keys(game);
+            let previous = keys
+                .iter()
+                .filter_map(|key| ids.get(key))
+                .find(|id| !taken.contains(*id))
+                .cloned();
+            if let Some(id) = previous {
+                if game.slug == game.harmony_id {
+                    game.slug = id.clone();
+                }
+                game.harmony_id = id;
+            }
+            taken.insert(game.harmony_id.clone());
+            for key in keys {
+                ids.insert(key, game.harmony_id.clone());
+            }
+        }
+        self.store.save_harmony_ids(&ids)
+    }
+
+    fn harmony_id_keys(game: &Game) -> Vec<String> {
+        let titles = std::iter::once(&game.title)
+            .chain(&game.alternate_titles)
+            .map(|title| format!("title:{}", title.to_lowercase()));
+        game.steam_id
+            .map(|id| format!("steam:{}", id))
+            .into_iter()
+            .chain(titles)
+            .collect()
+    }
+
+    /// Keeps the scores of all games, before `--top` and the thresholds, so
+    /// the next run can smooth games that didn't make the manifest
+    fn save_scores(&self, games: &[Game]) -> Result<()> {
+        self.store.save_scores(&Self::scores(games))
+    }
+
+    fn scores(games: &[Game]) -> BTreeMap<String, Score> {
+        games
+            .iter()
+            .map(|game| (TitleNormalizer::normalize(&game.title), game.harmony_score))
+            .collect()
+    }
+
+    /// Applies `--min-harmony-score`, `--min-sources` and `--top` to the
+    /// games, which are ordered by score already. Pinned games always stay.
+    fn filter_output(&self, games: Vec<Game>) -> Vec<Game> {
+        let args = &self.config.args;
+        let before = games.len();
+        let mut kept = 0;
+        let games: Vec<Game> = games
+            .into_iter()
+            .filter(|game| {
+                if self
+                    .pinned
+                    .contains(&TitleNormalizer::normalize(&game.title))
+                {
+                    return true;
+                }
+                let keep = args
+                    .min_harmony_score
+                    .is_none_or(|min| game.harmony_score >= min)
+                    && args
+                        .min_sources
+                        .is_none_or(|min| game.rankings.len() >= min)
+                    && (!args.released_only
+                        || game
+                            .release_status
+                            .is_none_or(|status| status.is_released()))
+                    && args.top.is_none_or(|top| kept < top);
+                if keep {
+                    kept += 1;
+                }
+                keep
+            })
+            .collect();
+        if games.len() < before {
+            info!("Kept {} of {} games for the manifest", games.len(), before);
         }
+        games
+    }
 
-        self.enrichment.enrich_games(games_with_steam).await
+    /// Limits `used`, derived from the games' data, to the providers queried
+    /// during this run
+    fn record_enrichment_used(&self, used: &mut EnrichmentInfo) {
+        used.steam &= self.enrichment.uses(STEAM_PROVIDER);
+        used.rawg &= self.enrichment.uses(RAWG_PROVIDER);
+        used.providers = self.enrichment.used_providers();
     }
 
-    async fn save_final_manifest(&self, games: Vec<Game>) -> Result<()> {
-        let manifest = Manifest::new(games);
+    /// Writes the manifest, describing every source with its attribution and
+    /// the `fetches` of this run, keyed by URL
+    async fn save_final_manifest(
+        &self,
+        games: Vec<Game>,
+        run_id: u64,
+        fetches: &HashMap<String, (SourceFetch, usize)>,
+    ) -> Result<()> {
+        let mut manifest = Manifest::new(games);
+        manifest.metadata.run_id = Some(run_id);
+        manifest.metadata.smoothing_alpha = self.config.args.smoothing_alpha;
+        self.record_enrichment_used(&mut manifest.metadata.enrichment_used);
+        for source in &mut manifest.metadata.sources {
+            if let Some(website) = self
+                .config
+                .scraper_config
+                .websites
+                .iter()
+                .find(|w| w.source_name() == source.name)
+            {
+                if website.category.is_some() {
+                    source.outlet = Some(website.display_name.clone());
+                    source.category = website.category.clone();
+                }
+                source.url = Some(website.url.clone());
+                source.attribution = website.attribution.clone();
+                source.license_note = website.license_note.clone();
+                if let Some((fetch, item_count)) = fetches.get(&website.url) {
+                    source.scraped_at = fetch.scraped_at.clone();
+                    source.http_status = fetch.http_status;
+                    source.item_count = Some(*item_count);
+                    source.author = fetch.author.clone();
+                    source.published_at = fetch.published_at.clone();
+                    source.updated_at = fetch.modified_at.clone();
+                }
+            }
+        }
+        manifest.selected_fields = self
+            .config
+            .args
+            .fields
+            .clone()
+            .or_else(|| self.config.scraper_config.output.fields.clone());
+        if self.config.args.with_provenance {
+            if let Some(fields) = &mut manifest.selected_fields {
+                fields.push("provenance".to_string());
+            }
+        }
         self.store.save_manifest(&manifest)?;
+        if let Some(chunk_size) = self.config.args.manifest_chunk_size {
+            self.store.save_manifest_chunks(&manifest, chunk_size)?;
+        }
+        if self.config.args.genre_manifests {
+            self.save_genre_manifests(&manifest)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a manifest per genre with its games, scored and ordered by
+    /// their genre score
+    fn save_genre_manifests(&self, manifest: &Manifest) -> Result<()> {
+        let genres: BTreeSet<&String> = self
+            .config
+            .scraper_config
+            .websites
+            .iter()
+            .filter_map(|w| w.genre.as_ref())
+            .collect();
+        for genre in genres {
+            let mut games: Vec<Game> = manifest
+                .games
+                .iter()
+                .filter_map(|game| {
+                    let score = *game.genre_scores.get(genre)?;
+                    let mut game = game.clone();
+                    game.harmony_score = score;
+                    game.raw_harmony_score = None;
+                    Some(game)
+                })
+                .collect();
+            games.sort_by(Game::cmp_by_score);
+            info!("Writing {} games of genre {}", games.len(), genre);
+
+            // Sources keep the attribution and fetch details of the manifest
+            let mut genre_manifest = Manifest::new(games);
+            genre_manifest.metadata.run_id = manifest.metadata.run_id;
+            self.record_enrichment_used(&mut genre_manifest.metadata.enrichment_used);
+            for source in &mut genre_manifest.metadata.sources {
+                if let Some(info) = manifest
+                    .metadata
+                    .sources
+                    .iter()
+                    .find(|s| s.name == source.name)
+                {
+                    *source = info.clone();
+                }
+            }
+            genre_manifest.selected_fields = manifest.selected_fields.clone();
+            self.store.save_genre_manifest(genre, &genre_manifest)?;
+        }
         Ok(())
     }
 }
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix, with the name of the signal
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                warn!("Can't listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
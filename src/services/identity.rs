@@ -0,0 +1,35 @@
+use crate::services::text_utils::TitleNormalizer;
+
+/// Makes a URL path segment of a title, e.g. "Baldur's Gate 3" becomes
+/// "baldurs-gate-3"
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars().filter(|c| !matches!(c, '\'' | '’')) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(slug.trim_end_matches('-').len());
+    slug
+}
+
+/// ID of a game that survives changes to its display title, derived from the
+/// normalized title and the Steam app ID. Hashed with FNV-1a, so it doesn't
+/// depend on the Rust version like `DefaultHasher` does. Games keep the ID of
+/// their first run, as the normalization rules may change after it.
+pub fn harmony_id(title: &str, steam_id: Option<u64>) -> String {
+    let key = match steam_id {
+        Some(id) => format!("{}:{}", TitleNormalizer::normalize(title), id),
+        None => TitleNormalizer::normalize(title),
+    };
+    format!("{:016x}", fnv1a(key.as_bytes()))
+}
+
+/// 64 bit FNV-1a hash, stable across Rust versions and platforms
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,238 @@
+use crate::config::MatchOverrides;
+use crate::domain::storage::Storage;
+use crate::error::{GameError, Result};
+use crate::services::matching::{GameWithSteamId, MatchCandidate, MatchingService};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+const CANDIDATE_LIMIT: usize = 10;
+
+/// A matched game that needs a manual decision
+struct PendingGame {
+    name: String,
+    steam_id: Option<String>,
+    candidates: Vec<MatchCandidate>,
+}
+
+enum Decision {
+    App(u64),
+    NotOnSteam,
+    Skip,
+    Quit,
+}
+
+/// Walks through unmatched and low-confidence matches in a terminal UI and
+/// writes the decisions to the overrides file used by `MatchingService`
+pub struct InteractiveMatch {
+    matching: MatchingService,
+    store: Arc<dyn Storage>,
+    overrides: MatchOverrides,
+    overrides_file: PathBuf,
+}
+
+impl InteractiveMatch {
+    pub fn new(
+        matching: MatchingService,
+        store: Arc<dyn Storage + 'static>,
+        overrides: MatchOverrides,
+        overrides_file: PathBuf,
+    ) -> Self {
+        Self {
+            matching,
+            store,
+            overrides,
+            overrides_file,
+        }
+    }
+
+    pub fn run(mut self, min_confidence: f64) -> Result<()> {
+        let matched_games = self.store.load_matched_games()?.ok_or_else(|| {
+            GameError::Other("No matched games cached, run the pipeline first".to_string())
+        })?;
+
+        let pending = self.pending_games(&matched_games, min_confidence);
+        info!("{} games need a decision", pending.len());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut terminal = ratatui::init();
+        let result = self.resolve_all(&mut terminal, &pending);
+        ratatui::restore();
+        let decided = result?;
+
+        // Keep the cached matches in sync, so the next run picks the decisions up
+        let matched_games = self.overrides.apply(matched_games);
+        self.store.save_matched_games(&matched_games)?;
+        info!("Saved {} decisions to {:?}", decided, self.overrides_file);
+        Ok(())
+    }
+
+    fn pending_games(
+        &self,
+        matched_games: &[GameWithSteamId],
+        min_confidence: f64,
+    ) -> Vec<PendingGame> {
+        matched_games
+            .iter()
+            .filter(|game| self.overrides.get(&game.name).is_none())
+            .filter_map(|game| {
+                let candidates = self.matching.candidates(&game.name, CANDIDATE_LIMIT);
+                let confidence = candidates.first().map_or(0.0, |c| c.similarity);
+                (game.steam_id.is_none() || confidence < min_confidence).then(|| PendingGame {
+                    name: game.name.clone(),
+                    steam_id: game.steam_id.clone(),
+                    candidates,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of decisions made
+    fn resolve_all(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        pending: &[PendingGame],
+    ) -> Result<usize> {
+        let mut decided = 0;
+        for (position, game) in pending.iter().enumerate() {
+            let decision = match Self::resolve(terminal, game, position, pending.len())? {
+                Decision::App(appid) => Some(appid),
+                Decision::NotOnSteam => None,
+                Decision::Skip => continue,
+                Decision::Quit => break,
+            };
+
+            self.overrides.set(&game.name, decision);
+            self.overrides.save(&self.overrides_file)?;
+            decided += 1;
+        }
+
+        Ok(decided)
+    }
+
+    fn resolve(
+        terminal: &mut DefaultTerminal,
+        game: &PendingGame,
+        position: usize,
+        total: usize,
+    ) -> Result<Decision> {
+        let mut list_state = ListState::default().with_selected(Some(0));
+        let mut input: Option<String> = None;
+
+        loop {
+            terminal.draw(|frame| {
+                Self::draw(
+                    frame,
+                    game,
+                    position,
+                    total,
+                    &mut list_state,
+                    input.as_deref(),
+                )
+            })?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(typed) = input.as_mut() {
+                match key.code {
+                    KeyCode::Char(c) if c.is_ascii_digit() => typed.push(c),
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Enter => {
+                        if let Ok(appid) = typed.parse() {
+                            return Ok(Decision::App(appid));
+                        }
+                    }
+                    KeyCode::Esc => input = None,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => list_state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => list_state.select_next(),
+                KeyCode::Enter => {
+                    if let Some(candidate) =
+                        list_state.selected().and_then(|i| game.candidates.get(i))
+                    {
+                        return Ok(Decision::App(candidate.appid));
+                    }
+                }
+                KeyCode::Char('i') => input = Some(String::new()),
+                KeyCode::Char('n') => return Ok(Decision::NotOnSteam),
+                KeyCode::Char('s') => return Ok(Decision::Skip),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Decision::Quit),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(
+        frame: &mut Frame,
+        game: &PendingGame,
+        position: usize,
+        total: usize,
+        list_state: &mut ListState,
+        input: Option<&str>,
+    ) {
+        let [header, candidates, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+
+        let current = match &game.steam_id {
+            Some(steam_id) => format!("Current match: {}", steam_id),
+            None => "Currently unmatched".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![game.name.clone().bold().into(), current.into()])
+                .block(Block::bordered().title(format!(" Game {}/{} ", position + 1, total))),
+            header,
+        );
+
+        let items: Vec<ListItem> = game
+            .candidates
+            .iter()
+            .map(|c| {
+                ListItem::new(format!(
+                    "{:>8}  {}  ({:.0}%)",
+                    c.appid,
+                    c.name,
+                    c.similarity * 100.0
+                ))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(" Candidates "))
+                .highlight_style(Style::new().reversed())
+                .highlight_symbol("> "),
+            candidates,
+            list_state,
+        );
+
+        let help = match input {
+            Some(typed) => format!("App ID: {}_   [enter] confirm  [esc] cancel", typed),
+            None => {
+                "[enter] pick  [i] type app ID  [n] not on Steam  [s] skip  [q] quit".to_string()
+            }
+        };
+        frame.render_widget(Paragraph::new(help).block(Block::bordered()), footer);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::error::Result;
+use crate::services::matching::MatchingService;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A scraped title and the Steam app it should match, `None` for games that
+/// aren't on Steam
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorpusEntry {
+    pub title: String,
+    pub appid: Option<u64>,
+}
+
+/// Counts of a corpus run, compared by appid
+#[derive(Debug, Default)]
+struct EvalReport {
+    correct: usize,
+    wrong: usize,
+    missed: usize,
+    false_matches: usize,
+    correctly_unmatched: usize,
+}
+
+impl EvalReport {
+    /// Share of the matches that are correct
+    fn precision(&self) -> f64 {
+        let matched = self.correct + self.wrong + self.false_matches;
+        if matched == 0 {
+            return 0.0;
+        }
+        self.correct as f64 / matched as f64
+    }
+
+    /// Share of the games on Steam that were matched correctly
+    fn recall(&self) -> f64 {
+        let on_steam = self.correct + self.wrong + self.missed;
+        if on_steam == 0 {
+            return 0.0;
+        }
+        self.correct as f64 / on_steam as f64
+    }
+}
+
+/// Runs the matcher against a curated corpus of titles with known appids, so
+/// normalization and matching changes can be compared before they are merged.
+/// Match overrides are not applied, only the automatic matching is evaluated.
+pub struct MatchEval {
+    matching: MatchingService,
+}
+
+impl MatchEval {
+    pub fn new(matching: MatchingService) -> Self {
+        Self { matching }
+    }
+
+    pub fn run(&self, corpus: &Path) -> Result<()> {
+        let entries: Vec<CorpusEntry> = serde_json::from_str(&std::fs::read_to_string(corpus)?)?;
+        let titles: Vec<&str> = entries.iter().map(|entry| entry.title.as_str()).collect();
+        let results = self.matching.match_all(&titles);
+
+        let mut report = EvalReport::default();
+        for (entry, result) in entries.iter().zip(&results) {
+            match (entry.appid, result.appid) {
+                (Some(expected), Some(actual)) if expected == actual => report.correct += 1,
+                (None, None) => report.correctly_unmatched += 1,
+                (Some(_), None) => {
+                    report.missed += 1;
+                    println!("Missed {:?}, expected {:?}", entry.title, entry.appid);
+                }
+                (expected, Some(actual)) => {
+                    if expected.is_some() {
+                        report.wrong += 1;
+                    } else {
+                        report.false_matches += 1;
+                    }
+                    println!(
+                        "Matched {:?} to {} by {:?} ({:.2}), expected {:?}",
+                        entry.title, actual, result.method, result.confidence, entry.appid
+                    );
+                }
+            }
+        }
+
+        println!(
+            "{} titles: {} correct, {} wrong, {} missed, {} false matches, {} correctly unmatched",
+            entries.len(),
+            report.correct,
+            report.wrong,
+            report.missed,
+            report.false_matches,
+            report.correctly_unmatched
+        );
+        println!(
+            "Precision {:.3}, recall {:.3}",
+            report.precision(),
+            report.recall()
+        );
+        Ok(())
+    }
+}
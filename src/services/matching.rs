@@ -1,29 +1,67 @@
+use crate::config::MatchOverrides;
+use crate::domain::numbers::{deserialize_rankings, Rank};
+use crate::domain::serde_utils::serialize_sorted;
 use crate::domain::storage::Storage;
+use crate::domain::{Quote, RankingLink};
 use crate::error::{GameError, Result};
 use crate::infrastructure::SteamApp;
 use crate::services::merging::MergedGame;
 use crate::services::text_utils::TitleNormalizer;
 use ahash::AHashMap;
+use fst::automaton::{Automaton, Levenshtein, Str};
+use fst::{IntoStreamer, Streamer};
+use memmap2::Mmap;
+use rayon::iter::Either;
 use rayon::prelude::*;
 use regex::Regex;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use strsim::normalized_levenshtein;
 use tokio::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameWithSteamId {
     pub name: String,
-    pub rankings: HashMap<String, u64>,
+    #[serde(
+        serialize_with = "serialize_sorted",
+        deserialize_with = "deserialize_rankings"
+    )]
+    pub rankings: HashMap<String, Option<Rank>>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub ranking_links: HashMap<String, RankingLink>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<Quote>,
     pub steam_id: Option<String>,
+    /// Every title the sources listed the game under, `name` first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub original_names: Vec<String>,
 }
 
+/// Title FST of `FstIndex`, memory-mapped from the cache
+pub type NameFst = fst::Map<Mmap>;
+
+/// Bump whenever the way apps are normalized or bucketed changes, so cached
+/// indices get rebuilt
+const INDEX_VERSION: u32 = 8;
+
+/// Bucket of titles that don't start with a letter or digit
+const CATCH_ALL_BUCKET: char = '#';
+
+/// Leading words ignored when picking the bucket of a title
+const LEADING_ARTICLES: [&str; 3] = ["the", "a", "an"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexedGames {
+    #[serde(default)]
+    pub version: u32,
     pub created_at: u64,
     pub name_index: HashMap<String, IndexedGame>,
     pub letter_index: HashMap<char, Vec<(IndexedGame, String)>>,
@@ -35,39 +73,156 @@ pub struct IndexedGame {
     pub name: String,
 }
 
+/// A Steam app that might be the match for a scraped title
+#[derive(Debug, Clone)]
+pub struct MatchCandidate {
+    pub appid: u64,
+    pub name: String,
+    pub similarity: f64,
+}
+
+/// How a title was matched to a Steam app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMethod {
+    /// A manual decision from the match overrides
+    Override,
+    /// The normalized titles are equal
+    Exact,
+    /// The most similar title above the similarity threshold
+    Fuzzy,
+    /// The main title and subtitle matched separately
+    Subtitle,
+    /// No app was similar enough
+    Unmatched,
+}
+
+/// Outcome of matching a single title
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchResult {
+    pub title: String,
+    pub appid: Option<u64>,
+    /// Similarity of the matched title, 1.0 for exact matches and overrides
+    pub confidence: f64,
+    pub method: MatchMethod,
+}
+
+/// Where the index of Steam app titles is kept during matching
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IndexBackend {
+    /// All titles held in memory, the fastest to match against
+    #[default]
+    Memory,
+    /// A finite state transducer of the titles, memory-mapped from the cache,
+    /// for machines short on memory
+    Fst,
+}
+
 pub struct MatchingConfig {
+    pub backend: IndexBackend,
     pub similarity_threshold: f64,
-    pub dlc_pattern: String,
+    pub dlc_rules: DlcRules,
     pub filter_dlc: bool,
+    /// Names of editions, which are only filtered when the index also has an
+    /// app of the base title. Some games exist on Steam as an edition only.
+    pub edition_pattern: String,
 }
 
 impl Default for MatchingConfig {
     fn default() -> Self {
         Self {
+            backend: IndexBackend::default(),
             similarity_threshold: 0.9,
-            dlc_pattern: String::from(
-                r"(?i)(DLC|Soundtrack|OST|Bonus|Season Pass|Content Pack|\bVR\b|\bBeta\b|\bDemo\b|\bArt\sof\b|\bUpgrade\b|\bPack\b|\bBundle\b)",
-            ),
+            dlc_rules: DlcRules::default(),
             filter_dlc: true,
+            edition_pattern: String::from(r"(?i)\bEdition\b"),
+        }
+    }
+}
+
+/// Decides which Steam apps are add-ons and stay out of the index. The app
+/// type Steam reported for cached store data wins over the name patterns.
+pub struct DlcRules {
+    /// Names of add-ons, filtered unless Steam says the app is a game
+    pub addon_pattern: String,
+    /// Names that are common for games too, like "Pack" or "Bundle". They are
+    /// only filtered when Steam says the app isn't a game.
+    pub hint_pattern: String,
+}
+
+impl Default for DlcRules {
+    fn default() -> Self {
+        Self {
+            addon_pattern: String::from(
+                r"(?i)(DLC|Soundtrack|\bOST\b|Season Pass|Content Pack|\bDemo\b|\bArt\sof\b|\bUpgrade\b)",
+            ),
+            hint_pattern: String::from(r"(?i)(\bBonus\b|\bVR\b|\bBeta\b|\bPack\b|\bBundle\b)"),
+        }
+    }
+}
+
+/// A Steam app left out of the index, written to `filtered_apps.json` for
+/// review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredApp {
+    pub appid: u64,
+    pub name: String,
+    /// The pattern or app type that filtered the app
+    pub reason: String,
+}
+
+struct CompiledDlcRules {
+    addon: Regex,
+    hint: Regex,
+}
+
+impl CompiledDlcRules {
+    fn new(rules: &DlcRules) -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern)
+                .map_err(|e| GameError::Other(format!("Invalid regex pattern: {}", e)))
+        };
+        Ok(Self {
+            addon: compile(&rules.addon_pattern)?,
+            hint: compile(&rules.hint_pattern)?,
+        })
+    }
+
+    /// Why `app` isn't a game, `None` if it is one
+    fn filter_reason(&self, app: &SteamApp, store: &dyn Storage) -> Option<String> {
+        let matched = self
+            .addon
+            .find(&app.name)
+            .or_else(|| self.hint.find(&app.name))?;
+        // Only apps with a suspicious name are looked up, most aren't cached
+        let app_type = store
+            .load_app_info(app.appid)
+            .ok()
+            .flatten()
+            .and_then(|info| info.app_type);
+        match app_type.as_deref() {
+            Some("game") => None,
+            Some(app_type) => Some(format!("type {}", app_type)),
+            None if self.addon.is_match(&app.name) => {
+                Some(format!("name \"{}\"", matched.as_str()))
+            }
+            None => None,
         }
     }
 }
 
 // Internal structure used during index building
-struct AppIndex {
+pub struct AppIndex {
     name_index: FxHashMap<String, Arc<SteamApp>>,
     letter_index: AHashMap<char, Vec<(Arc<SteamApp>, String)>>,
 }
 
 impl AppIndex {
-    fn build_index(
+    pub fn build_index(
         steam_apps: Vec<SteamApp>,
-        dlc_pattern: &str,
-        should_filter: bool,
+        config: &MatchingConfig,
+        store: &dyn Storage,
     ) -> Result<Self> {
-        let dlc_pattern = Regex::new(dlc_pattern)
-            .map_err(|e| GameError::Other(format!("Invalid regex pattern: {}", e)))?;
-
         let total_start = Instant::now();
         let mut last_checkpoint = total_start;
 
@@ -80,47 +235,24 @@ impl AppIndex {
         };
 
         // Step 1: Parallel filtering and normalization
-        let processed_apps: Vec<_> = steam_apps
-            .into_par_iter()
-            .filter(|app| {
-                if should_filter {
-                    let filtered = dlc_pattern.is_match(&app.name);
-                    !filtered
-                } else {
-                    true
-                }
-            })
-            .map(|app| {
-                let app = Arc::new(app);
-                let normalized = TitleNormalizer::normalize(&app.name);
-                (app, normalized)
-            })
-            .collect();
-
-        info!("After filtering: {} apps", processed_apps.len());
+        let processed_apps = Self::filter_apps(steam_apps, config, store)?;
 
         checkpoint("Filtering and normalization", &mut last_checkpoint);
 
         // Step 2: Create indices with pre-allocated capacity
         let capacity = processed_apps.len();
         let mut name_index = FxHashMap::with_capacity_and_hasher(capacity, Default::default());
-        let mut letter_index: AHashMap<char, Vec<(Arc<SteamApp>, String)>> =
-            AHashMap::with_capacity(27);
-
-        // Pre-initialize letter buckets
-        for c in 'a'..='z' {
-            letter_index.insert(c, Vec::with_capacity(capacity / 26));
-        }
-        letter_index.insert('0', Vec::with_capacity(capacity / 26));
+        let mut letter_index: AHashMap<char, Vec<(Arc<SteamApp>, String)>> = AHashMap::new();
 
         // Build both indices in a single pass
         for (app, normalized) in processed_apps {
             name_index.insert(normalized.clone(), Arc::clone(&app));
 
-            if let Some(first_char) = normalized.chars().next() {
-                if let Some(vec) = letter_index.get_mut(&first_char) {
-                    vec.push((Arc::clone(&app), normalized.clone()));
-                }
+            for key in Self::bucket_keys(&normalized) {
+                letter_index
+                    .entry(key)
+                    .or_default()
+                    .push((Arc::clone(&app), normalized.clone()));
             }
         }
 
@@ -139,8 +271,118 @@ impl AppIndex {
         })
     }
 
+    /// Leaves add-ons and duplicate editions out, written to the filtered
+    /// apps for review, and normalizes the titles of the rest
+    fn filter_apps(
+        steam_apps: Vec<SteamApp>,
+        config: &MatchingConfig,
+        store: &dyn Storage,
+    ) -> Result<Vec<(Arc<SteamApp>, String)>> {
+        let dlc_rules = CompiledDlcRules::new(&config.dlc_rules)?;
+        let (mut processed_apps, mut filtered): (Vec<_>, Vec<_>) =
+            steam_apps.into_par_iter().partition_map(|app| {
+                let reason = config
+                    .filter_dlc
+                    .then(|| dlc_rules.filter_reason(&app, store))
+                    .flatten();
+                match reason {
+                    Some(reason) => Either::Right(FilteredApp {
+                        appid: app.appid,
+                        name: app.name,
+                        reason,
+                    }),
+                    None => {
+                        let app = Arc::new(app);
+                        let normalized = TitleNormalizer::normalize(&app.name);
+                        Either::Left((app, normalized))
+                    }
+                }
+            });
+        if config.filter_dlc {
+            Self::filter_editions(&mut processed_apps, &mut filtered, &config.edition_pattern)?;
+        }
+
+        info!(
+            "After filtering: {} apps, {} filtered",
+            processed_apps.len(),
+            filtered.len()
+        );
+        filtered.sort_by_key(|app| app.appid);
+        store.save_filtered_apps(&filtered)?;
+        Ok(processed_apps)
+    }
+
+    /// Drops editions whose normalized title is shared with an app that isn't
+    /// an edition, so "Sleeping Dogs: Definitive Edition" stays when Steam has
+    /// no "Sleeping Dogs" on its own
+    fn filter_editions(
+        apps: &mut Vec<(Arc<SteamApp>, String)>,
+        filtered: &mut Vec<FilteredApp>,
+        edition_pattern: &str,
+    ) -> Result<()> {
+        let edition = Regex::new(edition_pattern)
+            .map_err(|e| GameError::Other(format!("Invalid regex pattern: {}", e)))?;
+        let base_titles: FxHashMap<&str, u64> = apps
+            .iter()
+            .filter(|(app, _)| !edition.is_match(&app.name))
+            .map(|(app, normalized)| (normalized.as_str(), app.appid))
+            .collect();
+
+        let mut editions = FxHashSet::default();
+        for (app, normalized) in apps.iter() {
+            if !edition.is_match(&app.name) {
+                continue;
+            }
+            if let Some(base_appid) = base_titles.get(normalized.as_str()) {
+                editions.insert(app.appid);
+                filtered.push(FilteredApp {
+                    appid: app.appid,
+                    name: app.name.clone(),
+                    reason: format!("edition of app {}", base_appid),
+                });
+            }
+        }
+
+        apps.retain(|(app, _)| !editions.contains(&app.appid));
+        Ok(())
+    }
+
+    /// Buckets a normalized title belongs to: the initials of its first two
+    /// significant words, skipping leading articles. "the last of us" goes to
+    /// 'l' and 'o', so "Last of Us" finds it. Any letter or digit of any script
+    /// is a bucket of its own, everything else lands in `CATCH_ALL_BUCKET`.
+    fn bucket_keys(normalized: &str) -> Vec<char> {
+        let mut words = normalized.split_whitespace().peekable();
+        while words
+            .peek()
+            .is_some_and(|word| LEADING_ARTICLES.contains(word))
+        {
+            words.next();
+        }
+
+        let mut keys: Vec<char> = words
+            .take(2)
+            .filter_map(|word| word.chars().next())
+            .map(Self::bucket_of)
+            .collect();
+        keys.dedup();
+        if keys.is_empty() {
+            keys.extend(normalized.chars().next().map(Self::bucket_of));
+        }
+        keys
+    }
+
+    fn bucket_of(first_char: char) -> char {
+        if first_char.is_alphanumeric() {
+            first_char
+        } else {
+            CATCH_ALL_BUCKET
+        }
+    }
+
     fn create_indexed_games(&self) -> IndexedGames {
         IndexedGames {
+            version: INDEX_VERSION,
             created_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
@@ -181,42 +423,24 @@ impl AppIndex {
     }
 }
 
-pub struct MatchingService {
-    pub name_index: FxHashMap<String, Arc<SteamApp>>,
-    pub letter_index: AHashMap<char, Vec<(Arc<SteamApp>, String)>>,
-    store: Arc<dyn Storage>,
-    config: MatchingConfig,
+/// Finds Steam apps by normalized title
+trait AppLookup: Send + Sync {
+    /// The app whose normalized title is `normalized`
+    fn exact(&self, normalized: &str) -> Option<Arc<SteamApp>>;
+    /// Apps whose normalized title starts with a string at most `distance`
+    /// edits away from `normalized`, with their normalized titles
+    fn fuzzy_pool(&self, normalized: &str, distance: u32) -> Vec<(Arc<SteamApp>, String)>;
 }
 
-impl MatchingService {
-    pub fn new(
-        steam_apps: Vec<SteamApp>,
-        store: Arc<dyn Storage>,
-        config: MatchingConfig,
-    ) -> Result<Self> {
-        let index_data = match store.load_indexed_games()? {
-            Some(cached) => {
-                info!("Found cached indexed games");
-                cached
-            }
-            None => {
-                info!("Building new index");
-                let app_index =
-                    AppIndex::build_index(steam_apps, &config.dlc_pattern, config.filter_dlc)?;
-                let index_data = app_index.create_indexed_games();
-                store.save_indexed_games(&index_data)?;
-                index_data
-            }
-        };
-
-        Ok(Self::from_indexed_games(index_data, store, config))
-    }
+/// The index held in memory: exact titles in a hash map, and a title FST for
+/// fuzzy lookups
+struct MemoryIndex {
+    name_index: FxHashMap<String, Arc<SteamApp>>,
+    titles: FstIndex<Vec<u8>>,
+}
 
-    fn from_indexed_games(
-        indexed: IndexedGames,
-        store: Arc<dyn Storage>,
-        config: MatchingConfig,
-    ) -> Self {
+impl MemoryIndex {
+    fn from_indexed_games(indexed: IndexedGames) -> Result<Self> {
         let name_index = indexed
             .name_index
             .into_iter()
@@ -231,81 +455,528 @@ impl MatchingService {
             })
             .collect();
 
-        let letter_index = indexed
+        // Titles starting with two significant words are in two buckets
+        let titles = indexed
             .letter_index
+            .into_values()
+            .flatten()
+            .map(|(app, normalized)| (normalized, app.appid, app.name))
+            .collect();
+
+        Ok(Self {
+            name_index,
+            titles: FstIndex::new(FstIndex::build(titles)?)?,
+        })
+    }
+}
+
+impl AppLookup for MemoryIndex {
+    fn exact(&self, normalized: &str) -> Option<Arc<SteamApp>> {
+        self.name_index.get(normalized).cloned()
+    }
+
+    fn fuzzy_pool(&self, normalized: &str, distance: u32) -> Vec<(Arc<SteamApp>, String)> {
+        self.titles.fuzzy_pool(normalized, distance)
+    }
+}
+
+/// Separates the normalized title from the app name in the keys of the FST
+const FST_SEPARATOR: char = '\0';
+
+/// Share of the main title and subtitle words a subtitle match must contain
+const SUBTITLE_MIN_SHARED: f64 = 0.5;
+
+/// Longest edit distance searched for, the automaton grows quickly beyond it
+const MAX_EDIT_DISTANCE: u32 = 2;
+
+/// The index as a finite state transducer from "normalized title, separator,
+/// app name" to the appid. Fuzzy lookups run a Levenshtein automaton over it,
+/// so they consider every title without comparing against each.
+struct FstIndex<D> {
+    map: fst::Map<D>,
+}
+
+impl<D: AsRef<[u8]>> FstIndex<D> {
+    fn new(data: D) -> Result<Self> {
+        let map = fst::Map::new(data)
+            .map_err(|e| GameError::Other(format!("Invalid title FST: {}", e)))?;
+        Ok(Self { map })
+    }
+
+    /// Entries whose key is accepted by `automaton`
+    fn entries(&self, automaton: impl fst::Automaton) -> Vec<(Arc<SteamApp>, String)> {
+        let mut stream = self.map.search(automaton).into_stream();
+        let mut entries = Vec::new();
+        while let Some((key, appid)) = stream.next() {
+            let key = String::from_utf8_lossy(key);
+            let Some((normalized, name)) = key.split_once(FST_SEPARATOR) else {
+                continue;
+            };
+            let app = Arc::new(SteamApp {
+                appid,
+                name: name.to_string(),
+            });
+            entries.push((app, normalized.to_string()));
+        }
+        entries
+    }
+}
+
+impl FstIndex<Vec<u8>> {
+    /// Builds the FST bytes of `(normalized title, appid, app name)` entries
+    fn build(titles: Vec<(String, u64, String)>) -> Result<Vec<u8>> {
+        let mut keys: Vec<(String, u64)> = titles
             .into_iter()
-            .map(|(k, v)| {
-                let entries = v
-                    .into_iter()
-                    .map(|(app, s)| {
-                        (
-                            Arc::new(SteamApp {
-                                appid: app.appid,
-                                name: app.name,
-                            }),
-                            s,
-                        )
-                    })
-                    .collect();
-                (k, entries)
+            .map(|(normalized, appid, name)| {
+                (format!("{}{}{}", normalized, FST_SEPARATOR, name), appid)
             })
             .collect();
+        keys.par_sort_unstable();
+        keys.dedup_by(|a, b| a.0 == b.0);
 
-        Self {
-            name_index,
-            letter_index,
-            store,
-            config,
+        let map = fst::Map::from_iter(keys)
+            .map_err(|e| GameError::Other(format!("Building the title FST failed: {}", e)))?;
+        Ok(map.into_fst().into_inner())
+    }
+}
+
+impl FstIndex<Mmap> {
+    /// Builds the FST from `steam_apps` and caches it for this index version
+    fn build_and_save(
+        steam_apps: Vec<SteamApp>,
+        config: &MatchingConfig,
+        store: &dyn Storage,
+    ) -> Result<Self> {
+        info!("Building new title FST");
+        let titles = AppIndex::filter_apps(steam_apps, config, store)?
+            .into_iter()
+            .map(|(app, normalized)| (normalized, app.appid, app.name.clone()))
+            .collect();
+        store.save_name_fst(INDEX_VERSION, &FstIndex::build(titles)?)?;
+        let map = store
+            .load_name_fst(INDEX_VERSION)?
+            .ok_or_else(|| GameError::Other("The saved title FST is missing".to_string()))?;
+        Ok(Self { map })
+    }
+
+    fn from_cache(store: &dyn Storage) -> Result<Option<Self>> {
+        Ok(store.load_name_fst(INDEX_VERSION)?.map(|map| Self { map }))
+    }
+}
+
+impl<D: AsRef<[u8]> + Send + Sync> AppLookup for FstIndex<D> {
+    fn exact(&self, normalized: &str) -> Option<Arc<SteamApp>> {
+        let prefix = format!("{}{}", normalized, FST_SEPARATOR);
+        self.entries(Str::new(&prefix).starts_with())
+            .into_iter()
+            .next()
+            .map(|(app, _)| app)
+    }
+
+    fn fuzzy_pool(&self, normalized: &str, distance: u32) -> Vec<(Arc<SteamApp>, String)> {
+        let distance = distance.min(MAX_EDIT_DISTANCE);
+        match Levenshtein::new(normalized, distance) {
+            Ok(automaton) => self.entries(automaton.starts_with()),
+            Err(e) => {
+                warn!("No fuzzy lookup for {:?}: {}", normalized, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+pub struct MatchingService {
+    index: Box<dyn AppLookup>,
+    config: MatchingConfig,
+    overrides: MatchOverrides,
+}
+
+impl MatchingService {
+    /// Builds the index from `steam_apps` and caches it, replacing any cached
+    /// one. `from_cache` reuses it.
+    pub fn new(
+        steam_apps: Vec<SteamApp>,
+        store: Arc<dyn Storage>,
+        config: MatchingConfig,
+    ) -> Result<Self> {
+        if config.backend == IndexBackend::Fst {
+            let index = FstIndex::build_and_save(steam_apps, &config, store.as_ref())?;
+            return Ok(Self::with_index(Box::new(index), config));
         }
+
+        info!("Building new index");
+        let app_index = AppIndex::build_index(steam_apps, &config, store.as_ref())?;
+        let index_data = app_index.create_indexed_games();
+        store.save_indexed_games(&index_data)?;
+
+        let index = MemoryIndex::from_indexed_games(index_data)?;
+        Ok(Self::with_index(Box::new(index), config))
     }
 
-    pub async fn match_games(&self, merged_games: Vec<MergedGame>) -> Result<Vec<GameWithSteamId>> {
-        if let Some(cached) = self.store.load_matched_games()? {
-            info!("Using cached matched games");
-            return Ok(cached);
+    /// Creates the service from the cached index only, `None` if there is none
+    pub fn from_cache(store: Arc<dyn Storage>, config: MatchingConfig) -> Result<Option<Self>> {
+        let index: Option<Box<dyn AppLookup>> = match config.backend {
+            IndexBackend::Fst => FstIndex::from_cache(store.as_ref())?
+                .map(|index| Box::new(index) as Box<dyn AppLookup>),
+            IndexBackend::Memory => {
+                match store.load_indexed_games()?.filter(Self::is_current_index) {
+                    Some(index_data) => {
+                        Some(Box::new(MemoryIndex::from_indexed_games(index_data)?))
+                    }
+                    None => None,
+                }
+            }
+        };
+        Ok(index.map(|index| Self::with_index(index, config)))
+    }
+
+    fn is_current_index(index: &IndexedGames) -> bool {
+        if index.version != INDEX_VERSION {
+            info!(
+                "Ignoring cached index of version {} (expected {})",
+                index.version, INDEX_VERSION
+            );
         }
+        index.version == INDEX_VERSION
+    }
 
-        info!("Matching games with Steam IDs in parallel");
-        let matched_games: Vec<GameWithSteamId> = merged_games
-            .into_par_iter()
-            .map(|game| {
-                let steam_id = self.find_steam_id(&game.original_names[0]);
+    pub fn with_overrides(mut self, overrides: MatchOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
 
-                if steam_id.is_none() {
-                    info!("No Steam ID found for: {}", game.original_names[0]);
+    fn with_index(index: Box<dyn AppLookup>, config: MatchingConfig) -> Self {
+        Self {
+            index,
+            config,
+            overrides: MatchOverrides::default(),
+        }
+    }
+
+    /// Finds the Steam IDs of merged games, preferring manual decisions
+    pub fn match_games(&self, games: Vec<MergedGame>) -> Vec<GameWithSteamId> {
+        let titles: Vec<&str> = games
+            .iter()
+            .map(|game| game.original_names[0].as_str())
+            .collect();
+        let results = self.match_all(&titles);
+
+        games
+            .into_iter()
+            .zip(results)
+            .map(|(game, result)| {
+                if result.appid.is_none() {
+                    info!("No Steam ID found for: {}", result.title);
                 }
                 GameWithSteamId {
-                    name: game.original_names[0].clone(),
+                    name: result.title,
                     rankings: game.rankings,
-                    steam_id,
+                    ranking_links: game.ranking_links,
+                    quotes: game.quotes,
+                    steam_id: result.appid.map(|appid| appid.to_string()),
+                    original_names: game.original_names,
                 }
             })
+            .collect()
+    }
+
+    /// Matches many titles at once. Each distinct title is normalized and
+    /// matched only once, results are in the order of `titles`.
+    pub fn match_all(&self, titles: &[&str]) -> Vec<MatchResult> {
+        let distinct: FxHashSet<&str> = titles.iter().copied().collect();
+        let results: FxHashMap<&str, MatchResult> = distinct
+            .into_par_iter()
+            .map(|title| {
+                (
+                    title,
+                    self.match_title(title, &TitleNormalizer::normalize(title)),
+                )
+            })
             .collect();
 
-        Ok(matched_games)
+        titles.iter().map(|title| results[title].clone()).collect()
     }
 
-    pub fn find_steam_id(&self, game_name: &str) -> Option<String> {
-        info!("Finding Steam ID for: {}", game_name);
-        let normalized_search = TitleNormalizer::normalize(game_name);
+    /// Matches `title`, normalized as `normalized`, preferring a manual decision
+    fn match_title(&self, title: &str, normalized: &str) -> MatchResult {
+        match self.overrides.get(title) {
+            Some(decision) => MatchResult {
+                title: title.to_string(),
+                appid: decision,
+                confidence: 1.0,
+                method: MatchMethod::Override,
+            },
+            None => self.match_normalized(title, normalized),
+        }
+    }
 
+    /// Matches `normalized` against the index, exactly and then fuzzily
+    fn match_normalized(&self, title: &str, normalized: &str) -> MatchResult {
         // Try exact match first
-        if let Some(app) = self.name_index.get(&normalized_search) {
-            return Some(app.appid.to_string());
+        if let Some(app) = self.index.exact(normalized) {
+            return MatchResult {
+                title: title.to_string(),
+                appid: Some(app.appid),
+                confidence: 1.0,
+                method: MatchMethod::Exact,
+            };
         }
 
         // Fuzzy matching if exact match fails
-        let first_char = normalized_search.chars().next()?;
-        let candidates = self.letter_index.get(&first_char)?;
-
-        candidates
+        let distance = Self::edit_budget(normalized, self.config.similarity_threshold);
+        let best = self
+            .index
+            .fuzzy_pool(normalized, distance)
             .par_iter()
             .map(|(app, normalized_name)| {
-                let similarity = normalized_levenshtein(&normalized_search, normalized_name);
-                (app, similarity)
+                let similarity = normalized_levenshtein(normalized, normalized_name);
+                (app.appid, similarity)
             })
             .filter(|(_, similarity)| *similarity > self.config.similarity_threshold)
-            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(app, _)| app.appid.to_string())
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        if let Some((appid, similarity)) = best {
+            return MatchResult {
+                title: title.to_string(),
+                appid: Some(appid),
+                confidence: similarity,
+                method: MatchMethod::Fuzzy,
+            };
+        }
+
+        match self.match_subtitle(title, normalized) {
+            Some((appid, similarity)) => MatchResult {
+                title: title.to_string(),
+                appid: Some(appid),
+                confidence: similarity,
+                method: MatchMethod::Subtitle,
+            },
+            None => MatchResult {
+                title: title.to_string(),
+                appid: None,
+                confidence: 0.0,
+                method: MatchMethod::Unmatched,
+            },
+        }
+    }
+
+    /// Matches the main title and the subtitle of `title` separately, for
+    /// titles whose subtitle is worded differently on Steam. Prefers apps
+    /// sharing most subtitle words, and falls back to the main title alone.
+    /// Returns the appid and its similarity to `normalized`.
+    fn match_subtitle(&self, title: &str, normalized: &str) -> Option<(u64, f64)> {
+        let (main, subtitle) = TitleNormalizer::split_subtitle(title);
+        let main = TitleNormalizer::normalize(main);
+        let subtitle = TitleNormalizer::normalize(subtitle?);
+        if main.is_empty() || subtitle.is_empty() {
+            return None;
+        }
+
+        let main_words: FxHashSet<&str> = main.split_whitespace().collect();
+        let subtitle_words: FxHashSet<&str> = subtitle.split_whitespace().collect();
+        let share = |words: &FxHashSet<&str>, candidate: &FxHashSet<&str>| {
+            words.intersection(candidate).count() as f64 / words.len() as f64
+        };
+
+        let threshold = self.config.similarity_threshold;
+        let mut pool = self
+            .index
+            .fuzzy_pool(&main, Self::edit_budget(&main, threshold));
+        pool.extend(
+            self.index
+                .fuzzy_pool(&subtitle, Self::edit_budget(&subtitle, threshold)),
+        );
+
+        pool.par_iter()
+            .filter_map(|(app, candidate)| {
+                let candidate_words: FxHashSet<&str> = candidate.split_whitespace().collect();
+                if share(&main_words, &candidate_words) < SUBTITLE_MIN_SHARED {
+                    return None;
+                }
+                let shared = share(&subtitle_words, &candidate_words);
+                // Without a shared subtitle word only the main title itself counts
+                if shared < SUBTITLE_MIN_SHARED && *candidate != main {
+                    return None;
+                }
+                let similarity = normalized_levenshtein(normalized, candidate);
+                Some((app.appid, shared, similarity))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(a.2.total_cmp(&b.2)))
+            .map(|(appid, _, similarity)| (appid, similarity))
+    }
+
+    /// Edits a title of `normalized` may differ by and still be more similar
+    /// than `threshold`
+    fn edit_budget(normalized: &str, threshold: f64) -> u32 {
+        let budget = ((1.0 - threshold) * normalized.chars().count() as f64).floor() as u32;
+        budget.min(MAX_EDIT_DISTANCE)
+    }
+
+    /// Returns the `limit` most similar Steam apps for `game_name`, best first
+    pub fn candidates(&self, game_name: &str, limit: usize) -> Vec<MatchCandidate> {
+        let normalized_search = TitleNormalizer::normalize(game_name);
+
+        if let Some(app) = self.index.exact(&normalized_search) {
+            return vec![MatchCandidate {
+                appid: app.appid,
+                name: app.name.clone(),
+                similarity: 1.0,
+            }];
+        }
+
+        // Candidates may differ by up to a quarter of the title
+        let distance = Self::edit_budget(&normalized_search, 0.75);
+        let mut candidates: Vec<MatchCandidate> = self
+            .index
+            .fuzzy_pool(&normalized_search, distance)
+            .par_iter()
+            .map(|(app, normalized_name)| MatchCandidate {
+                appid: app.appid,
+                name: app.name.clone(),
+                similarity: normalized_levenshtein(&normalized_search, normalized_name),
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        candidates.truncate(limit);
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_titles_by_their_first_two_significant_words() {
+        let cases: [(&str, &[char]); 11] = [
+            ("last of us", &['l', 'o']),
+            ("the last of us", &['l', 'o']),
+            ("a way out", &['w', 'o']),
+            ("an", &['a']),
+            ("the", &['t']),
+            ("doom", &['d']),
+            ("dead cells", &['d', 'c']),
+            ("x", &['x']),
+            ("1 2 switch", &['1', '2']),
+            ("", &[]),
+            ("the 7th guest", &['7', 'g']),
+        ];
+        for (normalized, keys) in cases {
+            assert_eq!(AppIndex::bucket_keys(normalized), keys, "{:?}", normalized);
+        }
+    }
+
+    #[test]
+    fn buckets_non_ascii_titles_by_their_first_letter() {
+        let cases: [(&str, &[char]); 4] = [
+            ("東方 project", &['東', 'p']),
+            ("ōkami", &['ō']),
+            ("λ zero", &['λ', 'z']),
+            ("★ stars", &[CATCH_ALL_BUCKET, 's']),
+        ];
+        for (normalized, keys) in cases {
+            assert_eq!(AppIndex::bucket_keys(normalized), keys, "{:?}", normalized);
+        }
+    }
+
+    #[test]
+    fn buckets_anything_but_letters_and_digits_together() {
+        for c in ['#', '-', '_', '!', '★', ' '] {
+            assert_eq!(AppIndex::bucket_of(c), CATCH_ALL_BUCKET, "{:?}", c);
+        }
+        for c in ['a', 'Z', '0', '9', 'é', 'ж', '東'] {
+            assert_eq!(AppIndex::bucket_of(c), c, "{:?}", c);
+        }
+    }
+
+    /// A handful of apps, every one of them offered to fuzzy lookups
+    struct StaticIndex(Vec<(Arc<SteamApp>, String)>);
+
+    impl StaticIndex {
+        fn new(apps: &[(u64, &str)]) -> Self {
+            Self(
+                apps.iter()
+                    .map(|&(appid, name)| {
+                        let app = SteamApp {
+                            appid,
+                            name: name.to_string(),
+                        };
+                        (Arc::new(app), TitleNormalizer::normalize(name))
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    impl AppLookup for StaticIndex {
+        fn exact(&self, normalized: &str) -> Option<Arc<SteamApp>> {
+            self.0
+                .iter()
+                .find(|(_, title)| title == normalized)
+                .map(|(app, _)| Arc::clone(app))
+        }
+
+        fn fuzzy_pool(&self, _normalized: &str, _distance: u32) -> Vec<(Arc<SteamApp>, String)> {
+            self.0.clone()
+        }
+    }
+
+    fn service() -> MatchingService {
+        let index = StaticIndex::new(&[
+            (292030, "The Witcher® 3: Wild Hunt"),
+            (367520, "Hollow Knight"),
+            (1172380, "STAR WARS Jedi: Fallen Order"),
+            (1145360, "Hades"),
+            (1145350, "Hades II"),
+        ]);
+        MatchingService::with_index(Box::new(index), MatchingConfig::default())
+    }
+
+    #[test]
+    fn matches_exact_fuzzy_and_subtitle_titles() {
+        let cases = [
+            ("The Witcher 3: Wild Hunt", Some(292030), MatchMethod::Exact),
+            ("Hades 2", Some(1145350), MatchMethod::Exact),
+            ("Hollow Knigt", Some(367520), MatchMethod::Fuzzy),
+            (
+                "Star Wars Jedi: Fallen Order Redux",
+                Some(1172380),
+                MatchMethod::Subtitle,
+            ),
+            ("Untitled Goose Game", None, MatchMethod::Unmatched),
+        ];
+        let service = service();
+        for (title, appid, method) in cases {
+            let result = service.match_all(&[title]).remove(0);
+            assert_eq!((result.appid, result.method), (appid, method), "{}", title);
+        }
+    }
+
+    #[test]
+    fn prefers_overrides_and_keeps_the_order_of_titles() {
+        let overrides = MatchOverrides(
+            [
+                ("Hades".to_string(), Some(1145350)),
+                ("Hollow Knight".to_string(), None),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let service = service().with_overrides(overrides);
+        let results = service.match_all(&["Hades", "Hollow Knight", "Hades II", "Hades"]);
+        let matched: Vec<_> = results
+            .iter()
+            .map(|result| (result.title.as_str(), result.appid, result.method))
+            .collect();
+        assert_eq!(
+            matched,
+            [
+                ("Hades", Some(1145350), MatchMethod::Override),
+                ("Hollow Knight", None, MatchMethod::Override),
+                ("Hades II", Some(1145350), MatchMethod::Exact),
+                ("Hades", Some(1145350), MatchMethod::Override),
+            ]
+        );
     }
 }
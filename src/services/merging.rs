@@ -1,19 +1,71 @@
+use crate::domain::numbers::{deserialize_rankings, Rank};
+use crate::domain::serde_utils::serialize_sorted;
 use crate::domain::storage::Storage;
+use crate::domain::{Quote, RankingLink};
 use crate::error::Result;
 use crate::services::scraping::WebsiteGames;
 use crate::services::text_utils::TitleNormalizer;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use tracing::info;
+use strsim::normalized_levenshtein;
+use tracing::{info, warn};
 use crate::config::ScraperConfig;
 
+/// Groups whose original names are less similar than this are flagged as
+/// suspicious in the merge report
+const SUSPICIOUS_SIMILARITY: f64 = 0.5;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MergedGame {
     pub normalized_name: String,
     pub original_names: Vec<String>,
-    pub rankings: HashMap<String, u64>,
+    #[serde(
+        serialize_with = "serialize_sorted",
+        deserialize_with = "deserialize_rankings"
+    )]
+    pub rankings: HashMap<String, Option<Rank>>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub ranking_links: HashMap<String, RankingLink>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quotes: Vec<Quote>,
+    /// Editions the game was listed as, e.g. "Royal" for "Persona 5 Royal"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub editions: Vec<String>,
+}
+
+/// Audit trail of a merge run, showing which scraped titles ended up in which
+/// merged game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub groups: Vec<MergeReportGroup>,
+    /// Groups that share a title key but whose original names differ a lot,
+    /// hinting at an over-merge
+    pub suspicious: Vec<MergeReportGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReportGroup {
+    /// Normalized title without numbers, which games are grouped by
+    pub title_key: String,
+    /// Numbers in the title, which separate sequels within a group
+    pub numeric_key: String,
+    pub normalized_name: String,
+    pub entries: Vec<MergeReportEntry>,
+    /// Lowest similarity between any two original names of the group
+    pub min_similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReportEntry {
+    pub original_name: String,
+    pub source: String,
+    pub rank: Option<Rank>,
 }
 
 struct GameData {
@@ -21,8 +73,11 @@ struct GameData {
     normalized_title: String,
     numeric_tokens: Vec<String>,
     non_numeric_title: String,
-    rank: u64,
+    rank: Option<Rank>,
     source: String,
+    link: RankingLink,
+    blurb: Option<String>,
+    edition: Option<String>,
 }
 
 pub struct MergingService {
@@ -43,7 +98,15 @@ impl MergingService {
         }
 
         let games_data = self.prepare_game_data(&website_games);
-        let merged_games = self.perform_merge(games_data);
+        let (merged_games, report) = self.perform_merge(games_data);
+
+        if !report.suspicious.is_empty() {
+            warn!(
+                "{} suspicious merges, see the merge report",
+                report.suspicious.len()
+            );
+        }
+        self.store.save_merge_report(&report)?;
 
         // Cache the results
         self.store.save_merged_games(&merged_games)?;
@@ -57,10 +120,12 @@ impl MergingService {
 
         for website in website_games {
             let source = TitleNormalizer::normalize_source(&website.source, &self.scraper_config);
+            let unranked = self.is_unranked(&website.source);
             info!("Processing games from {}", source);
 
             for game in &website.games {
-                let normalized_title = TitleNormalizer::normalize(&game.name);
+                let (title, edition) = self.split_edition(&game.name);
+                let normalized_title = TitleNormalizer::normalize(&title);
 
                 let numeric_tokens = numbers_re
                     .find_iter(&normalized_title)
@@ -79,8 +144,14 @@ impl MergingService {
                     normalized_title: normalized_title.clone(),
                     numeric_tokens,
                     non_numeric_title,
-                    rank: game.rank,
+                    rank: (!unranked).then_some(game.rank),
                     source: source.clone(),
+                    link: RankingLink {
+                        url: website.source.clone(),
+                        anchor: game.anchor.clone(),
+                    },
+                    blurb: game.blurb.clone(),
+                    edition,
                 });
             }
         }
@@ -88,9 +159,45 @@ impl MergingService {
         games_data
     }
 
-    fn perform_merge(&self, games_data: Vec<GameData>) -> Vec<MergedGame> {
-        // Group by non-numeric title
-        let mut title_groups: HashMap<String, Vec<GameData>> = HashMap::new();
+    /// Splits the edition off a title when edition-aware merging is enabled.
+    /// Editions of titles kept separate stay part of the title, so they never
+    /// merge with the base game.
+    fn split_edition(&self, name: &str) -> (String, Option<String>) {
+        let editions = &self.scraper_config.editions;
+        if !editions.enabled {
+            return (name.to_string(), None);
+        }
+
+        let (base, edition) = TitleNormalizer::split_edition(name, &editions.suffixes);
+        let Some(edition) = edition else {
+            return (name.to_string(), None);
+        };
+
+        let normalized_base = TitleNormalizer::normalize(base);
+        let keep_separate = editions
+            .keep_separate
+            .iter()
+            .any(|title| TitleNormalizer::normalize(title) == normalized_base);
+
+        if keep_separate {
+            // Normalizing drops words like "remake", so mark the edition as a
+            // single token that survives it
+            let marker = format!("edition_{}", edition.replace(' ', "_"));
+            (format!("{} {}", base, marker), Some(edition))
+        } else {
+            (base.to_string(), Some(edition))
+        }
+    }
+
+    fn is_unranked(&self, source: &str) -> bool {
+        self.scraper_config
+            .website_for(source)
+            .is_some_and(|w| w.unranked)
+    }
+
+    fn perform_merge(&self, games_data: Vec<GameData>) -> (Vec<MergedGame>, MergeReport) {
+        // Group by non-numeric title, in key order so the output is stable
+        let mut title_groups: BTreeMap<String, Vec<GameData>> = BTreeMap::new();
         for game in games_data {
             title_groups
                 .entry(game.non_numeric_title.clone())
@@ -99,9 +206,11 @@ impl MergingService {
         }
 
         let mut merged_games = Vec::new();
+        let mut report = MergeReport::default();
 
-        for group in title_groups.values() {
-            let mut merged_group: HashMap<String, MergedGame> = HashMap::new();
+        for (title_key, group) in &title_groups {
+            let mut merged_group: BTreeMap<String, MergedGame> = BTreeMap::new();
+            let mut report_entries: HashMap<String, Vec<MergeReportEntry>> = HashMap::new();
 
             for game in group {
                 let key = game.numeric_tokens.join("_");
@@ -111,12 +220,46 @@ impl MergingService {
                 } else {
                     self.create_new_merged_game(&mut merged_group, game, &key);
                 }
+
+                report_entries
+                    .entry(key)
+                    .or_default()
+                    .push(MergeReportEntry {
+                        original_name: game.original_name.clone(),
+                        source: game.source.clone(),
+                        rank: game.rank,
+                    });
+            }
+
+            for (numeric_key, merged_game) in &merged_group {
+                let group = MergeReportGroup {
+                    title_key: title_key.clone(),
+                    numeric_key: numeric_key.clone(),
+                    normalized_name: merged_game.normalized_name.clone(),
+                    entries: report_entries.remove(numeric_key).unwrap_or_default(),
+                    min_similarity: Self::min_similarity(&merged_game.original_names),
+                };
+                if group.min_similarity < SUSPICIOUS_SIMILARITY {
+                    report.suspicious.push(group.clone());
+                }
+                report.groups.push(group);
             }
 
             merged_games.extend(merged_group.into_values());
         }
 
-        merged_games
+        (merged_games, report)
+    }
+
+    fn min_similarity(names: &[String]) -> f64 {
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        let mut min = 1.0_f64;
+        for (i, a) in names.iter().enumerate() {
+            for b in &names[i + 1..] {
+                min = min.min(normalized_levenshtein(a, b));
+            }
+        }
+        min
     }
 
     fn update_existing_game(&self, existing_game: &mut MergedGame, game: &GameData) {
@@ -128,16 +271,34 @@ impl MergingService {
         existing_game
             .rankings
             .insert(game.source.clone(), game.rank);
+        existing_game
+            .ranking_links
+            .insert(game.source.clone(), game.link.clone());
+        if let Some(text) = &game.blurb {
+            if !existing_game.quotes.iter().any(|q| q.source == game.source) {
+                existing_game.quotes.push(Quote {
+                    source: game.source.clone(),
+                    text: text.clone(),
+                });
+            }
+        }
+        if let Some(edition) = &game.edition {
+            if !existing_game.editions.contains(edition) {
+                existing_game.editions.push(edition.clone());
+            }
+        }
     }
 
     fn create_new_merged_game(
         &self,
-        merged_group: &mut HashMap<String, MergedGame>,
+        merged_group: &mut BTreeMap<String, MergedGame>,
         game: &GameData,
         key: &str,
     ) {
         let mut rankings = HashMap::new();
         rankings.insert(game.source.clone(), game.rank);
+        let mut ranking_links = HashMap::new();
+        ranking_links.insert(game.source.clone(), game.link.clone());
 
         merged_group.insert(
             key.to_string(),
@@ -145,6 +306,16 @@ impl MergingService {
                 normalized_name: game.normalized_title.clone(),
                 original_names: vec![game.original_name.clone()],
                 rankings,
+                ranking_links,
+                quotes: game
+                    .blurb
+                    .iter()
+                    .map(|text| Quote {
+                        source: game.source.clone(),
+                        text: text.clone(),
+                    })
+                    .collect(),
+                editions: game.edition.iter().cloned().collect(),
             },
         );
     }
@@ -1,8 +1,18 @@
+pub mod cache;
+pub mod cards;
+pub mod dataset;
+pub mod digest;
 pub mod enrichment;
-pub(crate) mod game_service;
-pub(crate) mod matching;
-pub(crate) mod merging;
-pub(crate) mod publish;
-pub(crate) mod scoring;
-pub(crate) mod scraping;
-pub(crate) mod text_utils;
+pub mod game_service;
+pub mod identity;
+pub mod interactive_match;
+pub mod match_eval;
+pub mod matching;
+pub mod merging;
+pub mod progress;
+pub mod publish;
+pub mod runs;
+pub mod sanitize;
+pub mod scoring;
+pub mod scraping;
+pub mod text_utils;
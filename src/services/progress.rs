@@ -0,0 +1,30 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::Lazy;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Stages run concurrently, their bars are drawn below each other
+static BARS: Lazy<MultiProgress> = Lazy::new(MultiProgress::new);
+
+/// Turns progress bars off with `--quiet`, and when stderr isn't a terminal,
+/// so cron jobs and CI logs only get the log lines
+pub fn init(quiet: bool) {
+    ENABLED.store(!quiet && std::io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+/// A bar over `len` steps of a pipeline stage, hidden when bars are off
+pub fn bar(stage: &str, len: u64) -> ProgressBar {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(len);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {prefix:>10} {bar:40.cyan/blue} {pos}/{len} {msg}")
+            .unwrap(),
+    );
+    bar.set_prefix(stage.to_string());
+    BARS.add(bar)
+}
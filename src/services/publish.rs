@@ -1,16 +1,47 @@
-use crate::domain::Manifest;
-use crate::error::{GameError, Result};
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::domain::{Game, Manifest};
+use crate::error::Result;
+use crate::services::cards::render_card;
+use crate::services::identity::fnv1a;
+use crate::services::progress;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
 use tracing::info;
 
+/// Remembers what was published, so unchanged assets are skipped next time
+const PUBLISH_STATE_FILE: &str = "publish_state.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PublishState {
+    manifest_hash: Option<String>,
+    /// Published images by file name
+    assets: BTreeMap<String, AssetState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AssetState {
+    source_url: String,
+    hash: String,
+}
+
+/// Changes of a publish compared to the previous one
+#[derive(Debug, Default)]
+struct PublishDelta {
+    added: usize,
+    updated: usize,
+    unchanged: usize,
+    removed: usize,
+    cards_changed: usize,
+}
+
 pub struct PublishService {
     client: Client,
     username: String,
     repo: String,
+    screenshots: Option<usize>,
+    cards: bool,
 }
 
 impl PublishService {
@@ -25,73 +56,229 @@ impl PublishService {
             client,
             username,
             repo,
+            screenshots: None,
+            cards: false,
         }
     }
 
+    /// Also mirrors up to `count` screenshots of every game, dropping the
+    /// others. Unset, screenshots keep their original URLs.
+    pub fn with_screenshots(mut self, count: Option<usize>) -> Self {
+        self.screenshots = count;
+        self
+    }
+
+    /// Also renders a social preview card of every game to `public/cards`
+    pub fn with_cards(mut self, cards: bool) -> Self {
+        self.cards = cards;
+        self
+    }
+
     pub async fn prepare(&self, manifest_path: &Path) -> Result<()> {
         // Create prepare directory
         let prepare_dir = Path::new("public");
         let images_dir = prepare_dir.join("images");
         tokio::fs::create_dir_all(&prepare_dir).await?;
         tokio::fs::create_dir_all(&images_dir).await?;
+        let cards_dir = prepare_dir.join("cards");
+        if self.cards {
+            tokio::fs::create_dir_all(&cards_dir).await?;
+        }
 
         // Read manifest
         info!("Reading manifest from {:?}", manifest_path);
         let manifest_content = tokio::fs::read_to_string(manifest_path).await?;
         let mut manifest: Manifest = serde_json::from_str(&manifest_content)?;
 
-        let pb = ProgressBar::new(manifest.games.len() as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-                .map_err(|e| GameError::Other(e.to_string()))?,
-        );
+        let state_path = prepare_dir.join(PUBLISH_STATE_FILE);
+        let previous: PublishState = match tokio::fs::read_to_string(&state_path).await {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(_) => PublishState::default(),
+        };
+        let mut state = PublishState::default();
+        let mut delta = PublishDelta::default();
+
+        let pb = progress::bar("publish", manifest.games.len() as u64);
 
         // Process each game
         for game in &mut manifest.games {
             if let Some(ref url) = game.header_image {
                 let filename = self.sanitize_filename(&game.title);
-                let image_path = images_dir.join(format!("{}.jpg", filename));
+                let name = format!("{}.jpg", filename);
 
                 pb.set_message(format!("Processing {}", game.title));
 
-                // Download image if it doesn't exist
-                if !image_path.exists() {
-                    if let Err(e) =  self.download_image(url, &image_path).await {
-                        info!("Failed to download image for {}: {}", game.title, e);
-                        continue;
+                // The game keeps the source URL, but still gets its
+                // screenshots and card
+                match self
+                    .publish_image(url, &images_dir, &name, &previous, &mut state, &mut delta)
+                    .await
+                {
+                    Ok(()) => {
+                        game.header_image = Some(format!(
+                            "https://{}.github.io/{}/images/{}.jpg",
+                            self.username, self.repo, filename
+                        ));
                     }
+                    Err(e) => info!("Failed to download image for {}: {}", game.title, e),
                 }
+            }
 
-                game.header_image = Some(format!(
-                    "https://{}.github.io/{}/images/{}.jpg",
-                    self.username, self.repo, filename
-                ));
+            if let Some(count) = self.screenshots {
+                game.screenshots.truncate(count);
+                let filename = self.sanitize_filename(&game.title);
+                for (i, url) in game.screenshots.iter_mut().enumerate() {
+                    let name = format!("{}_screenshot_{}.jpg", filename, i + 1);
+                    if let Err(e) = self
+                        .publish_image(url, &images_dir, &name, &previous, &mut state, &mut delta)
+                        .await
+                    {
+                        info!("Failed to download screenshot for {}: {}", game.title, e);
+                        continue;
+                    }
+                    *url = format!(
+                        "https://{}.github.io/{}/images/{}",
+                        self.username, self.repo, name
+                    );
+                }
+            }
+            if self.cards {
+                let filename = self.sanitize_filename(&game.title);
+                let cover = tokio::fs::read(images_dir.join(format!("{}.jpg", filename)))
+                    .await
+                    .ok();
+                match self
+                    .publish_card(game, cover.as_deref(), &cards_dir, &filename)
+                    .await
+                {
+                    Ok(changed) => delta.cards_changed += changed as usize,
+                    Err(e) => info!("Failed to render card for {}: {}", game.title, e),
+                }
             }
             pb.inc(1);
         }
 
         pb.finish_with_message("Done processing images!");
 
+        // Images of games that dropped out of the manifest
+        for name in previous.assets.keys() {
+            if !state.assets.contains_key(name) {
+                let _ = tokio::fs::remove_file(images_dir.join(name)).await;
+                delta.removed += 1;
+            }
+        }
+
         // Save updated manifest
         let new_manifest_path = prepare_dir.join("manifest.json");
         let manifest_content = serde_json::to_string_pretty(&manifest)?;
-        tokio::fs::write(new_manifest_path, manifest_content).await?;
-        info!("Saved prepared manifest");
+        let manifest_hash = format!("{:016x}", fnv1a(manifest_content.as_bytes()));
+        let manifest_changed =
+            previous.manifest_hash.as_ref() != Some(&manifest_hash) || !new_manifest_path.exists();
+        if manifest_changed {
+            tokio::fs::write(new_manifest_path, manifest_content).await?;
+            info!("Saved prepared manifest");
+        } else {
+            info!("Prepared manifest is unchanged");
+        }
+        state.manifest_hash = Some(manifest_hash);
+        tokio::fs::write(&state_path, serde_json::to_string_pretty(&state)?).await?;
 
+        info!(
+            "Images: {} added, {} updated, {} unchanged, {} removed",
+            delta.added, delta.updated, delta.unchanged, delta.removed
+        );
+        if self.cards {
+            info!("Cards: {} rendered anew", delta.cards_changed);
+        }
         Ok(())
     }
 
-    async fn download_image(&self, url: &str, path: &Path) -> Result<()> {
-        let response = self.client.get(url).send().await?;
-        let bytes = response.bytes().await?;
-
-        let mut file = tokio::fs::File::create(path).await?;
-        file.write_all(&bytes).await?;
+    /// Downloads the image at `url` to `images_dir/name`, unless the previous
+    /// publish already got it from the same URL
+    async fn publish_image(
+        &self,
+        url: &str,
+        images_dir: &Path,
+        name: &str,
+        previous: &PublishState,
+        state: &mut PublishState,
+        delta: &mut PublishDelta,
+    ) -> Result<()> {
+        let path = images_dir.join(name);
+        let published = previous.assets.get(name);
+        if let Some(published) = published.filter(|p| p.source_url == url && path.exists()) {
+            state.assets.insert(name.to_string(), published.clone());
+            delta.unchanged += 1;
+            return Ok(());
+        }
 
+        let bytes = match self.download_image(url).await {
+            Ok(bytes) => bytes,
+            // The previous image stays published until a download succeeds
+            Err(e) => match published.filter(|_| path.exists()) {
+                Some(published) => {
+                    info!(
+                        "Keeping the previous {} after a failed download: {}",
+                        name, e
+                    );
+                    state.assets.insert(name.to_string(), published.clone());
+                    delta.unchanged += 1;
+                    return Ok(());
+                }
+                None => return Err(e),
+            },
+        };
+        let hash = format!("{:016x}", fnv1a(&bytes));
+        match published {
+            Some(published) if published.hash == hash && path.exists() => delta.unchanged += 1,
+            Some(_) => delta.updated += 1,
+            None => delta.added += 1,
+        }
+        let tmp_path = images_dir.join(format!("{}.tmp", name));
+        tokio::fs::write(&tmp_path, &bytes).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        state.assets.insert(
+            name.to_string(),
+            AssetState {
+                source_url: url.to_string(),
+                hash,
+            },
+        );
         Ok(())
     }
 
+    /// Renders the card of `game` and writes it unless it is unchanged.
+    /// Returns whether it was written.
+    async fn publish_card(
+        &self,
+        game: &mut Game,
+        cover: Option<&[u8]>,
+        cards_dir: &Path,
+        filename: &str,
+    ) -> Result<bool> {
+        let name = format!("{}.png", filename);
+        let path = cards_dir.join(&name);
+        let png = render_card(game, cover)?;
+        game.card_image = Some(format!(
+            "https://{}.github.io/{}/cards/{}",
+            self.username, self.repo, name
+        ));
+
+        if tokio::fs::read(&path)
+            .await
+            .is_ok_and(|existing| existing == png)
+        {
+            return Ok(false);
+        }
+        tokio::fs::write(&path, png).await?;
+        Ok(true)
+    }
+
+    async fn download_image(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
     fn sanitize_filename(&self, title: &str) -> String {
         title
             .to_lowercase()
@@ -0,0 +1,83 @@
+use crate::domain::storage::Storage;
+use crate::domain::RunRecord;
+use crate::error::{GameError, Result};
+use std::sync::Arc;
+use tracing::info;
+
+pub struct RunService {
+    store: Arc<dyn Storage>,
+    /// Number of runs whose records and artifacts are kept, all if unset
+    keep: Option<usize>,
+}
+
+impl RunService {
+    pub fn new(store: Arc<dyn Storage + 'static>) -> Self {
+        Self { store, keep: None }
+    }
+
+    /// Removes the records and artifacts of all but the last `keep` runs
+    /// whenever a run finishes
+    pub fn with_retention(mut self, keep: usize) -> Self {
+        self.keep = Some(keep);
+        self
+    }
+
+    /// Records a new run with the next free ID
+    pub fn start(&self) -> Result<RunRecord> {
+        let mut runs = self.store.load_runs()?;
+        let id = runs.iter().map(|run| run.id).max().unwrap_or(0) + 1;
+
+        let run = RunRecord::new(id);
+        runs.push(run.clone());
+        self.store.save_runs(&runs)?;
+        self.store.begin_run(&run.key);
+
+        Ok(run)
+    }
+
+    pub fn finish(&self, run: &mut RunRecord, outcome: &Result<usize>) -> Result<()> {
+        run.finish(outcome);
+
+        let mut runs = self.store.load_runs()?;
+        match runs.iter_mut().find(|r| r.id == run.id) {
+            Some(existing) => *existing = run.clone(),
+            None => runs.push(run.clone()),
+        }
+        if let Some(keep) = self.keep {
+            runs.sort_by_key(|r| r.id);
+            let expired = runs.len().saturating_sub(keep.max(1));
+            for old in runs.drain(..expired) {
+                info!("Removing run {} ({})", old.id, old.key);
+                if !old.key.is_empty() {
+                    self.store.remove_run(&old.key)?;
+                }
+            }
+        }
+        self.store.save_runs(&runs)
+    }
+
+    pub fn list(&self) -> Result<()> {
+        for run in self.store.load_runs()? {
+            println!(
+                "#{} {:?} started {} ({} games)",
+                run.id,
+                run.status,
+                run.started_at,
+                run.total_games.unwrap_or(0)
+            );
+        }
+        Ok(())
+    }
+
+    pub fn show(&self, id: u64) -> Result<()> {
+        let run = self
+            .store
+            .load_runs()?
+            .into_iter()
+            .find(|run| run.id == id)
+            .ok_or_else(|| GameError::Other(format!("No run with ID {}", id)))?;
+
+        println!("{}", serde_json::to_string_pretty(&run)?);
+        Ok(())
+    }
+}
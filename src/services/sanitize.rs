@@ -0,0 +1,47 @@
+use scraper::Html;
+
+/// Longest summary written to the manifest, in characters
+pub const SUMMARY_MAX_CHARS: usize = 300;
+
+/// Reduces store page HTML to its text. Tags, images (including tracking
+/// pixels), scripts and styles are dropped, entities decoded and whitespace
+/// collapsed.
+pub fn strip_html(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut text = String::with_capacity(html.len());
+    for node in fragment.root_element().descendants() {
+        let Some(node_text) = node.value().as_text() else {
+            continue;
+        };
+        let in_code = node.ancestors().any(|ancestor| {
+            ancestor
+                .value()
+                .as_element()
+                .is_some_and(|element| matches!(element.name(), "script" | "style"))
+        });
+        if !in_code {
+            text.push_str(node_text);
+            text.push(' ');
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Sanitized text of `html`, cut at a word boundary once longer than
+/// `max_chars`
+pub fn summarize(html: &str, max_chars: usize) -> Option<String> {
+    let text = strip_html(html);
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= max_chars {
+        return Some(text);
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) => &cut[..space],
+        None => cut.as_str(),
+    };
+    Some(format!("{}…", cut.trim_end_matches([',', '.', ';', ':'])))
+}
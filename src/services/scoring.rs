@@ -1,24 +1,277 @@
-use std::collections::HashMap;
+use crate::domain::numbers::{score_from_f64, Rank, Score};
+use crate::domain::{Game, ScoreConfidence};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-pub fn calculate_harmony_score(rankings: &HashMap<String, u64>) -> u64 {
-    if rankings.is_empty() {
-        return 0;
+/// Position score credited for an appearance on an unranked list, the same as
+/// sitting in the middle of a top 100.
+const UNRANKED_POSITION_SCORE: Score = 50;
+
+/// Lists of unknown length are scored as a top 100
+const DEFAULT_LIST_LENGTH: usize = 100;
+
+/// Weight of the mean score when shrinking, in sources: a game on this many
+/// lists is shrunk halfway toward the mean
+const PRIOR_SOURCES: f64 = 2.0;
+
+/// Games on fewer lists have a provisional score
+const SETTLED_SOURCES: usize = 3;
+
+/// How the rankings of the sources are aggregated into the harmony score
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringStrategy {
+    /// Sum of the position scores, boosted by the number of sources
+    #[default]
+    Heuristic,
+    /// Sum of the position scores
+    Borda,
+    /// Median position score of the sources listing the game
+    MedianRank,
+    /// Share of the other games won against head to head, a game winning
+    /// when more sources rank it higher. Games missing from a list rank below
+    /// all of its entries.
+    Copeland,
+}
+
+impl ScoringStrategy {
+    pub const ALL: [ScoringStrategy; 4] = [
+        ScoringStrategy::Heuristic,
+        ScoringStrategy::Borda,
+        ScoringStrategy::MedianRank,
+        ScoringStrategy::Copeland,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringStrategy::Heuristic => "heuristic",
+            ScoringStrategy::Borda => "borda",
+            ScoringStrategy::MedianRank => "median_rank",
+            ScoringStrategy::Copeland => "copeland",
+        }
+    }
+}
+
+/// Scores rankings, scaling every rank to the length of its source's list
+#[derive(Debug, Clone, Default)]
+pub struct HarmonyScorer {
+    /// Number of entries of each source's list, by source name
+    list_lengths: HashMap<String, usize>,
+    strategy: ScoringStrategy,
+    /// Also keep the scores of every strategy on the games
+    compare: bool,
+}
+
+impl HarmonyScorer {
+    pub fn new(list_lengths: HashMap<String, usize>) -> Self {
+        Self {
+            list_lengths,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: ScoringStrategy, compare: bool) -> Self {
+        self.strategy = strategy;
+        self.compare = compare;
+        self
+    }
+
+    /// Heuristic score of a single game, which unlike some strategies needs
+    /// no other games
+    pub fn score(&self, rankings: &HashMap<String, Option<Rank>>) -> Score {
+        if rankings.is_empty() {
+            return 0;
+        }
+
+        // Average position score (0-100)
+        let position_score: Score = self.position_scores(rankings).values().sum();
+
+        // Appearance multiplier (1.0 to 2.0)
+        // With 5 sites, this gives:
+        // 1 site:   no bonus (multiplier 1.0)
+        // 2 sites:  25% bonus (multiplier 1.25)
+        // 3 sites:  50% bonus (multiplier 1.5)
+        // 4 sites:  75% bonus (multiplier 1.75)
+        // 5 sites:  100% bonus (multiplier 2.0)
+        let extra_appearances = Score::try_from(rankings.len() - 1).unwrap_or(Score::MAX);
+        let appearance_multiplier = extra_appearances.saturating_mul(25).saturating_add(100);
+
+        position_score.saturating_mul(appearance_multiplier) / 100
+    }
+
+    /// Sets the harmony score of every game with the configured strategy and,
+    /// when comparing, the scores of all strategies
+    pub fn score_all(&self, games: &mut [Game]) {
+        let strategies: Vec<ScoringStrategy> = if self.compare {
+            ScoringStrategy::ALL.to_vec()
+        } else {
+            vec![self.strategy]
+        };
+        let rankings: Vec<&HashMap<String, Option<Rank>>> =
+            games.iter().map(|game| &game.rankings).collect();
+        let scores: Vec<(ScoringStrategy, Vec<Score>)> = strategies
+            .into_iter()
+            .map(|strategy| (strategy, self.scores(strategy, &rankings)))
+            .collect();
+
+        for (i, game) in games.iter_mut().enumerate() {
+            for (strategy, scores) in &scores {
+                if *strategy == self.strategy {
+                    game.harmony_score = scores[i];
+                }
+                if self.compare {
+                    game.strategy_scores
+                        .insert(strategy.name().to_string(), scores[i]);
+                }
+            }
+        }
     }
 
-    // Average position score (0-100)
-    let position_score: u64 = rankings
-        .values()
-        .map(|&rank| if rank <= 100 { 101 - rank } else { 0 })
-        .sum();
+    /// Shrinks every harmony score toward the mean of all games, weighting
+    /// the game's own score by its number of sources. Runs on the final,
+    /// possibly smoothed, scores.
+    pub fn estimate_confidence(games: &mut [Game]) {
+        if games.is_empty() {
+            return;
+        }
+        let mean = games.iter().map(|g| g.harmony_score as f64).sum::<f64>() / games.len() as f64;
+        for game in games.iter_mut() {
+            let sources = game.rankings.len();
+            let weight = sources as f64 / (sources as f64 + PRIOR_SOURCES);
+            let score = game.harmony_score as f64;
+            let shrunk = weight * score + (1.0 - weight) * mean;
+            game.confidence = Some(ScoreConfidence {
+                shrunk_score: score_from_f64(shrunk.round()),
+                margin: score_from_f64((score - shrunk).abs().round()),
+                provisional: sources < SETTLED_SOURCES,
+            });
+        }
+    }
+
+    /// Scores of each of `rankings` with the configured strategy, which may
+    /// compare them with each other
+    pub fn score_rankings(&self, rankings: &[&HashMap<String, Option<Rank>>]) -> Vec<Score> {
+        self.scores(self.strategy, rankings)
+    }
+
+    fn scores(
+        &self,
+        strategy: ScoringStrategy,
+        rankings: &[&HashMap<String, Option<Rank>>],
+    ) -> Vec<Score> {
+        match strategy {
+            ScoringStrategy::Heuristic => rankings.iter().map(|r| self.score(r)).collect(),
+            ScoringStrategy::Borda => rankings
+                .iter()
+                .map(|r| self.position_scores(r).values().sum())
+                .collect(),
+            ScoringStrategy::MedianRank => rankings
+                .iter()
+                .map(|r| median(self.position_scores(r).into_values().collect()))
+                .collect(),
+            ScoringStrategy::Copeland => self.copeland(rankings),
+        }
+    }
+
+    /// Position score on every list the game is on
+    fn position_scores(&self, rankings: &HashMap<String, Option<Rank>>) -> BTreeMap<String, Score> {
+        rankings
+            .iter()
+            .map(|(source, &rank)| {
+                let score = match rank {
+                    Some(rank) => position_score(rank, self.list_length(source)),
+                    None => UNRANKED_POSITION_SCORE,
+                };
+                (source.clone(), score)
+            })
+            .collect()
+    }
+
+    fn list_length(&self, source: &str) -> usize {
+        self.list_lengths
+            .get(source)
+            .copied()
+            .unwrap_or(DEFAULT_LIST_LENGTH)
+    }
+
+    fn copeland(&self, games: &[&HashMap<String, Option<Rank>>]) -> Vec<Score> {
+        if games.len() < 2 {
+            return vec![100; games.len()];
+        }
+
+        let sources: BTreeSet<&String> = games.iter().flat_map(|r| r.keys()).collect();
+        // Position score of every game on every list, 0 where it's missing
+        let table: Vec<Vec<Score>> = games
+            .iter()
+            .map(|rankings| {
+                let scores = self.position_scores(rankings);
+                sources
+                    .iter()
+                    .map(|source| scores.get(*source).copied().unwrap_or(0))
+                    .collect()
+            })
+            .collect();
+
+        // Two points per win and one per tie
+        let mut points: Vec<u64> = vec![0; games.len()];
+        for i in 0..games.len() {
+            for j in i + 1..games.len() {
+                let (mut wins, mut losses) = (0, 0);
+                for (a, b) in table[i].iter().zip(&table[j]) {
+                    if a > b {
+                        wins += 1;
+                    } else if b > a {
+                        losses += 1;
+                    }
+                }
+                match wins.cmp(&losses) {
+                    std::cmp::Ordering::Greater => points[i] += 2,
+                    std::cmp::Ordering::Less => points[j] += 2,
+                    std::cmp::Ordering::Equal => {
+                        points[i] += 1;
+                        points[j] += 1;
+                    }
+                }
+            }
+        }
 
-    // Appearance multiplier (1.0 to 2.0)
-    // With 5 sites, this gives:
-    // 1 site:   no bonus (multiplier 1.0)
-    // 2 sites:  25% bonus (multiplier 1.25)
-    // 3 sites:  50% bonus (multiplier 1.5)
-    // 4 sites:  75% bonus (multiplier 1.75)
-    // 5 sites:  100% bonus (multiplier 2.0)
-    let appearance_multiplier = 100 + (25 * (rankings.len() - 1)) as u64;
+        let most = 2 * (games.len() as u64 - 1);
+        points.into_iter().map(|p| p * 100 / most).collect()
+    }
+}
+
+/// Score of `rank` as if it were the same percentile of a top 100: #1 scores
+/// 100 and the last entry 1 regardless of the list's length. Ranks beyond the
+/// list score nothing.
+fn position_score(rank: Rank, list_length: usize) -> Score {
+    let length = list_length.max(1) as f64;
+    let rank = rank as f64;
+    if rank < 1.0 || rank > length {
+        return 0;
+    }
+    let scaled = if length > 1.0 {
+        1.0 + (rank - 1.0) * 99.0 / (length - 1.0)
+    } else {
+        1.0
+    };
+    score_from_f64((101.0 - scaled).round())
+}
+
+fn median(mut scores: Vec<Score>) -> Score {
+    if scores.is_empty() {
+        return 0;
+    }
+    scores.sort_unstable();
+    let middle = scores.len() / 2;
+    if scores.len().is_multiple_of(2) {
+        (scores[middle - 1] + scores[middle]) / 2
+    } else {
+        scores[middle]
+    }
+}
 
-    position_score * appearance_multiplier / 100
+/// Exponentially smooths a score, `alpha` being the weight of the new score
+pub fn smooth_harmony_score(raw: Score, previous: Score, alpha: f64) -> Score {
+    let alpha = alpha.clamp(0.0, 1.0);
+    score_from_f64(alpha * raw as f64 + (1.0 - alpha) * previous as f64)
 }
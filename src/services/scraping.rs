@@ -1,39 +1,206 @@
-use crate::config::Website;
-use crate::error::Result;
+use crate::config::{FetchBackend, Pagination, UserAgentPool, Website, REDDIT_SCRAPER_TYPE};
+use crate::domain::numbers::{deserialize_rank, Rank};
+use crate::domain::storage::Storage;
+use crate::error::{GameError, Result};
+use crate::infrastructure::read_local_list;
+use crate::infrastructure::BackloggdScraper;
+use crate::infrastructure::BrowserFetcher;
 use crate::infrastructure::EurogamerScraper;
+use crate::infrastructure::HttpFetcher;
 use crate::infrastructure::IGNScraper;
 use crate::infrastructure::PCGamerScraper;
 use crate::infrastructure::PolygonPS5Top25;
 use crate::infrastructure::PolygonScraper;
 use crate::infrastructure::RPSScraper;
-use crate::infrastructure::{Selectors, WebsiteScraper};
-use reqwest::Client;
-use scraper::Html;
+use crate::infrastructure::RedditClient;
+use crate::infrastructure::RobotsChecker;
+use crate::infrastructure::WaybackFetcher;
+use crate::infrastructure::{ExtractedGame, Selectors, WebsiteScraper, REQUEST_STATS};
+use crate::infrastructure::{CachingFetcher, Fetcher, FixtureFetcher, RecordingFetcher};
+use crate::services::identity::fnv1a;
+use crate::services::progress;
+use chrono::Local;
+use reqwest::{Client, Url};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::sleep;
 use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebsiteGames {
     pub source: String,
     pub games: Vec<ScrapedGame>,
+    #[serde(default)]
+    pub fetch: SourceFetch,
+    /// Number of entries of the list, the ranks being scaled to it
+    #[serde(default)]
+    pub list_length: Option<usize>,
 }
 
+impl WebsiteGames {
+    pub fn new(source: String, games: Vec<(String, Rank)>, fetch: SourceFetch) -> Self {
+        let games: Vec<ScrapedGame> = games
+            .into_iter()
+            .map(|(name, rank)| ScrapedGame {
+                name,
+                rank,
+                anchor: None,
+                blurb: None,
+            })
+            .collect();
+        let mut website = Self {
+            source,
+            games,
+            fetch,
+            list_length: None,
+        };
+        website.list_length = Some(website.list_length());
+        website
+    }
+
+    /// The highest rank, or the number of games if fewer were ranked, e.g.
+    /// because the scraper missed some
+    pub fn list_length(&self) -> usize {
+        self.list_length.unwrap_or_else(|| {
+            let highest = self.games.iter().map(|g| g.rank).max().unwrap_or(0);
+            (highest as usize).max(self.games.len())
+        })
+    }
+}
+
+/// When and how a source was fetched, and how current the list itself is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceFetch {
+    pub scraped_at: Option<String>,
+    /// Status of the page response, missing for local lists and API sources
+    pub http_status: Option<u16>,
+    /// Publication date of the article, from its meta tags
+    pub published_at: Option<String>,
+    pub modified_at: Option<String>,
+    /// Byline of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Hash of the text of the page, to tell a changed page from a changed
+    /// layout when the extracted games drift
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+/// Meta tags and links carrying the byline, most specific first
+const AUTHOR_META: &[&str] = &[
+    "meta[name='author']",
+    "meta[property='article:author']",
+    "meta[name='parsely-author']",
+    "[rel='author']",
+];
+
+/// Meta tags carrying the publication date, most specific first
+const PUBLISHED_META: &[&str] = &[
+    "meta[property='article:published_time']",
+    "meta[itemprop='datePublished']",
+    "meta[name='date']",
+];
+
+const MODIFIED_META: &[&str] = &[
+    "meta[property='article:modified_time']",
+    "meta[property='og:updated_time']",
+    "meta[itemprop='dateModified']",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrapedGame {
     pub name: String,
-    pub rank: u64,
+    #[serde(deserialize_with = "deserialize_rank")]
+    pub rank: Rank,
+    /// Id of the list entry in the page, for linking to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
+    /// The reviewer's paragraph about the game, from `blurb_selector`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blurb: Option<String>,
+}
+
+/// Browser of the sites fetched with one, unless set with `with_browser`
+const DEFAULT_BROWSER: &str = "chromium";
+
+/// Least time between two requests to the same site
+const MIN_CRAWL_DELAY: Duration = Duration::from_secs(1);
+
+/// Saved copies of the scraped pages, so scrapers can be checked against real
+/// pages without the network
+#[derive(Debug, Clone)]
+pub enum Fixtures {
+    /// Save every fetched page to the directory
+    Record(PathBuf),
+    /// Read pages from the directory instead of fetching them
+    Replay(PathBuf),
 }
 
+/// Fetches the pages of each site with its configured fetcher and extracts
+/// the games with its scraper
 pub struct ScrapingService {
-    client: Client,
+    http: HttpFetcher,
+    /// Headless browser of the sites fetched with one
+    browser: String,
+    reddit: RedditClient,
+    robots: RobotsChecker,
+    ignore_robots: bool,
+    fixtures: Option<Fixtures>,
+    /// Store the fetched pages are cached in, and how long they are reused
+    page_cache: Option<(Arc<dyn Storage>, Option<Duration>)>,
 }
 
 impl ScrapingService {
     pub fn new(client: Client) -> Self {
         info!("Created new Scraping service");
-        Self { client }
+        Self {
+            reddit: RedditClient::new(client.clone()),
+            robots: RobotsChecker::new(client.clone()),
+            http: HttpFetcher::new(client),
+            browser: DEFAULT_BROWSER.to_string(),
+            ignore_robots: false,
+            fixtures: None,
+            page_cache: None,
+        }
+    }
+
+    /// Sends every scraping request with the next user agent of `user_agents`,
+    /// unless the site configures its own
+    pub fn with_user_agents(mut self, user_agents: UserAgentPool) -> Self {
+        self.http = self.http.with_user_agents(user_agents);
+        self
+    }
+
+    /// Fetches the sites configured with the `browser` fetcher with `browser`
+    pub fn with_browser(mut self, browser: String) -> Self {
+        self.browser = browser;
+        self
+    }
+
+    /// Scrapes pages of every site even where its robots.txt disallows it
+    pub fn with_ignore_robots(mut self, ignore_robots: bool) -> Self {
+        self.ignore_robots = ignore_robots;
+        self
+    }
+
+    /// Records the fetched pages to, or replays them from, `fixtures`. Reddit
+    /// lists are always fetched from its API.
+    pub fn with_fixtures(mut self, fixtures: Option<Fixtures>) -> Self {
+        self.fixtures = fixtures;
+        self
+    }
+
+    /// Caches the fetched pages in `store`, scraping those fetched less than
+    /// `max_age` ago from it
+    pub fn with_page_cache(mut self, store: Arc<dyn Storage>, max_age: Option<Duration>) -> Self {
+        self.page_cache = Some((store, max_age));
+        self
+    }
+
+    fn replaying(&self) -> bool {
+        matches!(self.fixtures, Some(Fixtures::Replay(_)))
     }
 
     fn get_scraper(&self, website: &Website) -> Box<dyn WebsiteScraper> {
@@ -44,36 +211,382 @@ impl ScrapingService {
             "eurogamer" => Box::new(EurogamerScraper),
             "rps" => Box::new(RPSScraper),
             "pcgamer" => Box::new(PCGamerScraper),
+            "backloggd" => Box::new(BackloggdScraper),
             _ => panic!("Unknown scraper type")
         }
     }
 
+    /// The fetcher of `website` behind the page cache, replaced by the fixtures
+    /// when replaying them and wrapped to save its pages when recording them
+    fn get_fetcher<'a>(&'a self, website: &'a Website) -> Box<dyn Fetcher + 'a> {
+        let mut fetcher: Box<dyn Fetcher + 'a> = match website.fetcher {
+            FetchBackend::Http => Box::new(&self.http),
+            FetchBackend::Browser => Box::new(BrowserFetcher::new(&self.browser)),
+            FetchBackend::Wayback => Box::new(WaybackFetcher::new(
+                &self.http,
+                website.wayback_timestamp.as_deref(),
+            )),
+        };
+        if let Some((store, max_age)) = &self.page_cache {
+            fetcher = Box::new(CachingFetcher::new(fetcher, store.as_ref(), *max_age));
+        }
+        match &self.fixtures {
+            Some(Fixtures::Replay(dir)) => Box::new(FixtureFetcher::new(dir)),
+            Some(Fixtures::Record(dir)) => Box::new(RecordingFetcher::new(fetcher, dir)),
+            None => fetcher,
+        }
+    }
+
     pub async fn scrape_all(&self, websites: &[Website]) -> Result<Vec<WebsiteGames>> {
         let mut games = Vec::new();
+        let progress = progress::bar("scraping", websites.len() as u64);
 
         for website in websites {
-            let website_games = self.scrape_website(website).await?;
+            progress.set_message(website.display_name.clone());
+            progress.inc(1);
+            if website.is_local() {
+                games.push(Self::read_local(website).map_err(|cause| {
+                    GameError::scrape_failed(&website.display_name, &website.url, cause)
+                })?);
+                continue;
+            }
+
+            let website_games = self.scrape_website(website).await.map_err(|cause| {
+                GameError::scrape_failed(&website.display_name, &website.url, cause)
+            })?;
             games.push(website_games);
-            sleep(Duration::from_secs(1)).await;
+            REQUEST_STATS
+                .throttle("scraping", self.crawl_delay(website).await)
+                .await;
         }
 
+        progress.finish_and_clear();
         Ok(games)
     }
 
-    async fn scrape_website(&self, website: &Website) -> Result<WebsiteGames> {
-        let response = self.client.get(&website.url).send().await?.text().await?;
-        let document = Html::parse_document(&response);
-        let selectors = Selectors::new(&website.name_selector, &website.rank_selector)?;
+    fn read_local(website: &Website) -> Result<WebsiteGames> {
+        let games = read_local_list(Path::new(&website.url))?;
+        info!("Read {} games from {}", games.len(), website.url);
 
-        let scraper = self.get_scraper(website);
-        let games = scraper.extract_games(&document, &selectors)?;
+        Ok(WebsiteGames::new(
+            website.url.clone(),
+            games,
+            SourceFetch {
+                scraped_at: Some(Local::now().to_rfc3339()),
+                ..SourceFetch::default()
+            },
+        ))
+    }
 
-        Ok(WebsiteGames {
-            source: website.url.clone(),
-            games: games
+    async fn scrape_website(&self, website: &Website) -> Result<WebsiteGames> {
+        let mut fetch = SourceFetch {
+            scraped_at: Some(Local::now().to_rfc3339()),
+            ..SourceFetch::default()
+        };
+        let (games, anchors, blurbs) = if website.scraper_type == REDDIT_SCRAPER_TYPE {
+            let games = self.reddit.fetch_list(&website.url).await?;
+            (games, Vec::new(), Vec::new())
+        } else {
+            let (document, selectors) = self.fetch_document(website, &mut fetch).await?;
+            let scraper = self.get_scraper(website);
+            let extracted = scraper.extract_games(&document, &selectors)?;
+            let anchors = Self::anchors(&extracted);
+            let blurbs = scraper.extract_blurbs(&document, &selectors, &extracted);
+            let games = extracted
                 .into_iter()
-                .map(|(name, rank)| ScrapedGame { name, rank })
-                .collect(),
-        })
+                .map(|game| (game.name, game.rank))
+                .collect();
+            (games, anchors, blurbs)
+        };
+
+        let mut website_games = WebsiteGames::new(website.url.clone(), games, fetch);
+        for (game, anchor) in website_games.games.iter_mut().zip(anchors) {
+            game.anchor = anchor;
+        }
+        for (game, blurb) in website_games.games.iter_mut().zip(blurbs) {
+            game.blurb = blurb;
+        }
+        Ok(website_games)
+    }
+
+    /// Id of the element each game was extracted from, or of its closest
+    /// ancestor that has one
+    fn anchors(games: &[ExtractedGame]) -> Vec<Option<String>> {
+        games
+            .iter()
+            .map(|game| {
+                std::iter::once(game.element)
+                    .chain(game.element.ancestors().filter_map(ElementRef::wrap))
+                    .find_map(|el| el.value().id())
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// Fetches the list of `website`, including its further pages, and its
+    /// selectors
+    async fn fetch_document(
+        &self,
+        website: &Website,
+        fetch: &mut SourceFetch,
+    ) -> Result<(Html, Selectors)> {
+        self.check_robots(website, &website.url).await?;
+        let page = self
+            .get_fetcher(website)
+            .fetch(website, &website.url)
+            .await?;
+        fetch.http_status = Some(page.status);
+        let mut document = Html::parse_document(&page.body);
+        let meta = &website.article_meta;
+        fetch.author = Self::meta_value(&document, &meta.author, AUTHOR_META);
+        fetch.published_at = Self::meta_value(&document, &meta.published, PUBLISHED_META);
+        fetch.modified_at = Self::meta_value(&document, &meta.modified, MODIFIED_META);
+        let selectors = Selectors::new(&website.name_selector, &website.rank_selector)?
+            .with_regexes(website.rank_regex.as_deref(), website.name_regex.as_deref())?
+            .with_blurb(website.blurb_selector.as_deref())?;
+        if let Some(pagination) = &website.pagination {
+            document = self
+                .follow_pages(website, document, pagination, &selectors)
+                .await?;
+        }
+        fetch.fingerprint = Some(Self::fingerprint(&document));
+        Ok((document, selectors))
+    }
+
+    /// Scrapes a single website and prints the extracted games. With `debug`,
+    /// also every element the selectors match, and why those the scraper
+    /// extracted no game from were rejected.
+    pub async fn preview(&self, website: &Website, debug: bool) -> Result<()> {
+        if website.is_local() || website.scraper_type == REDDIT_SCRAPER_TYPE {
+            let games = self.scrape_all(std::slice::from_ref(website)).await?;
+            for game in games.iter().flat_map(|website| &website.games) {
+                println!("#{} {}", game.rank, game.name);
+            }
+            return Ok(());
+        }
+
+        let mut fetch = SourceFetch::default();
+        let (document, selectors) = self.fetch_document(website, &mut fetch).await?;
+        info!(
+            "Fetched {} (HTTP {})",
+            website.url,
+            fetch.http_status.unwrap_or_default()
+        );
+        let games = self
+            .get_scraper(website)
+            .extract_games(&document, &selectors)?;
+
+        if debug {
+            let names: Vec<ElementRef> = document.select(&selectors.name).collect();
+            println!(
+                "name_selector {:?} matches {} elements",
+                website.name_selector,
+                names.len()
+            );
+            for (i, element) in names.iter().enumerate() {
+                let (text, id) = Self::element_summary(element);
+                let extracted = games.iter().any(|game| game.element.id() == element.id());
+                match Self::reject_reason(website, &selectors, extracted, &text, &id) {
+                    None => println!("  {}. {:?} (id {:?})", i + 1, text, id),
+                    Some(reason) => {
+                        println!("  {}. {:?} (id {:?}) rejected: {}", i + 1, text, id, reason)
+                    }
+                }
+            }
+            if let Some(rank) = &selectors.rank {
+                let ranks: Vec<String> = document
+                    .select(rank)
+                    .map(|element| Self::element_summary(&element).0)
+                    .collect();
+                println!(
+                    "rank_selector {:?} matches {} elements: {:?}",
+                    website.rank_selector,
+                    ranks.len(),
+                    ranks
+                );
+            }
+        }
+
+        println!(
+            "The {} scraper extracted {} games",
+            website.scraper_type,
+            games.len()
+        );
+        for game in &games {
+            println!("#{} {}", game.rank, game.name);
+        }
+        Ok(())
+    }
+
+    fn element_summary(element: &ElementRef) -> (String, String) {
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let id = element.value().attr("id").unwrap_or_default().to_string();
+        (text, id)
+    }
+
+    /// Why no game came out of a matched element, `None` if one did
+    fn reject_reason(
+        website: &Website,
+        selectors: &Selectors,
+        extracted: bool,
+        text: &str,
+        id: &str,
+    ) -> Option<String> {
+        if extracted {
+            return None;
+        }
+        if text.is_empty() && id.is_empty() {
+            return Some("no text".to_string());
+        }
+        for (field, regex) in [
+            ("rank_regex", &selectors.rank_regex),
+            ("name_regex", &selectors.name_regex),
+        ] {
+            if let Some(regex) = regex {
+                if !regex.is_match(text) && !regex.is_match(id) {
+                    return Some(format!("{} {:?} doesn't match", field, regex.as_str()));
+                }
+            }
+        }
+        Some(format!(
+            "the {} scraper extracted no game from it",
+            website.scraper_type
+        ))
+    }
+
+    /// Fetches the further pages of a list and joins their bodies into one
+    /// document, so scrapers rank the entries across all pages. Pages that fail
+    /// or contain no games end the list.
+    async fn follow_pages(
+        &self,
+        website: &Website,
+        first: Html,
+        pagination: &Pagination,
+        selectors: &Selectors,
+    ) -> Result<Html> {
+        let next_selector = match &pagination.next_selector {
+            Some(selector) => {
+                Some(Selector::parse(selector).map_err(|e| GameError::Selector(e.to_string()))?)
+            }
+            None => None,
+        };
+
+        let url = &website.url;
+        let fetcher = self.get_fetcher(website);
+        let delay = self.crawl_delay(website).await;
+        let mut bodies = vec![Self::body(&first)];
+        let mut visited = vec![url.to_string()];
+        let mut current = first;
+        while visited.len() < pagination.max_pages {
+            let next_url = match (&next_selector, &pagination.url_template) {
+                (Some(selector), _) => current
+                    .select(selector)
+                    .find_map(|el| el.value().attr("href"))
+                    .and_then(|href| Url::parse(visited.last()?).ok()?.join(href).ok())
+                    .map(String::from),
+                (None, Some(template)) => Some(template.replace(
+                    "{page}",
+                    &(pagination.first_page + visited.len() - 1).to_string(),
+                )),
+                (None, None) => None,
+            };
+            let Some(next_url) = next_url.filter(|next| !visited.contains(next)) else {
+                break;
+            };
+
+            if self.check_robots(website, &next_url).await.is_err() {
+                break;
+            }
+            REQUEST_STATS.throttle("scraping", delay).await;
+            let Ok(page) = fetcher.fetch(website, &next_url).await else {
+                break;
+            };
+            if !(200..300).contains(&page.status) {
+                break;
+            }
+            let page = Html::parse_document(&page.body);
+            if page.select(&selectors.name).next().is_none() {
+                break;
+            }
+
+            info!("Following page {} of {}", visited.len() + 1, url);
+            bodies.push(Self::body(&page));
+            visited.push(next_url);
+            current = page;
+        }
+
+        if bodies.len() == 1 {
+            return Ok(current);
+        }
+        Ok(Html::parse_document(&format!(
+            "<html><body>{}</body></html>",
+            bodies.concat()
+        )))
+    }
+
+    /// Refuses `url` if the site's robots.txt disallows it, unless robots.txt
+    /// is ignored globally or for the site
+    async fn check_robots(&self, website: &Website, url: &str) -> Result<()> {
+        if self.ignore_robots
+            || website.ignore_robots
+            || self.replaying()
+            || self.robots.is_allowed(url).await
+        {
+            return Ok(());
+        }
+        Err(GameError::Other(format!(
+            "robots.txt disallows scraping {}, set ignore_robots to scrape it anyway",
+            url
+        )))
+    }
+
+    /// Delay between requests to the site: the configured one, else the
+    /// Crawl-delay of its robots.txt, but never below `MIN_CRAWL_DELAY`
+    async fn crawl_delay(&self, website: &Website) -> Duration {
+        if self.replaying() {
+            return Duration::ZERO;
+        }
+        let delay = match website.crawl_delay {
+            Some(seconds) => Some(Duration::from_secs_f64(seconds.max(0.0))),
+            None => self.robots.rules(&website.url).await.crawl_delay,
+        };
+        delay.unwrap_or_default().max(MIN_CRAWL_DELAY)
+    }
+
+    fn fingerprint(document: &Html) -> String {
+        let text = document.root_element().text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        format!("{:016x}", fnv1a(text.as_bytes()))
+    }
+
+    fn body(document: &Html) -> String {
+        let selector = Selector::parse("body").unwrap();
+        document
+            .select(&selector)
+            .next()
+            .map(|body| body.inner_html())
+            .unwrap_or_else(|| document.root_element().inner_html())
+    }
+
+    /// `content`, `datetime` or text of the first element matching the
+    /// website's `configured` selectors, else the `common` ones
+    fn meta_value(document: &Html, configured: &[String], common: &[&str]) -> Option<String> {
+        configured
+            .iter()
+            .map(String::as_str)
+            .chain(common.iter().copied())
+            .find_map(|selector| {
+                let selector = Selector::parse(selector).ok()?;
+                document.select(&selector).find_map(|el| {
+                    let attr = el.value().attr("content").or(el.value().attr("datetime"));
+                    let value = match attr {
+                        Some(content) => content.to_string(),
+                        None => el.text().collect::<Vec<_>>().join(" "),
+                    };
+                    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                    (!value.is_empty()).then_some(value)
+                })
+            })
     }
 }
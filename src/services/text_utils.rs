@@ -1,21 +1,125 @@
+use once_cell::sync::Lazy;
 use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use tracing::warn;
+use unicode_normalization::char::is_combining_mark;
 use unicode_normalization::UnicodeNormalization;
-use crate::config::ScraperConfig;
+use crate::config::{CasingRules, NormalizationRules, ScraperConfig};
+use crate::services::identity::fnv1a;
+
+/// Characters between words, which are replaced by spaces instead of being
+/// dropped, so "NieR:Automata" keeps its two words
+static SEPARATORS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[:/|·•–—~]").unwrap());
+static YEAR_SUFFIX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*(?:\(?\d{4}\)?)\b").unwrap());
+static POSSESSIVE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:'\s*s|\s+s)\b").unwrap());
+static PUNCTUATION_EXCEPT_HYPHENS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\w\s-]").unwrap());
+static NUMBERS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)").unwrap());
+static PUNCTUATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\w\s]").unwrap());
+/// First colon or dash between words, separating the main title from the
+/// subtitle
+static SUBTITLE_SEPARATOR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\s*[:：]\s*|\s+[-–—]\s+").unwrap());
+
+/// Letters of Roman numerals that are converted. C, D and M are left out, as
+/// they mostly form words and abbreviations like "mix", "civ" or "dc".
+const ROMAN_DIGITS: [(u32, &str); 7] = [
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+const WORD_NUMBERS: [&str; 11] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+];
+
+static WORD_NUMBER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"\b(?:{})\b", WORD_NUMBERS.join("|"))).unwrap());
+
+/// Rules `normalize` follows, the bundled defaults until `set_rules` is called
+static RULES: OnceLock<CompiledRules> = OnceLock::new();
+
+/// Normalization rules with their patterns compiled
+struct CompiledRules {
+    stop_words: HashSet<String>,
+    replacements: Vec<(Regex, String)>,
+    protected_terms: Vec<Vec<String>>,
+    /// Hash of the rules, part of the keys of caches built from normalized
+    /// titles
+    fingerprint: u64,
+}
+
+impl CompiledRules {
+    fn new(rules: &NormalizationRules) -> Self {
+        Self {
+            stop_words: rules.stop_words.iter().map(|w| w.to_lowercase()).collect(),
+            replacements: rules
+                .replacements
+                .iter()
+                .filter_map(|r| Some((Regex::new(&r.pattern).ok()?, r.replacement.clone())))
+                .collect(),
+            protected_terms: rules
+                .protected_terms
+                .iter()
+                .map(|term| {
+                    let words: Vec<String> =
+                        term.split_whitespace().map(|w| w.to_lowercase()).collect();
+                    words
+                })
+                .filter(|words| !words.is_empty())
+                .collect(),
+            fingerprint: fnv1a(&serde_json::to_vec(rules).unwrap_or_default()),
+        }
+    }
+
+    /// Marks the words that are part of a protected term
+    fn protected(&self, words: &[&str]) -> Vec<bool> {
+        let mut protected = vec![false; words.len()];
+        for term in &self.protected_terms {
+            for start in 0..words.len().saturating_sub(term.len() - 1) {
+                if term.iter().zip(&words[start..]).all(|(t, w)| t == w) {
+                    protected[start..start + term.len()].fill(true);
+                }
+            }
+        }
+        protected
+    }
+}
 
 /// Utilities for processing and normalizing game titles
 pub struct TitleNormalizer;
 
 impl TitleNormalizer {
-    /// Formats a title for display by properly capitalizing words
-    pub fn format_for_display(title: &str) -> String {
-        // List of words that should not be capitalized
-        let lowercase_words = ["the", "of", "and", "in", "on", "at", "to", "for", "with"];
+    /// Replaces the bundled normalization rules. Only the first call takes
+    /// effect, so it belongs at startup before any title is normalized.
+    pub fn set_rules(rules: &NormalizationRules) {
+        if RULES.set(CompiledRules::new(rules)).is_err() {
+            warn!("Normalization rules were already set, ignoring new ones");
+        }
+    }
+
+    fn rules() -> &'static CompiledRules {
+        RULES.get_or_init(|| CompiledRules::new(&NormalizationRules::default()))
+    }
+
+    /// Hash of the normalization rules in effect, which caches of normalized
+    /// titles are keyed by so they are rebuilt when the rules change
+    pub fn rules_fingerprint() -> u64 {
+        Self::rules().fingerprint
+    }
 
+    /// Formats a title for display by properly capitalizing words, following
+    /// the given casing rules
+    pub fn format_for_display(title: &str, rules: &CasingRules) -> String {
         title
             .split_whitespace()
             .enumerate()
             .map(|(i, word)| {
-                if i == 0 || !lowercase_words.contains(&word.to_lowercase().as_str()) {
+                let word = if i == 0 || !rules.is_lowercase_word(word) {
                     // Capitalize first letter, keep rest of the case
                     let mut chars = word.chars();
                     match chars.next() {
@@ -24,160 +128,311 @@ impl TitleNormalizer {
                     }
                 } else {
                     word.to_lowercase()
-                }
+                };
+                Self::apply_forced_casing(&word, rules)
             })
             .collect::<Vec<String>>()
             .join(" ")
     }
 
+    /// Applies forced casings to every alphanumeric part of a word, so
+    /// "nier:automata" becomes "NieR:Automata"
+    fn apply_forced_casing(word: &str, rules: &CasingRules) -> String {
+        let mut result = String::with_capacity(word.len());
+        let mut segment = String::new();
+
+        let flush = |segment: &mut String, result: &mut String| {
+            match rules.forced_casing(segment) {
+                Some(forced) => result.push_str(&forced),
+                None => result.push_str(segment),
+            }
+            segment.clear();
+        };
+
+        for c in word.chars() {
+            if c.is_alphanumeric() {
+                segment.push(c);
+            } else {
+                flush(&mut segment, &mut result);
+                result.push(c);
+            }
+        }
+        flush(&mut segment, &mut result);
+
+        result
+    }
+
+    /// Splits a trailing edition suffix off a title, returning the base title
+    /// and the edition, e.g. "Persona 5 Royal" becomes ("Persona 5", "Royal")
+    pub fn split_edition<'a>(title: &'a str, suffixes: &[String]) -> (&'a str, Option<String>) {
+        let trimmed = title.trim_end_matches([')', ']', ' ']);
+
+        let mut suffixes: Vec<&String> = suffixes.iter().collect();
+        suffixes.sort_by_key(|suffix| std::cmp::Reverse(suffix.len()));
+
+        for suffix in suffixes {
+            let Some(start) = trimmed.len().checked_sub(suffix.len()) else {
+                continue;
+            };
+            if !trimmed.is_char_boundary(start) || !trimmed[start..].eq_ignore_ascii_case(suffix) {
+                continue;
+            }
+
+            let base = trimmed[..start].trim_end_matches([' ', ':', '-', '–', '(', '[']);
+            // Only whole words count, "Rehd" has no "HD" edition
+            if base.is_empty() || base.len() == start {
+                continue;
+            }
+            return (base, Some(suffix.clone()));
+        }
+
+        (title, None)
+    }
+
+    /// Splits a title into its main title and subtitle, e.g. "Hades II: Early
+    /// Access" becomes ("Hades II", "Early Access")
+    pub fn split_subtitle(title: &str) -> (&str, Option<&str>) {
+        let mut parts = SUBTITLE_SEPARATOR.splitn(title, 2);
+        let main = parts.next().unwrap_or(title);
+        (main, parts.next())
+    }
+
     pub fn normalize_source(source: &str, scraper_config: &ScraperConfig) -> String {
-        scraper_config.websites.iter()
-            .find(|w| source.contains(&w.pattern))
-            .map(|w| w.display_name.clone())
+        scraper_config.website_for(source)
+            .map(|w| w.source_name())
             .unwrap_or_else(|| source.to_string())
     }
 
     /// Normalizes a game title by converting it to lowercase, removing apostrophes,
     /// replacing hyphens with spaces, removing punctuation, and collapsing multiple spaces.
     pub fn normalize(title: &str) -> String {
+        // Fold full-width forms and diacritics, and split words at separators
+        let title = Self::fold(&title.to_lowercase());
+        let title = SEPARATORS.replace_all(&title, " ");
+
         // Remove year suffixes in parentheses
-        let year_suffix_re = Regex::new(r"\s*(?:\(?\d{4}\)?)\b").unwrap();
-        let title = year_suffix_re
-            .replace_all(&title.to_lowercase(), "")
-            .to_string();
+        let title = YEAR_SUFFIX.replace_all(&title, "").to_string();
 
         // Convert to lowercase
         let mut title = title.to_lowercase();
 
-        let specific_game_replacements = [
-            // Use word boundaries to ensure we match complete words
-            (r"\bhalf[\s-]life\b", "halflife"),
-            (r"\bcounter[\s-]strike\b", "counterstrike"),
-            // Add other specific cases as needed
-        ];
-
-        // Remove game status suffixes
-        let status_patterns = [
-            (r"\s*\(early access\)", ""),
-            (r"\s*early access", ""),
-            (r"\s*\(full release\)", ""),
-            // Add other status patterns as needed
-        ];
-
-        for (pattern, replacement) in status_patterns {
-            let re = Regex::new(pattern).unwrap();
-            title = re.replace_all(&title, replacement).to_string();
+        let rules = Self::rules();
+        for (re, replacement) in &rules.replacements {
+            title = re.replace_all(&title, replacement.as_str()).to_string();
         }
 
         // Remove apostrophes and normalize possessives first
-        let possessive_re = Regex::new(r"(?:'\s*s|\s+s)\b").unwrap();
-        title = possessive_re.replace_all(&title, "s").to_string();
+        title = POSSESSIVE.replace_all(&title, "s").to_string();
 
         // Remove all punctuation except hyphens initially
-        let punctuation_re = Regex::new(r"[^\w\s-]").unwrap();
-        title = punctuation_re.replace_all(&title, "").to_string();
+        title = PUNCTUATION_EXCEPT_HYPHENS
+            .replace_all(&title, "")
+            .to_string();
 
         // Insert spaces around numbers
-        let numbers_re = Regex::new(r"(\d+)").unwrap();
-        title = numbers_re.replace_all(&title, " $1 ").to_string();
+        title = NUMBERS.replace_all(&title, " $1 ").to_string();
+
+        // Convert Roman numerals following a title stem and number words to
+        // Arabic numbers, except within protected terms. A lone "I" only counts
+        // at the end, so "I Am Alive" and "Am I Dead" keep it.
+        let words: Vec<&str> = title.split_whitespace().collect();
+        let protected = rules.protected(&words);
+        title = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if protected[i] {
+                    return word.to_string();
+                }
+                let is_ambiguous = *word == "i" && i + 1 < words.len();
+                match Self::roman_value(word) {
+                    Some(value) if i > 0 && !is_ambiguous => value.to_string(),
+                    _ => WORD_NUMBER
+                        .replace_all(word, |caps: &regex::Captures| {
+                            let number = WORD_NUMBERS.iter().position(|w| *w == &caps[0]);
+                            number.map_or_else(|| caps[0].to_string(), |n| n.to_string())
+                        })
+                        .to_string(),
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        // Remove all remaining punctuation
+        title = PUNCTUATION.replace_all(&title, "").to_string();
+
+        // Remove stop words, except within protected terms
+        let words: Vec<&str> = title.split_whitespace().collect();
+        let protected = rules.protected(&words);
+        let words: Vec<&str> = words
+            .into_iter()
+            .zip(protected)
+            .filter(|(word, protected)| *protected || !rules.stop_words.contains(*word))
+            .map(|(word, _)| word)
+            .collect();
 
-        for (pattern, replacement) in specific_game_replacements {
-            let re = Regex::new(pattern).unwrap();
-            title = re.replace_all(&title, replacement).to_string();
+        words.join(" ").trim().to_string()
+    }
+
+    /// Parses `word` as a Roman numeral written in its canonical form, e.g.
+    /// "xliv" but not "iiii"
+    fn roman_value(word: &str) -> Option<u32> {
+        let digit = |c: char| match c {
+            'i' => Some(1),
+            'v' => Some(5),
+            'x' => Some(10),
+            'l' => Some(50),
+            _ => None,
+        };
+
+        let mut value: u32 = 0;
+        let mut largest = 0;
+        for c in word.chars().rev() {
+            let digit = digit(c)?;
+            if digit < largest {
+                value = value.checked_sub(digit)?;
+            } else {
+                value += digit;
+                largest = digit;
+            }
         }
 
-        // Convert Roman numerals to Arabic numbers
-        let roman_numerals = vec![
-            ("(?i)\\bXXV\\b", "25"),
-            ("(?i)\\bXXIV\\b", "24"),
-            ("(?i)\\bXXIII\\b", "23"),
-            ("(?i)\\bXXII\\b", "22"),
-            ("(?i)\\bXXI\\b", "21"),
-            ("(?i)\\bXX\\b", "20"),
-            ("(?i)\\bXIX\\b", "19"),
-            ("(?i)\\bXVIII\\b", "18"),
-            ("(?i)\\bXVII\\b", "17"),
-            ("(?i)\\bXVI\\b", "16"),
-            ("(?i)\\bXV\\b", "15"),
-            ("(?i)\\bXIV\\b", "14"),
-            ("(?i)\\bXIII\\b", "13"),
-            ("(?i)\\bXII\\b", "12"),
-            ("(?i)\\bXI\\b", "11"),
-            ("(?i)\\bX\\b", "10"),
-            ("(?i)\\bIX\\b", "9"),
-            ("(?i)\\bVIII\\b", "8"),
-            ("(?i)\\bVII\\b", "7"),
-            ("(?i)\\bVI\\b", "6"),
-            ("(?i)\\bV\\b", "5"),
-            ("(?i)\\bIV\\b", "4"),
-            ("(?i)\\bIII\\b", "3"),
-            ("(?i)\\bII\\b", "2"),
-            ("(?i)\\bI\\b", "1"),
-        ];
+        let mut canonical = String::with_capacity(word.len());
+        let mut rest = value;
+        for (digit, numeral) in ROMAN_DIGITS {
+            while rest >= digit {
+                canonical.push_str(numeral);
+                rest -= digit;
+            }
+        }
+        (value > 0 && canonical == word).then_some(value)
+    }
 
-        // Apply Roman numeral replacements
-        for (roman_pattern, num) in roman_numerals {
-            let re = Regex::new(roman_pattern).unwrap();
-            title = re.replace_all(&title, num).to_string();
+    /// Decomposes `title` into compatibility forms without their combining
+    /// marks, so "ｐｏｋéｍｏｎ" becomes "pokemon", and transliterates letters
+    /// that don't decompose, like "ø" and "ß"
+    fn fold(title: &str) -> String {
+        let mut folded = String::with_capacity(title.len());
+        for c in title.nfkd().filter(|c| !is_combining_mark(*c)) {
+            match c {
+                'ø' => folded.push('o'),
+                'æ' => folded.push_str("ae"),
+                'œ' => folded.push_str("oe"),
+                'ß' => folded.push_str("ss"),
+                'đ' | 'ð' => folded.push('d'),
+                'ł' => folded.push('l'),
+                'þ' => folded.push_str("th"),
+                'ı' => folded.push('i'),
+                '’' | '‘' | '´' | '`' => folded.push('\''),
+                _ => folded.push(c),
+            }
         }
+        folded
+    }
+}
 
-        // Replace word numbers with digits
-        let word_numbers = vec![
-            (" zero ", " 0 "),
-            (" one ", " 1 "),
-            (" two ", " 2 "),
-            (" three ", " 3 "),
-            (" four ", " 4 "),
-            (" five ", " 5 "),
-            (" six ", " 6 "),
-            (" seven ", " 7 "),
-            (" eight ", " 8 "),
-            (" nine ", " 9 "),
-            (" ten ", " 10 "),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_titles() {
+        let cases = [
+            // Possessives
+            ("Baldur's Gate 3", "baldurs gate 3"),
+            ("Assassin’s Creed", "assassins creed"),
+            ("Tony Hawk's Pro Skater 1 + 2", "tony hawks pro skater 1 2"),
+            // Subtitles, separators and stop words
+            ("NieR:Automata", "nier automata"),
+            ("The Witcher 3: Wild Hunt", "witcher 3 wild hunt"),
+            ("Half-Life 2", "halflife 2"),
+            ("Hades II (Early Access)", "hades 2"),
+            ("Ｐｏｋéｍｏｎ", "pokemon"),
         ];
+        for (title, normalized) in cases {
+            assert_eq!(TitleNormalizer::normalize(title), normalized, "{}", title);
+        }
+    }
 
-        for (word, num) in word_numbers {
-            title = title.replace(word, num);
+    #[test]
+    fn splits_subtitles() {
+        let cases = [
+            ("Hades II: Early Access", "Hades II", Some("Early Access")),
+            ("NieR:Automata", "NieR", Some("Automata")),
+            ("Halo 3 - ODST", "Halo 3", Some("ODST")),
+            ("Persona 5：Royal", "Persona 5", Some("Royal")),
+            ("Half-Life 2", "Half-Life 2", None),
+            ("Hades", "Hades", None),
+        ];
+        for (title, main, subtitle) in cases {
+            let split = TitleNormalizer::split_subtitle(title);
+            assert_eq!(split, (main, subtitle), "{}", title);
         }
+    }
 
-        // Remove all remaining punctuation
-        let punctuation_re = Regex::new(r"[^\w\s]").unwrap();
-        title = punctuation_re.replace_all(&title, "").to_string();
-
-        // Remove stop words
-        let stop_words = vec![
-            "the",
-            "and",
-            "of",
-            "edition",
-            "remastered",
-            "definitive",
-            "part",
-            "collection",
-            "remake",
-            "reincarnation",
-            "rebirth",
-            "ultra",
-            "deluxe",
-            "ultimate",
-            "complete",
-            "enhanced",
-            "goty",
-            "expanded",
-            "final",
-            "cut",
-            "directors",
+    #[test]
+    fn normalizes_roman_numerals() {
+        let cases = [
+            ("Final Fantasy VII", "fantasy 7"),
+            ("Hades II", "hades 2"),
+            ("Civilization VI", "civilization 6"),
+            ("Grand Theft Auto IV", "grand theft auto 4"),
+            ("Mass Effect XLIV", "mass effect 44"),
+            ("Street Fighter IIII", "street fighter iiii"),
+            ("I Am Alive", "i am alive"),
+            ("Am I Dead", "am i dead"),
+            ("Rocky I", "rocky 1"),
+            ("Mix", "mix"),
+        ];
+        for (title, normalized) in cases {
+            assert_eq!(TitleNormalizer::normalize(title), normalized, "{}", title);
+        }
+    }
+
+    #[test]
+    fn parses_canonical_roman_numerals_only() {
+        let cases = [
+            ("i", Some(1)),
+            ("iv", Some(4)),
+            ("ix", Some(9)),
+            ("xliv", Some(44)),
+            ("iiii", None),
+            ("vx", None),
+            ("civ", None),
+            ("", None),
         ];
+        for (word, value) in cases {
+            assert_eq!(TitleNormalizer::roman_value(word), value, "{}", word);
+        }
+    }
 
-        let words: Vec<String> = title
-            .split_whitespace()
-            .filter(|word| !stop_words.contains(&word.to_lowercase().as_str()))
-            .map(|s| s.to_string())
-            .collect();
+    #[test]
+    fn normalizes_number_words_and_protected_terms() {
+        let cases = [
+            ("Two Point Hospital", "2 point hospital"),
+            ("Zero Escape: Nine Hours", "0 escape 9 hours"),
+            ("Someone", "someone"),
+            ("It Takes Two", "it takes two"),
+            ("It Takes Two (2021)", "it takes two"),
+        ];
+        for (title, normalized) in cases {
+            assert_eq!(TitleNormalizer::normalize(title), normalized, "{}", title);
+        }
+    }
 
-        let title = words.join(" ");
+    #[test]
+    fn fingerprint_changes_with_the_rules() {
+        let defaults = NormalizationRules::default();
+        let mut rules = defaults.clone();
+        rules.stop_words.push("remastered".to_string());
 
-        // Unicode normalization for consistency
-        title.nfkd().collect::<String>().trim().to_string()
+        assert_eq!(
+            CompiledRules::new(&defaults).fingerprint,
+            CompiledRules::new(&NormalizationRules::default()).fingerprint
+        );
+        assert_ne!(
+            CompiledRules::new(&defaults).fingerprint,
+            CompiledRules::new(&rules).fingerprint
+        );
     }
 }
@@ -0,0 +1,101 @@
+//! Scrapes the saved pages of every configured site and checks the games
+//! extracted from them
+
+use gameharmony::config::ScraperConfig;
+use gameharmony::domain::numbers::Rank;
+use gameharmony::services::scraping::{Fixtures, ScrapingService, WebsiteGames};
+use std::path::{Path, PathBuf};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pages")
+}
+
+async fn scrape_all() -> Vec<WebsiteGames> {
+    let config =
+        ScraperConfig::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("scraper_config.json"))
+            .expect("scraper config");
+    ScrapingService::new(reqwest::Client::new())
+        .with_fixtures(Some(Fixtures::Replay(fixtures_dir())))
+        .scrape_all(&config.websites)
+        .await
+        .expect("scraping the fixtures")
+}
+
+fn games(scraped: &[WebsiteGames], pattern: &str) -> Vec<(String, Rank)> {
+    let website = scraped
+        .iter()
+        .find(|website| website.source.contains(pattern))
+        .unwrap_or_else(|| panic!("no list scraped from {}", pattern));
+    website
+        .games
+        .iter()
+        .map(|game| (game.name.clone(), game.rank))
+        .collect()
+}
+
+fn expected(games: &[(&str, Rank)]) -> Vec<(String, Rank)> {
+    games
+        .iter()
+        .map(|(name, rank)| (name.to_string(), *rank))
+        .collect()
+}
+
+#[tokio::test]
+async fn extracts_titles_and_ranks_of_every_site() {
+    let scraped = scrape_all().await;
+    assert_eq!(scraped.len(), 6);
+
+    let cases: &[(&str, &[(&str, Rank)])] = &[
+        (
+            "ign.com",
+            &[
+                ("The Legend of Zelda: Breath of the Wild", 1),
+                ("Portal 2", 2),
+                ("Portal", 3),
+            ],
+        ),
+        (
+            "rockpapershotgun.com",
+            &[("Disco Elysium", 1), ("Outer Wilds", 2), ("Hades", 3)],
+        ),
+        (
+            "eurogamer.net",
+            &[("Tetris", 1), ("Half-Life 2", 2), ("Half-Life", 3)],
+        ),
+        (
+            "pcgamer.com",
+            &[
+                ("disco elysium", 1),
+                ("hades", 2),
+                ("the witcher wild hunt", 3),
+            ],
+        ),
+        (
+            "best-ps5-games-playstation-5",
+            &[("Astro Bot", 1), ("Elden Ring", 2), ("Returnal", 3)],
+        ),
+        (
+            "best-video-games-2024",
+            &[("Balatro", 1), ("Animal Well", 2), ("Astro Bot", 3)],
+        ),
+    ];
+    for (pattern, want) in cases {
+        assert_eq!(games(&scraped, pattern), expected(want), "{}", pattern);
+    }
+}
+
+#[tokio::test]
+async fn anchors_point_at_the_entry_of_each_game() {
+    let scraped = scrape_all().await;
+    let ign = scraped
+        .iter()
+        .find(|website| website.source.contains("ign.com"))
+        .expect("IGN list");
+
+    let anchors: Vec<Option<&str>> = ign
+        .games
+        .iter()
+        .map(|game| game.anchor.as_deref())
+        .collect();
+    assert_eq!(anchors, [Some("entry-1"), Some("entry-2"), Some("entry-3")]);
+}